@@ -1,4 +1,5 @@
 use ast_sourcegen::{derive_ast_nodes, derive_token_casts};
+use simulatron_utils::charset::printable_char_to_u8;
 use std::convert::TryInto;
 use std::ops::Range;
 use std::str::FromStr;
@@ -25,6 +26,7 @@ derive_ast_nodes! {
     Label,
     Instruction,
     Operand,
+    IndexedAddress,
     ArrayLiteral,
     Literal,
 }
@@ -46,6 +48,7 @@ pub enum RegisterType {
     Half,
     Word,
     Float,
+    Double,
 }
 
 /// Array lengths may be either a literal, or inferred from the initialiser.
@@ -55,11 +58,25 @@ pub enum ArrayLength {
     Inferred,
 }
 
-/// An enum for Operands, which can be either Identifiers or Literals.
+/// An enum for Operands, which can be Identifiers, Literals, or indexed
+/// addresses.
 #[derive(Debug)]
 pub enum OperandValue {
     Ident(String),
     Lit(LiteralValue),
+    Indexed {
+        base: String,
+        offset: Option<IndexOffset>,
+    },
+}
+
+/// The offset part of an indexed address: either an Identifier or Literal to
+/// add or subtract, or an index register with an optional scale.
+#[derive(Debug)]
+pub struct IndexOffset {
+    pub value: Box<OperandValue>,
+    pub negated: bool,
+    pub scale: Option<i64>,
 }
 
 /// The value of a literal.
@@ -69,6 +86,13 @@ pub enum LiteralValue {
         value: u32,
         min_reg_type: RegisterType,
     },
+    /// A float literal, kept at both precisions until we know which
+    /// register type it is used with. Each is parsed directly from the
+    /// text, so neither is rounded twice.
+    Float {
+        single: u32,
+        double: u64,
+    },
     Sizeof {
         ident: String,
     },
@@ -304,6 +328,9 @@ impl Instruction {
 /// Operands have a value.
 impl Operand {
     pub fn value(&self) -> SaltResult<OperandValue> {
+        if let Some(indexed) = self.syntax.children().find_map(IndexedAddress::cast) {
+            return indexed.value();
+        }
         match self.syntax.children_with_tokens().find_map(identifier_cast) {
             Some(ident) => Ok(OperandValue::Ident(ident.0)),
             None => {
@@ -319,28 +346,71 @@ impl Operand {
     }
 }
 
+/// IndexedAddresses have a base register and an optional offset.
+impl IndexedAddress {
+    pub fn value(&self) -> SaltResult<OperandValue> {
+        let mut identifiers = self
+            .syntax
+            .children_with_tokens()
+            .filter_map(identifier_cast);
+        let base = identifiers.next().unwrap().0;
+
+        // An offset identifier or index register.
+        let value = if let Some((ident, _)) = identifiers.next() {
+            Some(OperandValue::Ident(ident))
+        } else if let Some(lit) = self.syntax.children().find_map(Literal::cast) {
+            Some(OperandValue::Lit(lit.value()?))
+        } else {
+            None
+        };
+
+        let offset = match value {
+            Some(value) => {
+                let scale = match self
+                    .syntax
+                    .children_with_tokens()
+                    .find_map(int_literal_cast)
+                {
+                    Some((text, span)) => Some(int_literal_value(&text, span)?),
+                    None => None,
+                };
+                Some(IndexOffset {
+                    value: Box::new(value),
+                    negated: node_contains_kind(&self.syntax, SyntaxKind::Minus),
+                    scale,
+                })
+            }
+            None => None,
+        };
+
+        Ok(OperandValue::Indexed { base, offset })
+    }
+}
+
 /// ArrayLiterals are a vector of literal values and a vector of dimensions.
 impl ArrayLiteral {
     pub fn values(&self) -> SaltResult<(Vec<LiteralValue>, Vec<usize>)> {
         // Just a single literal.
         if let Some(lit) = self.syntax.children().find_map(Literal::cast) {
             Ok((vec![lit.value()?], vec![1]))
-        } else if let Some((text, _)) = self
+        } else if let Some((text, span)) = self
             .syntax
             .children_with_tokens()
             .find_map(string_literal_cast)
         {
-            // A string literal. Convert character by character.
-            let mut values = Vec::with_capacity(text.len() - 2);
+            // A string literal. Convert character by character, remembering
+            // that some characters may take up more than one byte of text.
+            let chars: Vec<char> = text.chars().collect();
+            let mut values = Vec::with_capacity(chars.len() - 2);
             // Split into slices that look like character literals, so we
             // can use the same conversion function.
             let mut i = 1;
-            while i < text.len() - 1 {
+            while i < chars.len() - 1 {
                 // Include the character before to take the place of the opening
                 // single quote, and include the character after in case this
                 // is an escape sequence.
-                let char_slice = &text[(i - 1)..=(i + 1)];
-                let (value, escape) = char_literal_value(char_slice);
+                let char_slice: String = chars[(i - 1)..=(i + 1)].iter().collect();
+                let (value, escape) = char_literal_value(&char_slice, span.clone())?;
                 values.push(LiteralValue::Lit {
                     value,
                     min_reg_type: RegisterType::Byte,
@@ -416,20 +486,18 @@ impl Literal {
             .children_with_tokens()
             .find_map(float_literal_cast)
         {
-            // Float literal: parse, get bit representation as u32 and
-            // size is always Word.
-            let value = f32::from_str(&text).unwrap().to_bits();
-            Ok(LiteralValue::Lit {
-                value,
-                min_reg_type: RegisterType::Float,
-            })
-        } else if let Some((text, _)) = self
+            // Float literal: parse at both precisions and keep their bit
+            // representations.
+            let single = f32::from_str(&text).unwrap().to_bits();
+            let double = f64::from_str(&text).unwrap().to_bits();
+            Ok(LiteralValue::Float { single, double })
+        } else if let Some((text, span)) = self
             .syntax
             .children_with_tokens()
             .find_map(char_literal_cast)
         {
             // Character literal: parse, and size is always Byte.
-            let (value, _) = char_literal_value(&text);
+            let (value, _) = char_literal_value(&text, span)?;
             Ok(LiteralValue::Lit {
                 value,
                 min_reg_type: RegisterType::Byte,
@@ -531,7 +599,7 @@ fn int_literal_value(text: &str, span: Range<usize>) -> SaltResult<i64> {
 /// Parse a string to get the value of a character. Useful for both CharLiterals
 /// and slices of StringLiterals. Also returns whether the character was an
 /// escape sequence.
-fn char_literal_value(text: &str) -> (u32, bool) {
+fn char_literal_value(text: &str, span: Range<usize>) -> SaltResult<(u32, bool)> {
     // The first character is a quote, and is ignored.
     // The second character is either the literal character itself or the start
     // of an escape sequence. Check which.
@@ -547,17 +615,15 @@ fn char_literal_value(text: &str) -> (u32, bool) {
                 '\\' => 92,
                 _ => unreachable!(),
             };
-            (value, true)
-        }
-        // Special cases where Simulatron instruction set diverges from ASCII.
-        '£' => (31, false),
-        '¬' => (127, false),
-        c => {
-            // ASCII conversion.
-            let value: u32 = c.into();
-            assert!((32..=126).contains(&value));
-            (value, false)
+            Ok((value, true))
         }
+        c => match printable_char_to_u8(c) {
+            Some(value) => Ok((value.into(), false)),
+            None => Err(SaltError {
+                span,
+                message: format!("'{}' is not in the Simulatron character set.", c).into(),
+            }),
+        },
     }
 }
 
@@ -611,7 +677,7 @@ mod tests {
     fn test_consts() {
         let ast = setup("examples/consts-only.simasm");
         let consts = ast.const_decls();
-        assert_eq!(consts.len(), 9);
+        assert_eq!(consts.len(), 10);
         let values: Vec<LiteralValue> = consts
             .iter()
             .map(ConstDecl::value)
@@ -647,16 +713,16 @@ mod tests {
         );
         assert_eq!(
             values[4],
-            LiteralValue::Lit {
-                value: f32::to_bits(1.0),
-                min_reg_type: RegisterType::Float
+            LiteralValue::Float {
+                single: f32::to_bits(1.0),
+                double: f64::to_bits(1.0)
             }
         );
         assert_eq!(
             values[5],
-            LiteralValue::Lit {
-                value: f32::to_bits(42e-12),
-                min_reg_type: RegisterType::Float
+            LiteralValue::Float {
+                single: f32::to_bits(42e-12),
+                double: f64::to_bits(42e-12)
             }
         );
         assert_eq!(
@@ -668,9 +734,9 @@ mod tests {
         );
         assert_eq!(
             values[7],
-            LiteralValue::Lit {
-                value: f32::to_bits(-9.9432),
-                min_reg_type: RegisterType::Float
+            LiteralValue::Float {
+                single: f32::to_bits(-9.9432),
+                double: f64::to_bits(-9.9432)
             }
         );
         assert_eq!(
@@ -680,6 +746,16 @@ mod tests {
                 min_reg_type: RegisterType::Half
             }
         );
+        // Rounding to a double first and then to a single would give 1.0.
+        assert_eq!(
+            values[9],
+            LiteralValue::Float {
+                single: 0x3F800001,
+                double: f64::from_str("1.0000000596046447753906251")
+                    .unwrap()
+                    .to_bits()
+            }
+        );
     }
 
     #[test]
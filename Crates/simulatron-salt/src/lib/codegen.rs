@@ -176,13 +176,15 @@ impl LabelLocation {
     }
 }
 
-/// Label symbol table entry.
+/// Label symbol table entry. Relative references are PC-relative offsets
+/// within the code, which are resolved here rather than by the linker.
 #[derive(Debug)]
 struct Label {
     public: bool,
     location: LabelLocation,
     span: Range<usize>,
     references: Vec<u32>,
+    relative_references: Vec<u32>,
 }
 
 /// External symbol symbol table entry.
@@ -200,6 +202,7 @@ enum RegRef {
     Byte,
     Half,
     Word,
+    /// Either a float or a double register.
     Float,
 }
 
@@ -209,7 +212,7 @@ fn register_type_matches(reg: RegisterType, ref_: RegRef) -> bool {
         RegisterType::Byte => ref_ == RegRef::Any || ref_ == RegRef::Int || ref_ == RegRef::Byte,
         RegisterType::Half => ref_ == RegRef::Any || ref_ == RegRef::Int || ref_ == RegRef::Half,
         RegisterType::Word => ref_ == RegRef::Any || ref_ == RegRef::Int || ref_ == RegRef::Word,
-        RegisterType::Float => ref_ == RegRef::Any || ref_ == RegRef::Float,
+        RegisterType::Float | RegisterType::Double => ref_ == RegRef::Any || ref_ == RegRef::Float,
     }
 }
 
@@ -261,7 +264,7 @@ impl CodeGenerator {
     /// Create a new CodeGenerator with the given AST and list of extra constants.
     pub fn new(
         program: ast::Program,
-        extra_consts: &Vec<ast::ConstDecl>,
+        extra_consts: &[ast::ConstDecl],
     ) -> Result<Self, CodegenFailure> {
         // Extract program components.
         let mut consts = program.const_decls();
@@ -348,6 +351,8 @@ impl CodeGenerator {
             ok_or_continue!(self, self.codegen_instruction(instruction));
         }
         self.instructions = Some(instructions);
+        // Now that all labels are resolved, fill in relative references.
+        self.resolve_relative_references();
 
         // Generate warnings for any unused private symbols.
         let mut unused_warnings = Vec::new();
@@ -355,7 +360,10 @@ impl CodeGenerator {
             let (used, span) = match entry {
                 SymbolTableEntry::C(const_) => (const_.used, const_.span.clone()),
                 SymbolTableEntry::D(data) => (!data.references.is_empty(), data.span.clone()),
-                SymbolTableEntry::L(label) => (!label.references.is_empty(), label.span.clone()),
+                SymbolTableEntry::L(label) => (
+                    !label.references.is_empty() || !label.relative_references.is_empty(),
+                    label.span.clone(),
+                ),
                 SymbolTableEntry::E(_) => unreachable!(),
             };
             if !used {
@@ -805,6 +813,7 @@ impl CodeGenerator {
                     location: LabelLocation::Reference(instruction),
                     span: span.clone(),
                     references: Vec::with_capacity(AVG_SYMBOL_REFERENCES),
+                    relative_references: Vec::with_capacity(AVG_SYMBOL_REFERENCES),
                 }),
             );
             if existing.is_some() {
@@ -830,6 +839,26 @@ impl CodeGenerator {
         }
     }
 
+    /// Fill in the PC-relative offsets for all relative label references.
+    /// Offsets are relative to the end of the 4-byte offset itself, which is
+    /// always the end of the instruction.
+    fn resolve_relative_references(&mut self) {
+        for (name, label) in self.symbol_table.iter_labels() {
+            let target = label.location.unwrap_offset();
+            for &reference in label.relative_references.iter() {
+                let offset = target.wrapping_sub(reference + 4);
+                trace!(
+                    "Resolved relative reference to {} at {:#X} to {:#X}",
+                    name,
+                    reference,
+                    offset
+                );
+                let reference = usize::try_from(reference).unwrap();
+                self.code[reference..reference + 4].copy_from_slice(&offset.to_be_bytes());
+            }
+        }
+    }
+
     /// Perform codegen for a single instruction.
     fn codegen_instruction(&mut self, instruction: &ast::Instruction) -> SaltResult<()> {
         let span: Range<usize> = instruction.syntax().text_range().into();
@@ -849,10 +878,33 @@ impl CodeGenerator {
             "timer" => def!("timer", i_w, (0x02, 0x03)),
             "usermode" => def!("usermode", i_none, 0x04),
             "ireturn" => def!("ireturn", i_none, 0x05),
-            "load" => def!("load", i_BHWF_a, (0x06, 0x07)),
-            "store" => def!("store", i_a_BHWF, (0x08, 0x09)),
+            "load" => def!("load", i_BHWF_x, (0x06, 0x07, 0x74, 0x75)),
+            "store" => def!("store", i_x_BHWF, (0x08, 0x09, 0x76, 0x77)),
             "copy" => def!("copy", i_BHWF_bhwf, (0x0A, 0x0B)),
             "swap" => def!("swap", i_BHWF_a, (0x0C, 0x0D)),
+            "cmpxchg" => def!("cmpxchg", i_BHW_BHW_a, (0x70, 0x71)),
+            "fetchadd" => def!("fetchadd", i_BHW_a, (0x72, 0x73)),
+            "loadpc" => def!("loadpc", i_W_r, 0x84),
+            "setequal" => def!("setequal", i_BHW, 0x85),
+            "setnotequal" => def!("setnotequal", i_BHW, 0x86),
+            "ssetgreater" => def!("ssetgreater", i_BHW, 0x87),
+            "ssetgreatereq" => def!("ssetgreatereq", i_BHW, 0x88),
+            "usetgreater" => def!("usetgreater", i_BHW, 0x89),
+            "usetgreatereq" => def!("usetgreatereq", i_BHW, 0x8A),
+            "ssetlesser" => def!("ssetlesser", i_BHW, 0x8B),
+            "ssetlessereq" => def!("ssetlessereq", i_BHW, 0x8C),
+            "usetlesser" => def!("usetlesser", i_BHW, 0x8D),
+            "usetlessereq" => def!("usetlessereq", i_BHW, 0x8E),
+            "cmovequal" => def!("cmovequal", i_BHWF_BHWF, 0x8F),
+            "cmovnotequal" => def!("cmovnotequal", i_BHWF_BHWF, 0x90),
+            "scmovgreater" => def!("scmovgreater", i_BHWF_BHWF, 0x91),
+            "scmovgreatereq" => def!("scmovgreatereq", i_BHWF_BHWF, 0x92),
+            "ucmovgreater" => def!("ucmovgreater", i_BHWF_BHWF, 0x93),
+            "ucmovgreatereq" => def!("ucmovgreatereq", i_BHWF_BHWF, 0x94),
+            "scmovlesser" => def!("scmovlesser", i_BHWF_BHWF, 0x95),
+            "scmovlessereq" => def!("scmovlessereq", i_BHWF_BHWF, 0x96),
+            "ucmovlesser" => def!("ucmovlesser", i_BHWF_BHWF, 0x97),
+            "ucmovlessereq" => def!("ucmovlessereq", i_BHWF_BHWF, 0x98),
             "push" => def!("push", i_BHWF, 0x0E),
             "pop" => def!("pop", i_BHWF, 0x0F),
             "blockcopy" => def!(
@@ -875,6 +927,17 @@ impl CodeGenerator {
             "udiv" => def!("udiv", i_BHW_bhw, (0x2D, 0x2E)),
             "srem" => def!("srem", i_BHWF_bhwf, (0x2F, 0x30)),
             "urem" => def!("urem", i_BHW_bhw, (0x31, 0x32)),
+            "umultwide" => def!("umultwide", i_W_W_w, (0xA3, 0xA4)),
+            "smultwide" => def!("smultwide", i_W_W_w, (0xA5, 0xA6)),
+            "udivwide" => def!("udivwide", i_W_W_w, (0xA7, 0xA8)),
+            "sdivwide" => def!("sdivwide", i_W_W_w, (0xA9, 0xAA)),
+            "sqrt" => def!("sqrt", i_F, 0xAB),
+            "abs" => def!("abs", i_F, 0xAC),
+            "min" => def!("min", i_F_f, (0xAD, 0xAE)),
+            "max" => def!("max", i_F_f, (0xAF, 0xB0)),
+            "floor" => def!("floor", i_F, 0xB1),
+            "ceil" => def!("ceil", i_F, 0xB2),
+            "round" => def!("round", i_F, 0xB3),
             "not" => def!("not", i_BHW, 0x33),
             "and" => def!("and", i_BHW_bhw, (0x34, 0x35)),
             "or" => def!("or", i_BHW_bhw, (0x36, 0x37)),
@@ -886,28 +949,37 @@ impl CodeGenerator {
             "rrot" => def!("rrot", i_BHW_b, (0x42, 0x43)),
             "lrotcarry" => def!("lrotcarry", i_BHW_b, (0x44, 0x45)),
             "rrotcarry" => def!("rrotcarry", i_BHW_b, (0x46, 0x47)),
-            "jump" => def!("jump", i_a, (0x48, 0x49)),
+            "popcount" => def!("popcount", i_BHW, 0x99),
+            "lzcount" => def!("lzcount", i_BHW, 0x9A),
+            "tzcount" => def!("tzcount", i_BHW, 0x9B),
+            "bittest" => def!("bittest", i_BHW_b, (0x9C, 0x9D)),
+            "bitset" => def!("bitset", i_BHW_b, (0x9E, 0x9F)),
+            "bitclear" => def!("bitclear", i_BHW_b, (0xA0, 0xA1)),
+            "byteswap" => def!("byteswap", i_BHW, 0xA2),
+            "jump" => def!("jump", i_r, (0x48, 0x49, 0x78)),
             "compare" => def!("compare", i_BHWF_bhwf, (0x4A, 0x4B)),
             "blockcmp" => def!(
                 "blockcmp",
                 i_w_a_a,
                 (0x4C, 0x4D, 0x4E, 0x4F, 0x50, 0x51, 0x52, 0x53)
             ),
-            "jequal" => def!("jequal", i_a, (0x54, 0x55)),
-            "jnotequal" => def!("jnotequal", i_a, (0x56, 0x57)),
-            "sjgreater" => def!("sjgreater", i_a, (0x58, 0x59)),
-            "sjgreatereq" => def!("sjgreatereq", i_a, (0x5A, 0x5B)),
-            "ujgreater" => def!("ujgreater", i_a, (0x5C, 0x5D)),
-            "ujgreatereq" => def!("ujgreatereq", i_a, (0x5E, 0x5F)),
-            "sjlesser" => def!("sjlesser", i_a, (0x60, 0x61)),
-            "sjlessereq" => def!("sjlessereq", i_a, (0x62, 0x63)),
-            "ujlesser" => def!("ujlesser", i_a, (0x64, 0x65)),
-            "ujlessereq" => def!("ujlessereq", i_a, (0x66, 0x67)),
-            "call" => def!("call", i_a, (0x68, 0x69)),
+            "jequal" => def!("jequal", i_r, (0x54, 0x55, 0x79)),
+            "jnotequal" => def!("jnotequal", i_r, (0x56, 0x57, 0x7A)),
+            "sjgreater" => def!("sjgreater", i_r, (0x58, 0x59, 0x7B)),
+            "sjgreatereq" => def!("sjgreatereq", i_r, (0x5A, 0x5B, 0x7C)),
+            "ujgreater" => def!("ujgreater", i_r, (0x5C, 0x5D, 0x7D)),
+            "ujgreatereq" => def!("ujgreatereq", i_r, (0x5E, 0x5F, 0x7E)),
+            "sjlesser" => def!("sjlesser", i_r, (0x60, 0x61, 0x7F)),
+            "sjlessereq" => def!("sjlessereq", i_r, (0x62, 0x63, 0x80)),
+            "ujlesser" => def!("ujlesser", i_r, (0x64, 0x65, 0x81)),
+            "ujlessereq" => def!("ujlessereq", i_r, (0x66, 0x67, 0x82)),
+            "call" => def!("call", i_r, (0x68, 0x69, 0x83)),
             "return" => def!("return", i_none, 0x6A),
-            "syscall" => def!("syscall", i_none, 0x6B),
+            "syscall" => def!("syscall", i_opt_w, (0x6B, 0xB6, 0xB7)),
             "sconvert" => def!("sconvert", i_WF_WF, 0x6C),
             "uconvert" => def!("uconvert", i_WF_WF, 0x6D),
+            "ipi" => def!("ipi", i_w, (0x6E, 0x6F)),
+            "raise" => def!("raise", i_w, (0xB4, 0xB5)),
             _ => Err(SaltError {
                 span: op_span,
                 message: "Unrecognised opcode.".into(),
@@ -929,54 +1001,68 @@ impl CodeGenerator {
         operand: &ast::Operand,
     ) -> SaltResult<(ResolvedOperand, Range<usize>)> {
         let span: Range<usize> = operand.syntax().text_range().into();
+        let resolved = self.resolve_operand_value(operand.value()?, span.clone())?;
+        Ok((resolved, span))
+    }
 
+    /// Resolve the value of an operand with the given span, as described for
+    /// `resolve_operand`. Indexed addresses are rejected, as they are only
+    /// valid where handled by `indexed_address`.
+    fn resolve_operand_value(
+        &mut self,
+        value: OperandValue,
+        span: Range<usize>,
+    ) -> SaltResult<ResolvedOperand> {
         // Directly resolve a literal, or extract an identifier.
-        let ident = match operand.value()? {
+        let ident = match value {
             OperandValue::Ident(ident) => ident,
             OperandValue::Lit(literal) => {
                 trace!("Operand resolved to {:?}", literal);
-                return Ok((ResolvedOperand::Literal(literal), span));
+                return Ok(ResolvedOperand::Literal(literal));
+            }
+            OperandValue::Indexed { .. } => {
+                return Err(SaltError {
+                    span,
+                    message: "Indexed addresses can only be used with LOAD and STORE.".into(),
+                });
             }
         };
 
         // Try and resolve as a register reference.
         if let Some((reg_ref, reg_type)) = get_reg_ref(&ident) {
             trace!("Operand resolved to register reference {}", ident);
-            return Ok((ResolvedOperand::RegRef(reg_ref, reg_type), span));
+            return Ok(ResolvedOperand::RegRef(reg_ref, reg_type));
         }
 
         // Try and resolve as symbol.
         if let Some(entry) = self.symbol_table.table.get_mut(&ident) {
-            return Ok((
-                match entry {
-                    SymbolTableEntry::C(constant) => {
-                        trace!("Operand resolved to constant {}", ident);
-                        constant.used = true;
-                        ResolvedOperand::Literal(constant.value.clone())
-                    }
-                    SymbolTableEntry::D(data) => {
-                        trace!("Operand resolved to static data {}", ident);
-                        data.references.push(self.code.len().try_into().unwrap());
-                        self.code.write_be_u32(0).unwrap();
-                        ResolvedOperand::SymbolReference
-                    }
-                    SymbolTableEntry::L(label) => {
-                        trace!("Operand resolved to label {}", ident);
-                        label.references.push(self.code.len().try_into().unwrap());
-                        self.code.write_be_u32(0).unwrap();
-                        ResolvedOperand::SymbolReference
-                    }
-                    SymbolTableEntry::E(external) => {
-                        trace!("Operand resolved to external symbol {}", ident);
-                        external
-                            .references
-                            .push(self.code.len().try_into().unwrap());
-                        self.code.write_be_u32(0).unwrap();
-                        ResolvedOperand::SymbolReference
-                    }
-                },
-                span,
-            ));
+            return Ok(match entry {
+                SymbolTableEntry::C(constant) => {
+                    trace!("Operand resolved to constant {}", ident);
+                    constant.used = true;
+                    ResolvedOperand::Literal(constant.value.clone())
+                }
+                SymbolTableEntry::D(data) => {
+                    trace!("Operand resolved to static data {}", ident);
+                    data.references.push(self.code.len().try_into().unwrap());
+                    self.code.write_be_u32(0).unwrap();
+                    ResolvedOperand::SymbolReference
+                }
+                SymbolTableEntry::L(label) => {
+                    trace!("Operand resolved to label {}", ident);
+                    label.references.push(self.code.len().try_into().unwrap());
+                    self.code.write_be_u32(0).unwrap();
+                    ResolvedOperand::SymbolReference
+                }
+                SymbolTableEntry::E(external) => {
+                    trace!("Operand resolved to external symbol {}", ident);
+                    external
+                        .references
+                        .push(self.code.len().try_into().unwrap());
+                    self.code.write_be_u32(0).unwrap();
+                    ResolvedOperand::SymbolReference
+                }
+            });
         }
 
         // Unresolved: create a new external symbol.
@@ -998,7 +1084,125 @@ impl CodeGenerator {
             .table
             .insert(ident, SymbolTableEntry::E(external));
         self.code.write_be_u32(0).unwrap();
-        Ok((ResolvedOperand::SymbolReference, span))
+        Ok(ResolvedOperand::SymbolReference)
+    }
+
+    /// If the operand is a label declared in this file, add a relative
+    /// reference to it and push a zero placeholder to the code, returning true.
+    /// Otherwise, do nothing and return false.
+    ///
+    /// This is called from the i_*r* macros in `instruction_macros`.
+    fn relative_label(&mut self, operand: &ast::Operand) -> SaltResult<bool> {
+        if let OperandValue::Ident(ident) = operand.value()? {
+            if get_reg_ref(&ident).is_none() {
+                if let Some(SymbolTableEntry::L(label)) = self.symbol_table.table.get_mut(&ident) {
+                    trace!("Operand resolved to relative label {}", ident);
+                    label
+                        .relative_references
+                        .push(self.code.len().try_into().unwrap());
+                    self.code.write_be_u32(0).unwrap();
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    /// Generate code for an indexed address operand. The base register is
+    /// pushed, followed by either a word offset or an index register and scale.
+    /// The opcode at `opcode_pos` is set to `opcodes.1` for a lone base
+    /// register, `opcodes.2` for a base plus offset, or `opcodes.3` for a base
+    /// plus scaled index.
+    ///
+    /// This is called from the `indexed_address` macro in `instruction_macros`.
+    fn indexed_address(
+        &mut self,
+        base: String,
+        offset: Option<ast::IndexOffset>,
+        opcodes: (u8, u8, u8, u8),
+        opcode_pos: usize,
+        span: Range<usize>,
+    ) -> SaltResult<()> {
+        // Base: must be a word register.
+        match get_reg_ref(&base) {
+            Some((reg_ref, RegisterType::Word)) => self.code.push(reg_ref),
+            _ => {
+                return Err(SaltError {
+                    span,
+                    message: "Expected an address (word) register reference \
+                              as the base."
+                        .into(),
+                })
+            }
+        }
+
+        let offset = match offset {
+            Some(offset) => offset,
+            None => {
+                self.code[opcode_pos] = opcodes.1;
+                return Ok(());
+            }
+        };
+
+        // Offset: an index register, literal, or symbol.
+        match self.resolve_operand_value(*offset.value, span.clone())? {
+            ResolvedOperand::RegRef(reg_ref, reg_type) => {
+                if !register_type_matches(reg_type, RegRef::Word) {
+                    return Err(SaltError {
+                        span,
+                        message: "Expected an index (word) register reference.".into(),
+                    });
+                }
+                if offset.negated {
+                    return Err(SaltError {
+                        span,
+                        message: "Index registers can't be subtracted.".into(),
+                    });
+                }
+                let scale = match offset.scale.unwrap_or(1) {
+                    scale @ (1 | 2 | 4 | 8) => scale as u8,
+                    _ => {
+                        return Err(SaltError {
+                            span,
+                            message: "Scale must be 1, 2, 4, or 8.".into(),
+                        })
+                    }
+                };
+                self.code[opcode_pos] = opcodes.3;
+                self.code.push(reg_ref);
+                self.code.push(scale);
+            }
+            ResolvedOperand::Literal(literal) => {
+                if offset.scale.is_some() {
+                    return Err(SaltError {
+                        span,
+                        message: "Only index registers can be scaled.".into(),
+                    });
+                }
+                self.code[opcode_pos] = opcodes.2;
+                // Errors resolving the literal have already been reported.
+                let value = self
+                    .value_as_word(&literal, span)
+                    .map_or(0, |bytes| u32::from_be_bytes(bytes.try_into().unwrap()));
+                let value = if offset.negated {
+                    value.wrapping_neg()
+                } else {
+                    value
+                };
+                self.code.write_be_u32(value).unwrap();
+            }
+            ResolvedOperand::SymbolReference => {
+                if offset.negated || offset.scale.is_some() {
+                    return Err(SaltError {
+                        span,
+                        message: "Symbol addresses can only be added as an offset.".into(),
+                    });
+                }
+                self.code[opcode_pos] = opcodes.2;
+            }
+        }
+
+        Ok(())
     }
 
     fn push_value_as_reg_type(
@@ -1012,6 +1216,7 @@ impl CodeGenerator {
             RegisterType::Half => self.value_as_half(val, span.clone()),
             RegisterType::Word => self.value_as_word(val, span.clone()),
             RegisterType::Float => self.value_as_float(val, span.clone()),
+            RegisterType::Double => self.value_as_double(val, span.clone()),
         }
         .map(|mut bytes| self.code.append(&mut bytes))
         .ok_or_else(|| SaltError {
@@ -1063,6 +1268,21 @@ impl CodeGenerator {
         Some(value.to_be_bytes().to_vec())
     }
 
+    fn value_as_double(&mut self, val: &LiteralValue, span: Range<usize>) -> Option<Vec<u8>> {
+        if let LiteralValue::Float { double, .. } = *val {
+            return Some(double.to_be_bytes().to_vec());
+        }
+
+        let (value, _) = self.resolve_literal(val, span.clone())?;
+        self.warning(SaltError {
+            span,
+            message: "Integer literal being used as a double; it will be taken as raw bits, not converted."
+                .into(),
+        });
+
+        Some(u64::from(value).to_be_bytes().to_vec())
+    }
+
     fn value_as_word_or_float(
         &mut self,
         val: &LiteralValue,
@@ -1084,6 +1304,7 @@ impl CodeGenerator {
                 value,
                 min_reg_type,
             } => Some((value, min_reg_type)),
+            LiteralValue::Float { single, .. } => Some((single, RegisterType::Float)),
             LiteralValue::Sizeof { ref ident } => {
                 if let Some(SymbolTableEntry::D(data)) = self.symbol_table.table.get(ident) {
                     let size: u32 = data.size.try_into().unwrap();
@@ -1146,6 +1367,27 @@ fn get_reg_ref(reg_ref: &str) -> Option<(u8, RegisterType)> {
         "pdpr" => (0x23, Word),
         "imr" => (0x24, Half),
         "pfsr" => (0x25, Word),
+        "cid" => (0x26, Word),
+        "fpsr" => (0x27, Half),
+        "d0" => (0x28, Double),
+        "d1" => (0x29, Double),
+        "d2" => (0x2A, Double),
+        "d3" => (0x2B, Double),
+        "d4" => (0x2C, Double),
+        "d5" => (0x2D, Double),
+        "d6" => (0x2E, Double),
+        "d7" => (0x2F, Double),
+        "ivbr" => (0x30, Word),
+        "ipr" => (0x31, Word),
+        "ipnd" => (0x32, Half),
+        "scnr" => (0x33, Word),
+        "espr" => (0x34, Word),
+        "da0" => (0x35, Word),
+        "da1" => (0x36, Word),
+        "da2" => (0x37, Word),
+        "da3" => (0x38, Word),
+        "dcr" => (0x39, Word),
+        "dsr" => (0x3A, Half),
         _ => return None,
     })
 }
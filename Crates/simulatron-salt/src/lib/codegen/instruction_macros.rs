@@ -52,7 +52,7 @@ macro_rules! reg_ref_any {
 }
 
 /// Shortcut for an operand that must be an address.
-/// Only applicable to two-operand instructions.
+/// Only applicable when the address is the last operand.
 macro_rules! address {
     ($self:ident, $resolved:expr, $opcodes:expr, $opcode_pos:expr) => {{
         match $resolved.0 {
@@ -80,6 +80,23 @@ macro_rules! address {
     }};
 }
 
+/// Shortcut for an operand that must be an address, which may also be an
+/// indexed address. See `CodeGenerator::indexed_address` for the opcodes.
+macro_rules! indexed_address {
+    ($self:ident, $operand:expr, $opcodes:expr, $opcode_pos:expr) => {{
+        match $operand.value()? {
+            OperandValue::Indexed { base, offset } => {
+                let span = $operand.syntax().text_range().into();
+                $self.indexed_address(base, offset, $opcodes, $opcode_pos, span)?;
+            }
+            _ => {
+                let resolved = $self.resolve_operand(&$operand)?;
+                address!($self, resolved, $opcodes, $opcode_pos);
+            }
+        }
+    }};
+}
+
 /// An instruction with no operands.
 macro_rules! i_none {
     ($self:ident, $opcode:expr, $operands:expr, $span:expr) => {{
@@ -120,6 +137,25 @@ macro_rules! i_w {
     }};
 }
 
+/// An instruction with an optional ..w. operand. Without it, the
+/// no-operand opcode `$opcodes.0` is used.
+macro_rules! i_opt_w {
+    ($self:ident, $opcodes:expr, $operands:expr, $span:expr) => {{
+        if $operands.len() > 1 {
+            return Err(SaltError {
+                span: $span,
+                message: format!("Expected 0 or 1 operands, but found {}.", $operands.len()).into(),
+            });
+        }
+        if $operands.is_empty() {
+            $self.code.push($opcodes.0);
+            Ok(())
+        } else {
+            i_w!($self, ($opcodes.1, $opcodes.2), $operands, $span)
+        }
+    }};
+}
+
 /// An instruction with operands BHWF ..a.
 macro_rules! i_BHWF_a {
     ($self:ident, $opcodes:expr, $operands:expr, $span:expr) => {{
@@ -141,8 +177,8 @@ macro_rules! i_BHWF_a {
     }};
 }
 
-/// An instruction with operands ..a. BHWF
-macro_rules! i_a_BHWF {
+/// An instruction with operands BHWF ..x.
+macro_rules! i_BHWF_x {
     ($self:ident, $opcodes:expr, $operands:expr, $span:expr) => {{
         num_operands!(2, $operands, $span);
 
@@ -150,9 +186,28 @@ macro_rules! i_a_BHWF {
         let opcode_pos = $self.code.len();
         $self.code.push(0);
 
-        // First operand: address.
+        // First operand: RegRefAny.
         let resolved = $self.resolve_operand(&$operands[0])?;
-        address!($self, resolved, $opcodes, opcode_pos);
+        reg_ref_any!($self, resolved);
+
+        // Second operand: address or indexed address.
+        indexed_address!($self, $operands[1], $opcodes, opcode_pos);
+
+        Ok(())
+    }};
+}
+
+/// An instruction with operands ..x. BHWF
+macro_rules! i_x_BHWF {
+    ($self:ident, $opcodes:expr, $operands:expr, $span:expr) => {{
+        num_operands!(2, $operands, $span);
+
+        // Push placeholder opcode.
+        let opcode_pos = $self.code.len();
+        $self.code.push(0);
+
+        // First operand: address or indexed address.
+        indexed_address!($self, $operands[0], $opcodes, opcode_pos);
 
         // Second operand: RegRefAny.
         let resolved = $self.resolve_operand(&$operands[1])?;
@@ -197,7 +252,7 @@ macro_rules! i_BHWF_bhwf {
             ResolvedOperand::SymbolReference => {
                 if reg_type == RegisterType::Word {
                     $self.code[opcode_pos] = $opcodes.0;
-                } else if reg_type == RegisterType::Float {
+                } else if let RegisterType::Float | RegisterType::Double = reg_type {
                     return Err(SaltError {
                         span: op_span,
                         message: "Symbol references resolve to addresses, \
@@ -230,6 +285,38 @@ macro_rules! i_BHWF {
     }};
 }
 
+/// An instruction with operands BHWF BHWF, which must be the same type.
+macro_rules! i_BHWF_BHWF {
+    ($self:ident, $opcode:expr, $operands:expr, $span:expr) => {{
+        num_operands!(2, $operands, $span);
+        $self.code.push($opcode);
+
+        // First operand: RegRefAny.
+        let resolved = $self.resolve_operand(&$operands[0])?;
+        let reg_type = reg_ref_any!($self, resolved);
+
+        // Second operand: register of the same type.
+        let (resolved, op_span) = $self.resolve_operand(&$operands[1])?;
+        match resolved {
+            ResolvedOperand::Literal(_) => no_literals!(op_span),
+            ResolvedOperand::RegRef(reg_ref, reg_type_2) => {
+                if reg_type != reg_type_2 {
+                    return Err(SaltError {
+                        span: op_span,
+                        message: "Cannot operate between differently-sized \
+                                  registers."
+                            .into(),
+                    });
+                }
+                $self.code.push(reg_ref);
+            }
+            ResolvedOperand::SymbolReference => no_symbols!(op_span),
+        }
+
+        Ok(())
+    }};
+}
+
 /// An instruction with operands ..w. ..a. ..a.
 macro_rules! i_w_a_a {
     ($self:ident, $opcodes:expr, $operands:expr, $span:expr) => {{
@@ -416,24 +503,25 @@ macro_rules! i_w_a_b {
 }
 
 /// An instruction with operands ..WF ..WF
+/// The two registers must be of different types, and at least one of them
+/// must be a float or double.
 macro_rules! i_WF_WF {
     ($self:ident, $opcode:expr, $operands:expr, $span:expr) => {{
         num_operands!(2, $operands, $span);
 
         // Push opcode.
         $self.code.push($opcode);
-        let float_first: bool;
+        let first_type: RegisterType;
 
-        // First operand: word or float register ref.
+        // First operand: word, float, or double register ref.
         let (resolved, op_span) = $self.resolve_operand(&$operands[0])?;
         match resolved {
             ResolvedOperand::Literal(_) => no_literals!(op_span),
             ResolvedOperand::RegRef(reg_ref, reg_type) => {
-                if register_type_matches(reg_type, RegRef::Word) {
-                    float_first = false;
-                    $self.code.push(reg_ref);
-                } else if register_type_matches(reg_type, RegRef::Float) {
-                    float_first = true;
+                if register_type_matches(reg_type, RegRef::Word)
+                    || register_type_matches(reg_type, RegRef::Float)
+                {
+                    first_type = reg_type;
                     $self.code.push(reg_ref);
                 } else {
                     return Err(SaltError {
@@ -445,30 +533,32 @@ macro_rules! i_WF_WF {
             ResolvedOperand::SymbolReference => no_symbols!(op_span),
         }
 
-        // Second operand: register ref of opposite type to first.
+        // Second operand: register ref of a different type to the first.
         let (resolved, op_span) = $self.resolve_operand(&$operands[1])?;
         match resolved {
             ResolvedOperand::Literal(_) => no_literals!(op_span),
             ResolvedOperand::RegRef(reg_ref, reg_type) => {
-                if float_first {
-                    if register_type_matches(reg_type, RegRef::Word) {
-                        $self.code.push(reg_ref);
-                    } else {
-                        return Err(SaltError {
-                            span: op_span,
-                            message: "Expected a word register reference.".into(),
-                        });
-                    }
-                } else {
-                    if register_type_matches(reg_type, RegRef::Float) {
-                        $self.code.push(reg_ref);
-                    } else {
-                        return Err(SaltError {
-                            span: op_span,
-                            message: "Expected a float register reference.".into(),
-                        });
-                    }
+                let (okay, message) = match first_type {
+                    RegisterType::Float => (
+                        reg_type == RegisterType::Word || reg_type == RegisterType::Double,
+                        "Expected a word or double register reference.",
+                    ),
+                    RegisterType::Double => (
+                        reg_type == RegisterType::Word || reg_type == RegisterType::Float,
+                        "Expected a word or float register reference.",
+                    ),
+                    _ => (
+                        register_type_matches(reg_type, RegRef::Float),
+                        "Expected a float register reference.",
+                    ),
+                };
+                if !okay {
+                    return Err(SaltError {
+                        span: op_span,
+                        message: message.into(),
+                    });
                 }
+                $self.code.push(reg_ref);
             }
             ResolvedOperand::SymbolReference => no_symbols!(op_span),
         }
@@ -491,7 +581,7 @@ macro_rules! i_BHW_bhw {
         let reg_type = match resolved {
             ResolvedOperand::Literal(_) => no_literals!(op_span),
             ResolvedOperand::RegRef(reg_ref, reg_type) => {
-                if let RegisterType::Float = reg_type {
+                if let RegisterType::Float | RegisterType::Double = reg_type {
                     return Err(SaltError {
                         span: op_span,
                         message: "Operation not applicable to floats.".into(),
@@ -550,7 +640,7 @@ macro_rules! i_BHW {
         match resolved {
             ResolvedOperand::Literal(_) => no_literals!(op_span),
             ResolvedOperand::RegRef(reg_ref, reg_type) => {
-                if let RegisterType::Float = reg_type {
+                if let RegisterType::Float | RegisterType::Double = reg_type {
                     return Err(SaltError {
                         span: op_span,
                         message: "Operation not applicable to floats.".into(),
@@ -565,6 +655,93 @@ macro_rules! i_BHW {
     }};
 }
 
+/// An instruction with operands BHW. ..a.
+macro_rules! i_BHW_a {
+    ($self:ident, $opcodes:expr, $operands:expr, $span:expr) => {{
+        num_operands!(2, $operands, $span);
+
+        // Push placeholder opcode.
+        let opcode_pos = $self.code.len();
+        $self.code.push(0);
+
+        // First operand: register (no floats).
+        let (resolved, op_span) = $self.resolve_operand(&$operands[0])?;
+        match resolved {
+            ResolvedOperand::Literal(_) => no_literals!(op_span),
+            ResolvedOperand::RegRef(reg_ref, reg_type) => {
+                if let RegisterType::Float | RegisterType::Double = reg_type {
+                    return Err(SaltError {
+                        span: op_span,
+                        message: "Operation not applicable to floats.".into(),
+                    });
+                } else {
+                    $self.code.push(reg_ref);
+                }
+            }
+            ResolvedOperand::SymbolReference => no_symbols!(op_span),
+        }
+
+        // Second operand: address.
+        let resolved = $self.resolve_operand(&$operands[1])?;
+        address!($self, resolved, $opcodes, opcode_pos);
+
+        Ok(())
+    }};
+}
+
+/// An instruction with operands BHW. BHW. ..a.
+macro_rules! i_BHW_BHW_a {
+    ($self:ident, $opcodes:expr, $operands:expr, $span:expr) => {{
+        num_operands!(3, $operands, $span);
+
+        // Push placeholder opcode.
+        let opcode_pos = $self.code.len();
+        $self.code.push(0);
+
+        // First operand: register (no floats).
+        let (resolved, op_span) = $self.resolve_operand(&$operands[0])?;
+        let reg_type = match resolved {
+            ResolvedOperand::Literal(_) => no_literals!(op_span),
+            ResolvedOperand::RegRef(reg_ref, reg_type) => {
+                if let RegisterType::Float | RegisterType::Double = reg_type {
+                    return Err(SaltError {
+                        span: op_span,
+                        message: "Operation not applicable to floats.".into(),
+                    });
+                } else {
+                    $self.code.push(reg_ref);
+                    reg_type
+                }
+            }
+            ResolvedOperand::SymbolReference => no_symbols!(op_span),
+        };
+
+        // Second operand: register of the same type.
+        let (resolved, op_span) = $self.resolve_operand(&$operands[1])?;
+        match resolved {
+            ResolvedOperand::Literal(_) => no_literals!(op_span),
+            ResolvedOperand::RegRef(reg_ref, reg_type_2) => {
+                if reg_type != reg_type_2 {
+                    return Err(SaltError {
+                        span: op_span,
+                        message: "Cannot operate between differently-sized \
+                                  registers."
+                            .into(),
+                    });
+                }
+                $self.code.push(reg_ref);
+            }
+            ResolvedOperand::SymbolReference => no_symbols!(op_span),
+        }
+
+        // Third operand: address.
+        let resolved = $self.resolve_operand(&$operands[2])?;
+        address!($self, resolved, $opcodes, opcode_pos);
+
+        Ok(())
+    }};
+}
+
 /// An instruction with operands BHW. b...
 macro_rules! i_BHW_b {
     ($self:ident, $opcodes:expr, $operands:expr, $span:expr) => {{
@@ -579,7 +756,7 @@ macro_rules! i_BHW_b {
         match resolved {
             ResolvedOperand::Literal(_) => no_literals!(op_span),
             ResolvedOperand::RegRef(reg_ref, reg_type) => {
-                if let RegisterType::Float = reg_type {
+                if let RegisterType::Float | RegisterType::Double = reg_type {
                     return Err(SaltError {
                         span: op_span,
                         message: "Operation not applicable to floats.".into(),
@@ -622,8 +799,9 @@ macro_rules! i_BHW_b {
     }};
 }
 
-/// An instruction with a single ..a. operand.
-macro_rules! i_a {
+/// An instruction with a single ..r. operand. Labels declared in this file
+/// use the relative opcode, `$opcodes.2`.
+macro_rules! i_r {
     ($self:ident, $opcodes:expr, $operands:expr, $span:expr) => {{
         num_operands!(1, $operands, $span);
 
@@ -631,8 +809,203 @@ macro_rules! i_a {
         let opcode_pos = $self.code.len();
         $self.code.push(0);
 
-        let resolved = $self.resolve_operand(&$operands[0])?;
-        address!($self, resolved, $opcodes, opcode_pos);
+        if $self.relative_label(&$operands[0])? {
+            $self.code[opcode_pos] = $opcodes.2;
+        } else {
+            let resolved = $self.resolve_operand(&$operands[0])?;
+            address!($self, resolved, $opcodes, opcode_pos);
+        }
+
+        Ok(())
+    }};
+}
+
+/// An instruction with a ..W. operand and an optional relative offset, which
+/// may be a literal or a label declared in this file.
+macro_rules! i_W_r {
+    ($self:ident, $opcode:expr, $operands:expr, $span:expr) => {{
+        if $operands.is_empty() || $operands.len() > 2 {
+            return Err(SaltError {
+                span: $span,
+                message: format!("Expected 1 or 2 operands, but found {}.", $operands.len()).into(),
+            });
+        }
+        $self.code.push($opcode);
+
+        // First operand: word register.
+        let (resolved, op_span) = $self.resolve_operand(&$operands[0])?;
+        match resolved {
+            ResolvedOperand::Literal(_) => no_literals!(op_span),
+            ResolvedOperand::RegRef(reg_ref, reg_type) => {
+                if !register_type_matches(reg_type, RegRef::Word) {
+                    return Err(SaltError {
+                        span: op_span,
+                        message: "Expected a word register reference.".into(),
+                    });
+                }
+                $self.code.push(reg_ref);
+            }
+            ResolvedOperand::SymbolReference => no_symbols!(op_span),
+        }
+
+        // Second operand: relative offset, defaulting to zero.
+        if $operands.len() == 1 {
+            $self.code.write_be_u32(0).unwrap();
+        } else if !$self.relative_label(&$operands[1])? {
+            let (resolved, op_span) = $self.resolve_operand(&$operands[1])?;
+            match resolved {
+                ResolvedOperand::Literal(literal) => {
+                    let mut value = $self.value_as_word(&literal, op_span).unwrap();
+                    $self.code.append(&mut value);
+                }
+                _ => {
+                    return Err(SaltError {
+                        span: op_span,
+                        message: "Expected a literal offset or a label declared \
+                                  in this file."
+                            .into(),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }};
+}
+
+/// An instruction with operands ..W. ..W. ..w., where the two registers must
+/// be different.
+macro_rules! i_W_W_w {
+    ($self:ident, $opcodes:expr, $operands:expr, $span:expr) => {{
+        num_operands!(3, $operands, $span);
+
+        // Push placeholder opcode.
+        let opcode_pos = $self.code.len();
+        $self.code.push(0);
+
+        // First and second operands: distinct word registers.
+        let mut high_ref = None;
+        for operand in &$operands[..2] {
+            let (resolved, op_span) = $self.resolve_operand(operand)?;
+            match resolved {
+                ResolvedOperand::Literal(_) => no_literals!(op_span),
+                ResolvedOperand::RegRef(reg_ref, reg_type) => {
+                    if !register_type_matches(reg_type, RegRef::Word) {
+                        return Err(SaltError {
+                            span: op_span,
+                            message: "Expected a word register reference.".into(),
+                        });
+                    }
+                    if high_ref == Some(reg_ref) {
+                        return Err(SaltError {
+                            span: op_span,
+                            message: "The high and low registers must be different.".into(),
+                        });
+                    }
+                    high_ref = Some(reg_ref);
+                    $self.code.push(reg_ref);
+                }
+                ResolvedOperand::SymbolReference => no_symbols!(op_span),
+            }
+        }
+
+        // Third operand: word value or register.
+        let (resolved, op_span) = $self.resolve_operand(&$operands[2])?;
+        match resolved {
+            ResolvedOperand::Literal(literal) => {
+                $self.code[opcode_pos] = $opcodes.0;
+                let mut value = $self.value_as_word(&literal, op_span).unwrap();
+                $self.code.append(&mut value);
+            }
+            ResolvedOperand::RegRef(reg_ref, reg_type) => {
+                if !register_type_matches(reg_type, RegRef::Word) {
+                    return Err(SaltError {
+                        span: op_span,
+                        message: "Expected a word register reference.".into(),
+                    });
+                }
+                $self.code[opcode_pos] = $opcodes.1;
+                $self.code.push(reg_ref);
+            }
+            ResolvedOperand::SymbolReference => {
+                $self.code[opcode_pos] = $opcodes.0;
+            }
+        }
+
+        Ok(())
+    }};
+}
+
+/// An instruction with a single ...F operand.
+macro_rules! i_F {
+    ($self:ident, $opcode:expr, $operands:expr, $span:expr) => {{
+        num_operands!(1, $operands, $span);
+        $self.code.push($opcode);
+        let (resolved, op_span) = $self.resolve_operand(&$operands[0])?;
+        match resolved {
+            ResolvedOperand::Literal(_) => no_literals!(op_span),
+            ResolvedOperand::RegRef(reg_ref, reg_type) => {
+                if !register_type_matches(reg_type, RegRef::Float) {
+                    return Err(SaltError {
+                        span: op_span,
+                        message: "Expected a float register reference.".into(),
+                    });
+                }
+                $self.code.push(reg_ref);
+            }
+            ResolvedOperand::SymbolReference => no_symbols!(op_span),
+        }
+        Ok(())
+    }};
+}
+
+/// An instruction with operands ...F ...f
+macro_rules! i_F_f {
+    ($self:ident, $opcodes:expr, $operands:expr, $span:expr) => {{
+        num_operands!(2, $operands, $span);
+
+        // Push placeholder opcode.
+        let opcode_pos = $self.code.len();
+        $self.code.push(0);
+
+        // First operand: float or double register.
+        let (resolved, op_span) = $self.resolve_operand(&$operands[0])?;
+        let reg_type = match resolved {
+            ResolvedOperand::Literal(_) => no_literals!(op_span),
+            ResolvedOperand::RegRef(reg_ref, reg_type) => {
+                if !register_type_matches(reg_type, RegRef::Float) {
+                    return Err(SaltError {
+                        span: op_span,
+                        message: "Expected a float register reference.".into(),
+                    });
+                }
+                $self.code.push(reg_ref);
+                reg_type
+            }
+            ResolvedOperand::SymbolReference => no_symbols!(op_span),
+        };
+
+        // Second operand: value or register of the same type.
+        let (resolved, op_span) = $self.resolve_operand(&$operands[1])?;
+        match resolved {
+            ResolvedOperand::Literal(literal) => {
+                $self.code[opcode_pos] = $opcodes.0;
+                $self.push_value_as_reg_type(&literal, reg_type, op_span)?;
+            }
+            ResolvedOperand::RegRef(reg_ref, reg_type_2) => {
+                if reg_type != reg_type_2 {
+                    return Err(SaltError {
+                        span: op_span,
+                        message: "Cannot operate between differently-sized \
+                                  registers."
+                            .into(),
+                    });
+                }
+                $self.code[opcode_pos] = $opcodes.1;
+                $self.code.push(reg_ref);
+            }
+            ResolvedOperand::SymbolReference => no_symbols!(op_span),
+        }
 
         Ok(())
     }};
@@ -14,6 +14,7 @@ pub enum SyntaxKind {
     Label,
     Instruction,
     Operand,
+    IndexedAddress,
     ArrayLiteral,
     Literal,
 
@@ -33,6 +34,9 @@ pub enum SyntaxKind {
     Comma,
     Colon,
     DoubleDot,
+    Plus,
+    Minus,
+    Star,
     IntLiteral,
     FloatLiteral,
     CharLiteral,
@@ -66,6 +70,9 @@ impl From<TokenType> for SyntaxKind {
             TokenType::Comma => SyntaxKind::Comma,
             TokenType::Colon => SyntaxKind::Colon,
             TokenType::DoubleDot => SyntaxKind::DoubleDot,
+            TokenType::Plus => SyntaxKind::Plus,
+            TokenType::Minus => SyntaxKind::Minus,
+            TokenType::Star => SyntaxKind::Star,
             TokenType::IntLiteral => SyntaxKind::IntLiteral,
             TokenType::FloatLiteral => SyntaxKind::FloatLiteral,
             TokenType::CharLiteral => SyntaxKind::CharLiteral,
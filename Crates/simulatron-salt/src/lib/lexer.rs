@@ -36,13 +36,21 @@ pub enum TokenType {
     Colon,
     #[token("..")]
     DoubleDot,
+    #[token("+")]
+    Plus,
+    #[token("-")]
+    Minus,
+    #[token("*")]
+    Star,
 
     // Literal components
     #[regex(r"-?([0-9]+(e-?[0-9]+)?|0b[01]+|0x[A-Fa-f0-9]+)")]
     IntLiteral,
     #[regex(r"-?[0-9]+\.[0-9]+(e-?[0-9]+)?")]
     FloatLiteral,
-    #[regex(r"'(\\[^\n]|[^\n\\'])'")]
+    // Non-ASCII characters are matched separately, as otherwise a negated
+    // class only matches the first byte of a multi-byte character.
+    #[regex(r"'(\\[^\n]|[^\n\\'\x80-\u{10FFFF}]|[\u{80}-\u{10FFFF}])'")]
     CharLiteral,
     #[regex(r#""(\\[^\n]|[^\n\\"])*""#)]
     StringLiteral,
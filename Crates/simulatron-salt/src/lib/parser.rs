@@ -101,7 +101,7 @@ impl<'a> Parser<'a> {
     }
 
     /// Peek at the type of the next non-whitespace token.
-    fn peek(&mut self) -> ParseResult<TokenType> {
+    fn peek(&mut self) -> ParseResult<'_, TokenType> {
         for tt in self.iter_token_types() {
             if tt != TokenType::Whitespace {
                 return Ok(tt);
@@ -110,8 +110,17 @@ impl<'a> Parser<'a> {
         Err(Failure::Eof)
     }
 
+    /// Check whether the next non-whitespace token in the buffer starts with
+    /// a minus sign. This should only be used after a successful `peek`.
+    fn next_is_negative(&self) -> bool {
+        self.buffer
+            .iter()
+            .find(|token| token.tt != TokenType::Whitespace)
+            .is_some_and(|token| token.slice.starts_with('-'))
+    }
+
     /// Double lookahead, skipping whitespace.
-    fn double_lookahead(&mut self) -> ParseResult<TokenType> {
+    fn double_lookahead(&mut self) -> ParseResult<'_, TokenType> {
         let mut seen: usize = 0;
         for tt in self.iter_token_types() {
             if tt != TokenType::Whitespace {
@@ -126,7 +135,7 @@ impl<'a> Parser<'a> {
 
     /// Consume the next non-whitespace token and all whitespace before it,
     /// adding them to the current position.
-    fn consume(&mut self) -> ParseResult<()> {
+    fn consume(&mut self) -> ParseResult<'_, ()> {
         // Helper function for DRY.
         fn eat(self_: &mut Parser) -> TokenType {
             let token = self_.buffer.pop_front().unwrap();
@@ -161,7 +170,7 @@ impl<'a> Parser<'a> {
 
     /// Try and consume the specified token. If the token is wrong, it will
     /// not be consumed.
-    fn try_consume_exact(&mut self, target: TokenType) -> ParseResult<()> {
+    fn try_consume_exact(&mut self, target: TokenType) -> ParseResult<'_, ()> {
         trace!("Trying to consume {:?}.", target);
         if self.peek()? == target {
             self.consume()?;
@@ -173,7 +182,7 @@ impl<'a> Parser<'a> {
 
     /// Try and consume the specified token. If the token is wrong, the given
     /// error will be generated and the token consumed.
-    fn consume_exact<M>(&mut self, target: TokenType, msg: M) -> ParseResult<()>
+    fn consume_exact<M>(&mut self, target: TokenType, msg: M) -> ParseResult<'_, ()>
     where
         M: Into<Cow<'static, str>>,
     {
@@ -188,7 +197,7 @@ impl<'a> Parser<'a> {
     }
 
     /// Consume everything up to and including a newline.
-    fn consume_till_nl(&mut self) -> ParseResult<()> {
+    fn consume_till_nl(&mut self) -> ParseResult<'_, ()> {
         debug!("Consuming till the next newline.");
         loop {
             match self.try_consume_exact(TokenType::Newline) {
@@ -244,7 +253,7 @@ impl<'a> Parser<'a> {
     }
 
     /// Line non-terminal.
-    fn parse_line(&mut self) -> ParseResult<SequenceResult> {
+    fn parse_line(&mut self) -> ParseResult<'_, SequenceResult> {
         debug!("Parsing Line...");
 
         // We might have gracefully reached the end of the file.
@@ -356,7 +365,7 @@ impl<'a> Parser<'a> {
     }
 
     /// ConstDecl non-terminal.
-    fn parse_const_decl(&mut self) -> ParseResult<()> {
+    fn parse_const_decl(&mut self) -> ParseResult<'_, ()> {
         let _guard = self.start_node(SyntaxKind::ConstDecl);
         debug!("Parsing ConstDecl...");
 
@@ -379,7 +388,7 @@ impl<'a> Parser<'a> {
     }
 
     /// DataDecl non-terminal.
-    fn parse_data_decl(&mut self) -> ParseResult<()> {
+    fn parse_data_decl(&mut self) -> ParseResult<'_, ()> {
         let _guard = self.start_node(SyntaxKind::DataDecl);
         debug!("Parsing DataDecl...");
 
@@ -410,7 +419,7 @@ impl<'a> Parser<'a> {
     }
 
     /// DataType non-terminal.
-    fn parse_data_type(&mut self) -> ParseResult<()> {
+    fn parse_data_type(&mut self) -> ParseResult<'_, ()> {
         let _guard = self.start_node(SyntaxKind::DataType);
         debug!("Parsing DataType...");
 
@@ -445,7 +454,7 @@ impl<'a> Parser<'a> {
     }
 
     /// Label non-terminal.
-    fn parse_label(&mut self) -> ParseResult<()> {
+    fn parse_label(&mut self) -> ParseResult<'_, ()> {
         let _guard = self.start_node(SyntaxKind::Label);
         debug!("Parsing Label...");
 
@@ -465,7 +474,7 @@ impl<'a> Parser<'a> {
     }
 
     /// Instruction non-terminal.
-    fn parse_instruction(&mut self) -> ParseResult<()> {
+    fn parse_instruction(&mut self) -> ParseResult<'_, ()> {
         let _guard = self.start_node(SyntaxKind::Instruction);
         debug!("Parsing Instruction...");
 
@@ -484,7 +493,7 @@ impl<'a> Parser<'a> {
     }
 
     /// Operand non-terminal.
-    fn parse_operand(&mut self) -> ParseResult<SequenceResult> {
+    fn parse_operand(&mut self) -> ParseResult<'_, SequenceResult> {
         debug!("Parsing Operand...");
         // Since operand lists have no terminator, we must be aware of
         // potential EOFs.
@@ -504,6 +513,12 @@ impl<'a> Parser<'a> {
                 debug!("...Finished Operand.");
                 Ok(SequenceResult::GoAgain)
             }
+            Ok(TokenType::OpenSquare) => {
+                let _guard = self.start_node(SyntaxKind::Operand);
+                self.parse_indexed_address()?;
+                debug!("...Finished Operand.");
+                Ok(SequenceResult::GoAgain)
+            }
             _ => {
                 // No more operands.
                 debug!("...Finished last Operand.");
@@ -512,8 +527,67 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// IndexedAddress non-terminal.
+    fn parse_indexed_address(&mut self) -> ParseResult<'_, ()> {
+        let _guard = self.start_node(SyntaxKind::IndexedAddress);
+        debug!("Parsing IndexedAddress...");
+
+        // Open bracket.
+        self.consume_exact(TokenType::OpenSquare, "Expected '['.")?;
+
+        // Base register identifier.
+        self.consume_exact(TokenType::Identifier, "Expected base register.")?;
+
+        // Optional offset or index.
+        match self.peek()? {
+            TokenType::Plus | TokenType::Minus => {
+                self.consume()?;
+                match self.peek()? {
+                    TokenType::Identifier => {
+                        // Offset identifier or index register, with an
+                        // optional scale.
+                        self.consume()?;
+                        if let TokenType::Star = self.peek()? {
+                            self.consume()?;
+                            self.consume_exact(TokenType::IntLiteral, "Expected scale.")?;
+                        }
+                    }
+                    TokenType::IntLiteral
+                    | TokenType::FloatLiteral
+                    | TokenType::CharLiteral
+                    | TokenType::Sizeof => {
+                        // Offset literal.
+                        self.parse_literal()?;
+                    }
+                    _ => {
+                        self.error_consume("Expected offset or index register.");
+                        debug!("...Finished IndexedAddress with error.");
+                        return Err(Failure::WrongToken);
+                    }
+                }
+            }
+            TokenType::IntLiteral if self.next_is_negative() => {
+                // The lexer reads `[r1 -8]` and `[r1-8]` as a single
+                // negative literal, so accept it as the offset.
+                self.parse_literal()?;
+            }
+            TokenType::CloseSquare => {}
+            _ => {
+                self.error_consume("Expected '+', '-', or ']'.");
+                debug!("...Finished IndexedAddress with error.");
+                return Err(Failure::WrongToken);
+            }
+        }
+
+        // Close bracket.
+        self.consume_exact(TokenType::CloseSquare, "Expected ']'.")?;
+
+        debug!("...Finished IndexedAddress.");
+        Ok(())
+    }
+
     /// ArrayLiteral non-terminal.
-    fn parse_array_literal(&mut self) -> ParseResult<()> {
+    fn parse_array_literal(&mut self) -> ParseResult<'_, ()> {
         let _guard = self.start_node(SyntaxKind::ArrayLiteral);
         debug!("Parsing ArrayLiteral...");
 
@@ -569,7 +643,7 @@ impl<'a> Parser<'a> {
     }
 
     /// Literal non-terminal.
-    fn parse_literal(&mut self) -> ParseResult<()> {
+    fn parse_literal(&mut self) -> ParseResult<'_, ()> {
         let _guard = self.start_node(SyntaxKind::Literal);
         debug!("Parsing Literal...");
 
@@ -681,6 +755,11 @@ mod tests {
         assert_syntax_tree_snapshot!("examples/hello-world.simasm");
     }
 
+    #[test]
+    fn test_indexed_addressing() {
+        assert_syntax_tree_snapshot!("examples/indexed-addressing.simasm");
+    }
+
     #[test]
     fn test_instruction_block() {
         assert_syntax_tree_snapshot!("examples/instruction-block.simasm");
@@ -70,12 +70,24 @@ fn test_array_inferred() {
     test_failure!("examples/array-bad.simasm");
 }
 
+#[test]
+fn test_bit_manipulation() {
+    test_success!("examples/bit-manipulation.simasm", true);
+    test_failure!("examples/bit-manipulation-bad.simasm");
+}
+
 #[test]
 fn test_bitwise() {
     test_success!("examples/bitwise.simasm", true);
     test_failure!("examples/bitwise-bad.simasm");
 }
 
+#[test]
+fn test_atomics() {
+    test_success!("examples/atomics.simasm", true);
+    test_failure!("examples/atomics-bad.simasm");
+}
+
 #[test]
 fn test_blockcopy() {
     test_success!("examples/blockcopy.simasm", false);
@@ -100,6 +112,12 @@ fn test_comments() {
     test_success_with_warnings!("examples/comments.simasm", false);
 }
 
+#[test]
+fn test_conditional() {
+    test_success!("examples/conditional.simasm", true);
+    test_failure!("examples/conditional-bad.simasm");
+}
+
 #[test]
 fn test_convert() {
     test_success!("examples/convert.simasm", true);
@@ -112,26 +130,66 @@ fn test_copy() {
     test_failure!("examples/copy-bad.simasm");
 }
 
+#[test]
+fn test_debug_registers() {
+    test_success!("examples/debug-registers.simasm", true);
+}
+
+#[test]
+fn test_doubles() {
+    test_success!("examples/doubles.simasm", true);
+    test_failure!("examples/doubles-bad.simasm");
+}
+
 #[test]
 fn test_empty() {
     test_failure!("examples/empty-file.simasm");
 }
 
+#[test]
+fn test_extended_chars() {
+    test_success!("examples/extended-chars.simasm", true);
+    test_failure!("examples/extended-chars-bad.simasm");
+}
+
 #[test]
 fn test_external_refs() {
     test_success_with_warnings!("examples/external-refs.simasm", false);
 }
 
+#[test]
+fn test_float_math() {
+    test_success!("examples/float-math.simasm", true);
+    test_failure!("examples/float-math-bad.simasm");
+}
+
 #[test]
 fn test_hello_world() {
     test_success_with_warnings!("examples/hello-world.simasm", true);
 }
 
+#[test]
+fn test_indexed_addressing() {
+    test_success!("examples/indexed-addressing.simasm", true);
+    test_failure!("examples/indexed-addressing-bad.simasm");
+}
+
+#[test]
+fn test_interrupt_vector() {
+    test_success!("examples/interrupt-vector.simasm", true);
+}
+
 #[test]
 fn test_minimal() {
     test_success!("examples/minimal.simasm", true);
 }
 
+#[test]
+fn test_multicore() {
+    test_success!("examples/multicore.simasm", true);
+    test_failure!("examples/multicore-bad.simasm");
+}
+
 #[test]
 fn test_naming_violations() {
     test_success_with_warnings!("examples/naming-violations.simasm", true);
@@ -148,6 +206,12 @@ fn test_push_pop() {
     test_failure!("examples/push-pop-bad.simasm");
 }
 
+#[test]
+fn test_relative_jumps() {
+    test_success!("examples/relative-jumps.simasm", true);
+    test_failure!("examples/relative-jumps-bad.simasm");
+}
+
 #[test]
 fn test_store() {
     test_success!("examples/store.simasm", true);
@@ -157,3 +221,15 @@ fn test_store() {
 fn test_swap() {
     test_success!("examples/swap.simasm", true);
 }
+
+#[test]
+fn test_syscalls() {
+    test_success!("examples/syscalls.simasm", true);
+    test_failure!("examples/syscalls-bad.simasm");
+}
+
+#[test]
+fn test_wide_arithmetic() {
+    test_success!("examples/wide-arithmetic.simasm", true);
+    test_failure!("examples/wide-arithmetic-bad.simasm");
+}
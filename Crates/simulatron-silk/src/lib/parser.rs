@@ -165,7 +165,7 @@ impl<S: ReadBE> Parser<S> {
     /// Parse the sections themselves.
     fn parse_sections(
         &mut self,
-        headers: &Vec<SectionHeader>,
+        headers: &[SectionHeader],
         sections_start: u32,
     ) -> OFResult<Vec<Section>> {
         let mut sections = Vec::with_capacity(headers.len());
@@ -194,9 +194,8 @@ impl<S: ReadBE> Parser<S> {
     fn read_u8(&mut self) -> OFResult<u8> {
         self.source
             .read_u8()
-            .map(|val| {
+            .inspect(|_| {
                 self.bytes_read += 1;
-                val
             })
             .map_err(Into::into)
     }
@@ -204,9 +203,8 @@ impl<S: ReadBE> Parser<S> {
     fn read_u16(&mut self) -> OFResult<u16> {
         self.source
             .read_be_u16()
-            .map(|val| {
+            .inspect(|_| {
                 self.bytes_read += 2;
-                val
             })
             .map_err(Into::into)
     }
@@ -214,9 +212,8 @@ impl<S: ReadBE> Parser<S> {
     fn read_u32(&mut self) -> OFResult<u32> {
         self.source
             .read_be_u32()
-            .map(|val| {
+            .inspect(|_| {
                 self.bytes_read += 4;
-                val
             })
             .map_err(Into::into)
     }
@@ -224,9 +221,8 @@ impl<S: ReadBE> Parser<S> {
     fn read_buffer(&mut self, buf: &mut [u8]) -> OFResult<()> {
         self.source
             .read_exact(buf)
-            .map(|val| {
+            .inspect(|_| {
                 self.bytes_read += u32::try_from(buf.len()).unwrap();
-                val
             })
             .map_err(Into::into)
     }
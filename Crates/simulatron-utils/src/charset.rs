@@ -0,0 +1,50 @@
+/// The graphical characters occupying codes 128-255 of the Simulatron
+/// character set, in order.
+const EXTENDED_CHARS: [char; 128] = [
+    // 128-175: shading, box drawing and blocks (code page 437 176-223).
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐', //
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧', //
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀', //
+    // 176-189: arrows.
+    '←', '↑', '→', '↓', '↔', '↕', '↖', '↗', '↘', '↙', '▲', '►', '▼', '◄', //
+    // 190-193: rounded corners.
+    '╭', '╮', '╯', '╰', //
+    // 194-204: heavy box drawing.
+    '━', '┃', '┏', '┓', '┗', '┛', '┣', '┫', '┳', '┻', '╋', //
+    // 205-216: partial blocks.
+    '▁', '▂', '▃', '▅', '▆', '▇', '▏', '▎', '▍', '▋', '▊', '▉', //
+    // 217-226: quadrants.
+    '▖', '▗', '▘', '▝', '▚', '▞', '▙', '▛', '▜', '▟', //
+    // 227-236: shapes.
+    '■', '□', '▪', '▫', '●', '○', '◘', '◙', '◆', '◇', //
+    // 237-246: symbols.
+    '☺', '☻', '♥', '♦', '♣', '♠', '•', '♪', '♫', '☼', //
+    // 247-255: maths.
+    '≡', '±', '≥', '≤', '÷', '≈', '°', '√', '²',
+];
+
+/// Try and convert the given byte in the Simulatron character set to a
+/// printable Unicode character.
+pub fn u8_to_printable_char(byte: u8) -> Option<char> {
+    match byte {
+        31 => Some('£'),
+        32..=126 => Some(char::from(byte)),
+        127 => Some('¬'),
+        128..=255 => Some(EXTENDED_CHARS[usize::from(byte - 128)]),
+        _ => None,
+    }
+}
+
+/// Try and convert the given printable Unicode character to its byte
+/// representation in the Simulatron character set.
+pub fn printable_char_to_u8(c: char) -> Option<u8> {
+    match c {
+        '£' => Some(31),
+        ' '..='~' => Some(c as u8),
+        '¬' => Some(127),
+        _ => EXTENDED_CHARS
+            .iter()
+            .position(|&ext| ext == c)
+            .map(|i| i as u8 + 128),
+    }
+}
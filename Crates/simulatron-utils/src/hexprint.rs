@@ -2,9 +2,9 @@
 
 use std::fmt::Write;
 
-/// Nicely format the given `Vec<u8>` as a hex block. The listed addresses will
+/// Nicely format the given bytes as a hex block. The listed addresses will
 /// start from `start`.
-pub fn pretty_print_hex_block(buf: &Vec<u8>, start: usize) -> String {
+pub fn pretty_print_hex_block(buf: &[u8], start: usize) -> String {
     // Each 16 bytes of the input produces a line consisting of:
     // - a 10-character address
     // - 32 characters of bytes
@@ -69,7 +69,7 @@ pub fn pretty_print_hex_block(buf: &Vec<u8>, start: usize) -> String {
 
 /// Shortcut for starting the addresses at zero.
 #[inline]
-pub fn pretty_print_hex_block_zero(buf: &Vec<u8>) -> String {
+pub fn pretty_print_hex_block_zero(buf: &[u8]) -> String {
     pretty_print_hex_block(buf, 0)
 }
 
@@ -1,3 +1,4 @@
+pub mod charset;
 pub mod file;
 pub mod hexprint;
 pub mod read_be;
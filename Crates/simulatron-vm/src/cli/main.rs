@@ -8,6 +8,8 @@ use time::macros::format_description;
 const ROM_PATH: &str = "ROM_PATH";
 const DISK_A_PATH: &str = "DISK_A_PATH";
 const DISK_B_PATH: &str = "DISK_B_PATH";
+const SOUND_PATH: &str = "SOUND_PATH";
+const CORES: &str = "CORES";
 const LOG_PATH: &str = "LOG_PATH";
 const LOG_LEVEL: &str = "LOG_LEVEL";
 const INIT: &str = "INIT";
@@ -60,6 +62,20 @@ fn cli() -> Command {
                 .action(ArgAction::Set)
                 .default_value("./DiskB"),
         )
+        .arg(
+            Arg::new(SOUND_PATH)
+                .help("If set, sound output will be written as a WAV file to the given path.")
+                .long("sound")
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new(CORES)
+                .help("The number of CPU cores.")
+                .long("cores")
+                .action(ArgAction::Set)
+                .default_value("1")
+                .value_parser(value_parser!(u8).range(1..=simulatron_vm::MAX_CORES as i64)),
+        )
         .arg(
             Arg::new(LOG_PATH)
                 .help("If set, a debug log will be written to the given path.")
@@ -195,10 +211,23 @@ fn run(args: ArgMatches) -> u8 {
             }
         }
 
-        // Run the Simulatron.
-        simulatron_vm::run(rom.as_slice().try_into().unwrap(), disk_a_path, disk_b_path);
+        // Create the sound output if configured.
+        let sound_file = match args.get_one::<String>(SOUND_PATH) {
+            Some(sound_path) => match File::create(sound_path) {
+                Ok(sound_file) => Some(sound_file),
+                Err(e) => return Err(format!("Failed to create sound file: {}", e)),
+            },
+            None => None,
+        };
 
-        Ok(())
+        // Run the Simulatron.
+        simulatron_vm::run(
+            rom.as_slice().try_into().unwrap(),
+            disk_a_path,
+            disk_b_path,
+            sound_file,
+            (*args.get_one::<u8>(CORES).unwrap()).into(),
+        )
     }
 }
 
@@ -1,5 +1,6 @@
 #![allow(clippy::comparison_chain)]
 
+mod fpu;
 mod rotcarry;
 
 #[macro_use]
@@ -8,16 +9,21 @@ mod macros; // Macros moved to separate file due to length.
 #[cfg(test)] // Unit tests moved to separate file due to length.
 mod tests;
 
-use log::{debug, info, trace};
+use log::{debug, error, info, trace};
 use std::convert::{TryFrom, TryInto};
-use std::ops::{Add, BitAnd, BitOr, BitXor, Div, Mul, Rem, Sub};
-use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::ops::{BitAnd, BitOr, BitXor};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError},
+    Arc, Mutex, MutexGuard,
+};
 use std::thread;
 use std::time::Duration;
 
 use crate::disk::DiskController;
 use crate::mmu::MMU;
 use crate::ui::UICommand;
+use fpu::{FPSR_ENABLE_SHIFT, FPSR_MASK};
 use rotcarry::{Rcl, Rcr};
 
 // Interrupt values.
@@ -29,19 +35,40 @@ pub const INTERRUPT_DISK_A: u32 = 4;
 pub const INTERRUPT_DISK_B: u32 = 5;
 pub const INTERRUPT_TIMER: u32 = 6;
 pub const INTERRUPT_SYSCALL: u32 = 7;
+pub const INTERRUPT_MOUSE: u32 = 8;
+pub const INTERRUPT_INTERVAL_TIMER: u32 = 9;
+pub const INTERRUPT_IPI: u32 = 10;
+pub const INTERRUPT_FLOATING_POINT: u32 = 11;
+pub const INTERRUPT_DOUBLE_FAULT: u32 = 12;
+pub const INTERRUPT_DEBUG: u32 = 13;
+
+// One interrupt for each bit of the IMR.
+const NUM_INTERRUPTS: usize = 16;
 const JOIN_THREAD: u32 = u32::MAX; // Not a real interrupt, just a thread join command.
 
+pub const MAX_CORES: usize = 4;
+
 // Flag bits.
 const FLAG_ZERO: u16 = 0x01;
 const FLAG_NEGATIVE: u16 = 0x02;
 const FLAG_CARRY: u16 = 0x04;
 const FLAG_OVERFLOW: u16 = 0x08;
+const FLAG_TRACE: u16 = 0x10;
+
+// Breakpoints.
+const NUM_BREAKPOINTS: usize = 4;
+const BREAK_EXECUTE: u32 = 0x01;
+const BREAK_READ: u32 = 0x02;
+const BREAK_WRITE: u32 = 0x04;
+const DCR_MASK: u32 = 0x1F1F1F1F; // Condition and length bits of each breakpoint.
+const DSR_TRACE: u16 = 0x10;
+const DSR_MASK: u16 = 0x1F;
 
 /// Possible errors from a CPU cycle.
 #[derive(Debug, PartialEq, Eq)]
 pub enum CPUError {
     TryAgainError,
-    FatalError,
+    FatalError(String),
 }
 pub type CPUResult<T> = Result<T, CPUError>;
 
@@ -52,6 +79,20 @@ enum ValueType {
     Half,
     Word,
     Float,
+    Double,
+}
+
+impl ValueType {
+    /// Return the number of bytes in a value of this type.
+    fn size_in_bytes(&self) -> u32 {
+        match *self {
+            ValueType::Byte => 1,
+            ValueType::Half => 2,
+            ValueType::Word => 4,
+            ValueType::Float => 4,
+            ValueType::Double => 8,
+        }
+    }
 }
 
 /// Get the type of a TypedValue.
@@ -62,17 +103,19 @@ impl From<&TypedValue> for ValueType {
             TypedValue::Half(_) => ValueType::Half,
             TypedValue::Word(_) => ValueType::Word,
             TypedValue::Float(_) => ValueType::Float,
+            TypedValue::Double(_) => ValueType::Double,
         }
     }
 }
 
 /// A value that Simulatron can work with.
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 enum TypedValue {
     Byte(u8),
     Half(u16),
     Word(u32),
     Float(f32),
+    Double(f64),
 }
 
 impl TypedValue {
@@ -83,6 +126,7 @@ impl TypedValue {
             TypedValue::Half(_) => 2,
             TypedValue::Word(_) => 4,
             TypedValue::Float(_) => 4,
+            TypedValue::Double(_) => 8,
         }
     }
 
@@ -93,6 +137,7 @@ impl TypedValue {
             TypedValue::Half(x) => x == 0,
             TypedValue::Word(x) => x == 0,
             TypedValue::Float(_) => false,
+            TypedValue::Double(_) => false,
         }
     }
 
@@ -103,6 +148,7 @@ impl TypedValue {
             TypedValue::Half(x) => *x += 1,
             TypedValue::Word(x) => *x += 1,
             TypedValue::Float(x) => *x += 1.0,
+            TypedValue::Double(x) => *x += 1.0,
         };
     }
 }
@@ -155,9 +201,21 @@ impl TryFrom<TypedValue> for f32 {
     }
 }
 
+impl TryFrom<TypedValue> for f64 {
+    type Error = WrongType;
+    fn try_from(value: TypedValue) -> Result<Self, Self::Error> {
+        if let TypedValue::Double(d) = value {
+            Ok(d)
+        } else {
+            Err(WrongType)
+        }
+    }
+}
+
 /// An interrupt latch.
 struct InterruptLatch {
-    latched: [bool; 8],
+    latched: [bool; NUM_INTERRUPTS],
+    join_requested: bool,
     interrupt_rx: Receiver<u32>,
 }
 
@@ -165,72 +223,118 @@ impl InterruptLatch {
     /// Create a new interrupt latch with the given interrupt channel.
     fn new(interrupt_rx: Receiver<u32>) -> Self {
         InterruptLatch {
-            latched: [false; 8],
+            latched: [false; NUM_INTERRUPTS],
+            join_requested: false,
             interrupt_rx,
         }
     }
 
-    /// Poll the next interrupt, returning immediately if none are present.
-    fn try_get_next(&mut self, imr: u16) -> Option<u32> {
-        // First, try and service latched interrupts, prioritising lower numbers first.
-        for i in 0..8 {
-            if self.latched[i] && (imr & (1 << i as u16)) > 0 {
-                debug!("Returning latched interrupt {}.", i);
-                self.latched[i] = false;
-                return Some(i as u32);
-            }
-        }
-
-        // If there aren't any enabled latched interrupts, check the channel.
+    /// Latch everything waiting on the channel, so that it can be
+    /// prioritised along with the interrupts already latched.
+    fn latch_waiting(&mut self) {
         loop {
             match self.interrupt_rx.try_recv() {
+                Ok(JOIN_THREAD) => self.join_requested = true,
                 Ok(interrupt) => {
-                    // If enabled, return. If disabled, latch it and check again.
-                    if interrupt == JOIN_THREAD || (imr & (1 << interrupt as u16)) > 0 {
-                        debug!("Returning interrupt {}.", interrupt);
-                        return Some(interrupt);
-                    } else {
-                        debug!("Latching interrupt {}.", interrupt);
-                        self.latched[interrupt as usize] = true;
-                    }
+                    debug!("Latching interrupt {}.", interrupt);
+                    self.latched[interrupt as usize] = true;
                 }
                 Err(TryRecvError::Disconnected) => panic!(),
-                Err(TryRecvError::Empty) => {
-                    trace!("No enabled interrupts available.");
-                    return None;
-                }
+                Err(TryRecvError::Empty) => break,
             }
         }
     }
 
-    /// Block until an interrupt is available.
-    fn wait_for_next(&mut self, imr: u16) -> u32 {
-        debug!("Waiting on interrupt.");
-        // First, try and service latched interrupts, prioritising lower numbers first.
-        for i in 0..8 {
-            if self.latched[i] && (imr & (1 << i as u16)) > 0 {
+    /// Get the set of latched interrupts, one bit per interrupt.
+    fn pending(&mut self) -> u16 {
+        self.latch_waiting();
+        (0..NUM_INTERRUPTS)
+            .filter(|&i| self.latched[i])
+            .fold(0, |pending, i| pending | (1 << i))
+    }
+
+    /// Check whether the given interrupt is latched, without polling the
+    /// channel.
+    fn is_latched(&self, interrupt: u32) -> bool {
+        self.latched[interrupt as usize]
+    }
+
+    /// Discard the latched interrupts whose bits are set in the given mask.
+    fn clear(&mut self, mask: u16) {
+        self.latch_waiting();
+        for i in 0..NUM_INTERRUPTS {
+            if (mask & (1 << i as u16)) > 0 {
+                debug!("Clearing interrupt {}.", i);
+                self.latched[i] = false;
+            }
+        }
+    }
+
+    /// Poll the next interrupt, returning immediately if none are present.
+    fn try_get_next(&mut self, imr: u16, ipr: u32) -> Option<u32> {
+        self.latch_waiting();
+        if self.join_requested {
+            return Some(JOIN_THREAD);
+        }
+
+        // Service the enabled interrupt with the highest priority level,
+        // prioritising lower numbers within the same level.
+        let mut next: Option<usize> = None;
+        for i in 0..NUM_INTERRUPTS {
+            if self.latched[i]
+                && (imr & (1 << i as u16)) > 0
+                && next.is_none_or(|n| priority_level(ipr, i) > priority_level(ipr, n))
+            {
+                next = Some(i);
+            }
+        }
+        match next {
+            Some(i) => {
                 debug!("Returning latched interrupt {}.", i);
                 self.latched[i] = false;
-                return i as u32;
+                Some(i as u32)
+            }
+            None => {
+                trace!("No enabled interrupts available.");
+                None
             }
         }
+    }
 
-        // If there aren't any enabled latched interrupts, wait on the channel.
+    /// Block until an interrupt is available.
+    fn wait_for_next(&mut self, imr: u16, ipr: u32) -> u32 {
+        debug!("Waiting on interrupt.");
         loop {
+            if let Some(interrupt) = self.try_get_next(imr, ipr) {
+                return interrupt;
+            }
+            // Nothing enabled yet, so wait on the channel. Directly return
+            // JOIN_THREAD, and latch anything else before checking again.
             let interrupt = self.interrupt_rx.recv().unwrap();
-            // If enabled, directly return. If disabled, latch it and check again.
-            // Also directly return JOIN_THREAD.
-            if interrupt == JOIN_THREAD || (imr & (1 << interrupt as u16)) > 0 {
-                debug!("Returning interrupt {}.", interrupt);
+            if interrupt == JOIN_THREAD {
                 return interrupt;
-            } else {
-                debug!("Latching interrupt {}.", interrupt);
-                self.latched[interrupt as usize] = true;
             }
+            debug!("Latching interrupt {}.", interrupt);
+            self.latched[interrupt as usize] = true;
         }
     }
 }
 
+/// Get the priority level (0-3) of the given interrupt from the Interrupt
+/// Priority Register, which holds two bits per interrupt.
+fn priority_level(ipr: u32, interrupt: usize) -> u32 {
+    (ipr >> (interrupt * 2)) & 0b11
+}
+
+/// Get the mask of all interrupts with a strictly higher priority level than
+/// the given interrupt.
+fn higher_priority_mask(ipr: u32, interrupt: usize) -> u16 {
+    let level = priority_level(ipr, interrupt);
+    (0..NUM_INTERRUPTS)
+        .filter(|&i| priority_level(ipr, i) > level)
+        .fold(0, |mask, i| mask | (1 << i))
+}
+
 /// A CPU timer.
 struct Timer {
     interrupt_tx: Option<Sender<u32>>,
@@ -305,107 +409,182 @@ enum PostCycleAction {
     None,
 }
 
-/// The internals of a CPU, which get moved to a separate thread while running.
+/// The internals of a single CPU core, which get moved to a separate thread
+/// while running.
 #[allow(clippy::upper_case_acronyms)]
 struct CPUInternal<D> {
+    core_id: usize,
     timer: Timer,
-    mmu: MMU<D>,
+    mmu: Arc<Mutex<MMU<D>>>, // Shared between all cores.
     interrupts: InterruptLatch,
     r: [u32; 8], // r0-r7 registers
     f: [f32; 8], // f0-f7 registers
+    d: [f64; 8], // d0-d7 registers
     flags: u16,  // Flags register
+    trace: bool, // Trace flag, kept apart from the arithmetic flags
     uspr: u32,   // User Stack Pointer Register
     kspr: u32,   // Kernel Stack Pointer Register
     pdpr: u32,   // Page Directory Pointer Register
     imr: u16,    // Interrupt Mask Register
+    fpsr: u16,   // Floating-Point Status Register
+    ivbr: u32,   // Interrupt Vector Base Register
+    ipr: u32,    // Interrupt Priority Register
+    scnr: u32,   // Syscall Number Register
+    espr: u32,   // Emergency Stack Pointer Register
+    // Breakpoint state.
+    da: [u32; NUM_BREAKPOINTS], // Debug Address Registers
+    dcr: u32,                   // Debug Control Register
+    dsr: u16,                   // Debug Status Register
     program_counter: u32,
     kernel_mode: bool,
     ui_tx: Sender<UICommand>,
     interrupt_tx: Sender<u32>,
+    ipi_tx: Vec<Sender<u32>>,        // Interrupt channels of every core.
+    running_cores: Arc<AtomicUsize>, // The number of cores yet to halt.
+    fatal_error: Option<String>,     // Why the core halted, if it failed.
     // Per-cycle state.
     rewind: u32,      // How much to rewind if the last cycle failed.
     skip_pause: bool, // Whether to skip PAUSE instructions this cycle.
 }
 
-/// The public-facing CPU interface.
+/// The public-facing CPU interface. This may consist of several cores, each
+/// running on its own thread.
 #[allow(clippy::upper_case_acronyms)]
 pub struct CPU<D> {
-    interrupt_tx: Sender<u32>,
-    thread_handle: Option<thread::JoinHandle<CPUInternal<D>>>,
-    internal: Option<CPUInternal<D>>,
+    interrupt_tx: Vec<Sender<u32>>,
+    thread_handles: Vec<thread::JoinHandle<CPUInternal<D>>>,
+    internal: Option<Vec<CPUInternal<D>>>,
 }
 
 impl<D: DiskController + 'static> CPU<D> {
-    /// Create a new CPU with the given MMU, interrupt channel, and UI command
-    /// channel.
+    /// Create a new CPU with the given number of cores, MMU, interrupt
+    /// channel, and UI command channel. Interrupts sent on the given channel
+    /// are delivered to core 0. Panics if the number of cores is not between
+    /// 1 and MAX_CORES.
     pub fn new(
         ui_tx: Sender<UICommand>,
-        mmu: MMU<D>,
+        mut mmu: MMU<D>,
         interrupt_tx: Sender<u32>,
         interrupt_rx: Receiver<u32>,
+        num_cores: usize,
     ) -> Self {
-        CPU {
-            interrupt_tx: interrupt_tx.clone(),
-            thread_handle: None,
-            internal: Some(CPUInternal {
-                timer: Timer::new(interrupt_tx.clone()),
-                mmu,
-                interrupts: InterruptLatch::new(interrupt_rx),
+        assert!(
+            (1..=MAX_CORES).contains(&num_cores),
+            "A CPU must have between 1 and {} cores.",
+            MAX_CORES
+        );
+
+        // Create an interrupt channel for each additional core.
+        let mut channels = vec![(interrupt_tx, interrupt_rx)];
+        for _ in 1..num_cores {
+            let (core_tx, core_rx) = mpsc::channel();
+            mmu.add_core(core_tx.clone());
+            channels.push((core_tx, core_rx));
+        }
+        let interrupt_tx: Vec<Sender<u32>> = channels.iter().map(|(tx, _)| tx.clone()).collect();
+
+        let mmu = Arc::new(Mutex::new(mmu));
+        let running_cores = Arc::new(AtomicUsize::new(0));
+        let cores = channels
+            .into_iter()
+            .enumerate()
+            .map(|(core_id, (core_tx, core_rx))| CPUInternal {
+                core_id,
+                timer: Timer::new(core_tx.clone()),
+                mmu: Arc::clone(&mmu),
+                interrupts: InterruptLatch::new(core_rx),
                 r: [0; 8],
                 f: [0.0; 8],
+                d: [0.0; 8],
                 flags: 0,
+                trace: false,
                 uspr: 0,
                 kspr: 0,
                 pdpr: 0,
                 imr: 0,
+                fpsr: 0,
+                ivbr: 0,
+                ipr: 0,
+                scnr: 0,
+                espr: 0,
+                da: [0; NUM_BREAKPOINTS],
+                dcr: 0,
+                dsr: 0,
                 program_counter: 64, // Start of ROM.
                 kernel_mode: true,
-                ui_tx,
-                interrupt_tx,
+                ui_tx: ui_tx.clone(),
+                interrupt_tx: core_tx,
+                ipi_tx: interrupt_tx.clone(),
+                running_cores: Arc::clone(&running_cores),
+                fatal_error: None,
                 rewind: 0,
                 skip_pause: false,
-            }),
+            })
+            .collect();
+
+        CPU {
+            interrupt_tx,
+            thread_handles: Vec::new(),
+            internal: Some(cores),
         }
     }
 
-    /// Start the CPU on its own thread. Panics if already running.
+    /// Start each CPU core on its own thread. Panics if already running.
     pub fn start(&mut self) {
-        // Spawn the worker thread and move the CPUInternal into it.
-        let mut internal = self.internal.take().expect("CPU was already running.");
-        let thread_handle = thread::spawn(move || {
-            // Setup.
-            internal.mmu.start();
-            internal.timer.start();
-
-            // Main loop.
-            internal.cpu_loop();
-
-            // Cleanup.
-            internal.ui_tx.send(UICommand::CPUHalted).unwrap();
-            internal.timer.stop();
-            internal.mmu.stop();
-
-            // Move the data back out.
-            internal
-        });
-        self.thread_handle = Some(thread_handle);
+        let cores = self.internal.take().expect("CPU was already running.");
+
+        // The MMU is shared, so it is started once here, and stopped by
+        // whichever core halts last.
+        cores[0].mmu.lock().unwrap().start();
+        cores[0].running_cores.store(cores.len(), Ordering::SeqCst);
+
+        // Spawn the worker threads and move each CPUInternal into its own.
+        for mut internal in cores {
+            let thread_handle = thread::spawn(move || {
+                // Setup.
+                internal.timer.start();
+
+                // Main loop.
+                internal.cpu_loop();
+
+                // Cleanup.
+                internal.timer.stop();
+                if internal.running_cores.fetch_sub(1, Ordering::SeqCst) == 1 {
+                    internal.ui_tx.send(UICommand::CPUHalted).unwrap();
+                    internal.mmu.lock().unwrap().stop();
+                }
+
+                // Move the data back out.
+                internal
+            });
+            self.thread_handles.push(thread_handle);
+        }
     }
 
-    /// Stop the CPU thread. Panics if not running.
+    /// Stop all CPU threads. Panics if not running.
     pub fn stop(&mut self) {
-        self.interrupt_tx.send(JOIN_THREAD).unwrap();
+        for interrupt_tx in self.interrupt_tx.iter() {
+            interrupt_tx.send(JOIN_THREAD).unwrap();
+        }
         self.wait_for_halt();
     }
 
-    /// Block until the CPU thread terminates. Panics if not running.
+    /// Get the reason the given core halted, if it was due to a fatal error.
+    /// Panics if running.
+    pub fn fatal_error(&self, core: usize) -> Option<&str> {
+        let cores = self.internal.as_ref().expect("CPU is still running.");
+        cores[core].fatal_error.as_deref()
+    }
+
+    /// Block until all CPU threads terminate. Panics if not running.
     fn wait_for_halt(&mut self) {
-        let thread_data = self
-            .thread_handle
-            .take()
-            .expect("CPU was already stopped.")
-            .join()
-            .expect("CPU thread terminated with error.");
-        self.internal = Some(thread_data);
+        assert!(!self.thread_handles.is_empty(), "CPU was already stopped.");
+        let cores = self
+            .thread_handles
+            .drain(..)
+            .map(|handle| handle.join().expect("CPU thread terminated with error."))
+            .collect();
+        self.internal = Some(cores);
     }
 }
 
@@ -413,12 +592,12 @@ impl<D: DiskController> CPUInternal<D> {
     /// Top-level CPU loop.
     fn cpu_loop(&mut self) {
         let mut pausing = false;
-        info!("CPU starting.");
+        info!("CPU core {} starting.", self.core_id);
         loop {
             // Perform one cycle.
             match self.interrupt_fetch_decode_execute(pausing) {
                 Ok(PostCycleAction::Halt) => {
-                    info!("CPU halting.");
+                    info!("CPU core {} halting.", self.core_id);
                     break;
                 }
                 Ok(PostCycleAction::Pause) => {
@@ -433,8 +612,18 @@ impl<D: DiskController> CPUInternal<D> {
                     self.program_counter = self.program_counter.wrapping_sub(self.rewind);
                     pausing = false;
                 }
-                Err(CPUError::FatalError) => {
-                    info!("Fatal CPU error, halting.");
+                Err(CPUError::FatalError(reason)) => {
+                    error!(
+                        "Fatal error on CPU core {}, halting: {}.",
+                        self.core_id, reason
+                    );
+                    self.ui_tx
+                        .send(UICommand::CPUFatalError {
+                            core: self.core_id,
+                            reason: reason.clone(),
+                        })
+                        .unwrap();
+                    self.fatal_error = Some(reason);
                     break;
                 }
             }
@@ -500,29 +689,66 @@ impl<D: DiskController> CPUInternal<D> {
             }};
         }
 
-        /// Ensure the given value is not a float, returning with an illegal
-        /// operation interrupt otherwise.
-        macro_rules! reject_float {
+        /// Compute a base + index * scale address from the given register
+        /// references, returning with an illegal operation interrupt if the
+        /// scale is invalid.
+        macro_rules! indexed_address {
+            ($base_ref:expr, $index_ref:expr, $scale:expr) => {{
+                if !matches!($scale, 1 | 2 | 4 | 8) {
+                    self.interrupt_tx.send(INTERRUPT_ILLEGAL_OPERATION).unwrap();
+                    return Err(CPUError::TryAgainError);
+                }
+                let base: u32 = try_tv_into_v!(self.read_from_register($base_ref)?);
+                let index: u32 = try_tv_into_v!(self.read_from_register($index_ref)?);
+                base.wrapping_add(index.wrapping_mul(u32::from($scale)))
+            }};
+        }
+
+        /// Ensure that the given high and low register references are distinct
+        /// word registers, returning with an illegal operation interrupt
+        /// otherwise.
+        macro_rules! check_wide_registers {
+            ($high:expr, $low:expr) => {{
+                if $high == $low
+                    || self.reg_ref_type($high)? != ValueType::Word
+                    || self.reg_ref_type($low)? != ValueType::Word
+                {
+                    self.interrupt_tx.send(INTERRUPT_ILLEGAL_OPERATION).unwrap();
+                    return Err(CPUError::TryAgainError);
+                }
+            }};
+        }
+
+        /// Ensure the given register reference is a float or double register,
+        /// returning with an illegal operation interrupt otherwise.
+        macro_rules! require_float {
             ($r:expr) => {{
-                if self.reg_ref_type($r)? == ValueType::Float {
+                if !matches!(self.reg_ref_type($r)?, ValueType::Float | ValueType::Double) {
                     self.interrupt_tx.send(INTERRUPT_ILLEGAL_OPERATION).unwrap();
                     return Err(CPUError::TryAgainError);
                 }
             }};
         }
 
-        /// Safely unwrap an operation for which an error is fatal.
-        macro_rules! critical {
-            ($op:expr) => {
-                $op.or(Err(CPUError::FatalError))?
-            };
+        /// Ensure the given value is not a float or double, returning with an
+        /// illegal operation interrupt otherwise.
+        macro_rules! reject_float {
+            ($r:expr) => {{
+                if matches!(self.reg_ref_type($r)?, ValueType::Float | ValueType::Double) {
+                    self.interrupt_tx.send(INTERRUPT_ILLEGAL_OPERATION).unwrap();
+                    return Err(CPUError::TryAgainError);
+                }
+            }};
         }
 
+        // Nothing has been fetched yet this cycle.
+        self.rewind = 0;
+
         // Check for interrupts.
         let possible_interrupt = if pausing {
-            Some(self.interrupts.wait_for_next(self.imr))
+            Some(self.interrupts.wait_for_next(self.imr, self.ipr))
         } else {
-            self.interrupts.try_get_next(self.imr)
+            self.interrupts.try_get_next(self.imr, self.ipr)
         };
         if let Some(interrupt) = possible_interrupt {
             // If it's the join thread command, exit.
@@ -537,21 +763,44 @@ impl<D: DiskController> CPUInternal<D> {
                 0
             };
             self.kernel_mode = true;
-            // Push flags to stack, with bit 15 set to the old mode.
-            // These operations must not fail; we have no way to recover.
-            let flags = self.flags | old_mode;
-            critical!(self.push(TypedValue::Half(flags)));
-            // Push the program counter to stack.
-            critical!(self.push(TypedValue::Word(self.program_counter)));
-            // Push the IMR to stack.
-            critical!(self.push(TypedValue::Half(self.imr)));
-            // Disable all interrupts.
-            self.imr = 0;
-            // Jump to the interrupt handler.
-            self.program_counter =
-                tv_into_v!(critical!(self.load(interrupt * 4, false, ValueType::Word)));
+            // The saved flags have bit 15 set to the old mode.
+            let flags = self.read_flags() | old_mode;
+            // Handlers are never traced.
+            self.trace = false;
+            // Disable all interrupts except those of a higher priority level,
+            // which may pre-empt this handler.
+            let imr_mask = higher_priority_mask(self.ipr, interrupt as usize);
+            let illegal_latched = self.interrupts.is_latched(INTERRUPT_ILLEGAL_OPERATION);
+            if self.enter_handler(interrupt, flags, imr_mask, &[]).is_err() {
+                // We couldn't enter the handler, e.g. because the kernel stack
+                // is unusable. Try the double fault handler instead, on the
+                // emergency stack, telling it what went wrong.
+                debug!("Double fault entering handler for interrupt {}.", interrupt);
+                // The failed access raised an illegal operation interrupt;
+                // the double fault supersedes it, so don't leave it latched.
+                if !illegal_latched {
+                    self.interrupts.clear(1 << INTERRUPT_ILLEGAL_OPERATION);
+                }
+                let old_kspr = self.kspr;
+                self.kspr = self.espr;
+                let extra = [old_kspr, interrupt];
+                if self
+                    .enter_handler(INTERRUPT_DOUBLE_FAULT, flags, 0, &extra)
+                    .is_err()
+                {
+                    // We have no way to recover.
+                    return Err(CPUError::FatalError(format!(
+                        "failed to enter the double fault handler after failing to \
+                         enter the handler for interrupt {} (KSPR {:#x}, ESPR {:#x})",
+                        interrupt, old_kspr, self.espr
+                    )));
+                }
+            }
         }
 
+        // Remember whether to trap after this instruction.
+        let tracing = self.trace && !self.kernel_mode;
+
         // Fetch next instruction.
         let opcode: u8 = fetch!(Byte);
         // Decode and execute instruction.
@@ -606,6 +855,7 @@ impl<D: DiskController> CPUInternal<D> {
                 self.program_counter = tv_into_v!(self.pop(ValueType::Word)?);
                 // Clear flags.
                 self.flags = 0;
+                self.trace = false;
                 // Enter user mode.
                 self.kernel_mode = false;
             }
@@ -616,22 +866,20 @@ impl<D: DiskController> CPUInternal<D> {
                 // Pop the IMR off the stack.
                 let imr: u16 = tv_into_v!(self.pop(ValueType::Half)?);
                 // Pop the program counter off the stack.
-                let pc: u32 = tv_into_v!(self.pop(ValueType::Word).map_err(|e| {
+                let pc: u32 = tv_into_v!(self.pop(ValueType::Word).inspect_err(|_| {
                     // Ensure this operation is atomic by undoing any changes.
                     // If the pop worked, push should too.
                     self.push(TypedValue::Half(imr))
                         .expect("Failed to clean up partially-failed IRETURN.");
-                    e
                 })?);
                 // Pop the flags off the stack.
-                let flags: u16 = tv_into_v!(self.pop(ValueType::Half).map_err(|e| {
+                let flags: u16 = tv_into_v!(self.pop(ValueType::Half).inspect_err(|_| {
                     // Ensure this operation is atomic by undoing any changes.
                     // If the pops worked, pushes should too.
                     self.push(TypedValue::Word(pc))
                         .expect("Failed to clean up partially-failed IRETURN.");
                     self.push(TypedValue::Half(imr))
                         .expect("Failed to clean up partially-failed IRETURN.");
-                    e
                 })?);
                 // If bit 15 is 0, enter user mode.
                 if (flags & 0b1000000000000000) == 0 {
@@ -640,7 +888,7 @@ impl<D: DiskController> CPUInternal<D> {
                 // Set the registers.
                 self.imr = imr;
                 self.program_counter = pc;
-                self.flags = flags & 0b0111111111111111;
+                self.write_flags(flags);
                 // If the next instruction is PAUSE, skip it.
                 skip_pause_next = true;
             }
@@ -953,6 +1201,7 @@ impl<D: DiskController> CPUInternal<D> {
                     TypedValue::Half(h) => TypedValue::Half(-(h as i16) as u16),
                     TypedValue::Word(w) => TypedValue::Word(-(w as i32) as u32),
                     TypedValue::Float(f) => TypedValue::Float(-f),
+                    TypedValue::Double(d) => TypedValue::Double(-d),
                 };
                 self.write_to_register(reg_ref, negated)?;
             }
@@ -1170,7 +1419,7 @@ impl<D: DiskController> CPUInternal<D> {
                         flags = make_flags_int!(not_x as i32, false, false);
                         TypedValue::Word(!x)
                     }
-                    TypedValue::Float(_) => unreachable!(),
+                    TypedValue::Float(_) | TypedValue::Double(_) => unreachable!(),
                 };
                 self.write_to_register(reg_ref, negated)?;
                 self.flags = flags;
@@ -1647,55 +1896,606 @@ impl<D: DiskController> CPUInternal<D> {
             0x6B => {
                 // SYSCALL
                 trace!("SYSCALL");
-                self.interrupt_tx.send(INTERRUPT_SYSCALL).unwrap();
+                self.instruction_syscall(0);
             }
             0x6C => {
                 // SCONVERT
                 trace!("SCONVERT");
                 let dest = fetch!(Byte);
                 let src = fetch!(Byte);
-                let dest_type = self.reg_ref_type(dest)?;
-                let src_type = self.reg_ref_type(src)?;
                 trace!("Signed conversion from {:#x} to {:#x}", src, dest);
-                if src_type == ValueType::Word && dest_type == ValueType::Float {
-                    // Signed integer to float.
-                    let u: u32 = tv_into_v!(self.read_from_register(src)?);
-                    let i: i32 = u as i32;
-                    let f: f32 = i as f32;
-                    self.write_to_register(dest, TypedValue::Float(f))?;
-                } else if src_type == ValueType::Float && dest_type == ValueType::Word {
-                    // Float to signed integer.
-                    let f: f32 = tv_into_v!(self.read_from_register(src)?);
-                    let i: i32 = f as i32;
-                    let u: u32 = i as u32;
-                    self.write_to_register(dest, TypedValue::Word(u))?;
-                } else {
-                    self.interrupt_tx.send(INTERRUPT_ILLEGAL_OPERATION).unwrap();
-                    return Err(CPUError::TryAgainError);
-                }
+                self.instruction_convert(dest, src, true)?;
             }
             0x6D => {
                 // UCONVERT
                 trace!("UCONVERT");
                 let dest = fetch!(Byte);
                 let src = fetch!(Byte);
-                let dest_type = self.reg_ref_type(dest)?;
-                let src_type = self.reg_ref_type(src)?;
                 trace!("Unsigned conversion from {:#x} to {:#x}", src, dest);
-                if src_type == ValueType::Word && dest_type == ValueType::Float {
-                    // Unsigned integer to float.
-                    let u: u32 = tv_into_v!(self.read_from_register(src)?);
-                    let f: f32 = u as f32;
-                    self.write_to_register(dest, TypedValue::Float(f))?;
-                } else if src_type == ValueType::Float && dest_type == ValueType::Word {
-                    // Float to unsigned integer.
-                    let f: f32 = tv_into_v!(self.read_from_register(src)?);
-                    let u: u32 = f as u32;
-                    self.write_to_register(dest, TypedValue::Word(u))?;
-                } else {
-                    self.interrupt_tx.send(INTERRUPT_ILLEGAL_OPERATION).unwrap();
-                    return Err(CPUError::TryAgainError);
-                }
+                self.instruction_convert(dest, src, false)?;
+            }
+            0x6E => {
+                // IPI literal
+                trace!("IPI literal");
+                privileged!(self)?;
+                let core = fetch!(Word);
+                trace!("IPI to core {}", core);
+                self.instruction_ipi(core)?;
+            }
+            0x6F => {
+                // IPI ref
+                trace!("IPI ref");
+                privileged!(self)?;
+                let reg_ref = fetch!(Byte);
+                let core = try_tv_into_v!(self.read_from_register(reg_ref)?);
+                trace!("IPI to core {}", core);
+                self.instruction_ipi(core)?;
+            }
+            0x70 => {
+                // CMPXCHG ref ref literal
+                trace!("CMPXCHG ref ref literal");
+                let expected_ref = fetch!(Byte);
+                let new_ref = fetch!(Byte);
+                check_same_type!(expected_ref, new_ref);
+                reject_float!(expected_ref);
+                let address = fetch!(Word);
+                trace!(
+                    "address {:#x}: expecting {:#x}, replacing with {:#x}",
+                    address,
+                    expected_ref,
+                    new_ref
+                );
+                self.instruction_cmpxchg(expected_ref, new_ref, address)?;
+            }
+            0x71 => {
+                // CMPXCHG ref ref ref
+                trace!("CMPXCHG ref ref ref");
+                let expected_ref = fetch!(Byte);
+                let new_ref = fetch!(Byte);
+                check_same_type!(expected_ref, new_ref);
+                reject_float!(expected_ref);
+                let address_ref = fetch!(Byte);
+                let address = try_tv_into_v!(self.read_from_register(address_ref)?);
+                trace!(
+                    "address {:#x}: expecting {:#x}, replacing with {:#x}",
+                    address,
+                    expected_ref,
+                    new_ref
+                );
+                self.instruction_cmpxchg(expected_ref, new_ref, address)?;
+            }
+            0x72 => {
+                // FETCHADD ref literal
+                trace!("FETCHADD ref literal");
+                let reg_ref = fetch!(Byte);
+                reject_float!(reg_ref);
+                let address = fetch!(Word);
+                trace!("register {:#x} to address {:#x}", reg_ref, address);
+                self.instruction_fetchadd(reg_ref, address)?;
+            }
+            0x73 => {
+                // FETCHADD ref ref
+                trace!("FETCHADD ref ref");
+                let reg_ref = fetch!(Byte);
+                reject_float!(reg_ref);
+                let address_ref = fetch!(Byte);
+                let address = try_tv_into_v!(self.read_from_register(address_ref)?);
+                trace!("register {:#x} to address {:#x}", reg_ref, address);
+                self.instruction_fetchadd(reg_ref, address)?;
+            }
+            0x74 => {
+                // LOAD ref ref+literal
+                trace!("LOAD ref ref+literal");
+                let reg_ref_dest = fetch!(Byte);
+                let reg_ref_base = fetch!(Byte);
+                let offset: u32 = fetch!(Word);
+                let base: u32 = try_tv_into_v!(self.read_from_register(reg_ref_base)?);
+                let address = base.wrapping_add(offset);
+                trace!("Dest: {:#x} Address: {:#x}", reg_ref_dest, address);
+                self.instruction_load(reg_ref_dest, address)?;
+            }
+            0x75 => {
+                // LOAD ref ref+ref*literal
+                trace!("LOAD ref ref+ref*literal");
+                let reg_ref_dest = fetch!(Byte);
+                let reg_ref_base = fetch!(Byte);
+                let reg_ref_index = fetch!(Byte);
+                let scale: u8 = fetch!(Byte);
+                let address = indexed_address!(reg_ref_base, reg_ref_index, scale);
+                trace!("Dest: {:#x} Address: {:#x}", reg_ref_dest, address);
+                self.instruction_load(reg_ref_dest, address)?;
+            }
+            0x76 => {
+                // STORE ref+literal ref
+                trace!("STORE ref+literal ref");
+                let reg_ref_base = fetch!(Byte);
+                let offset: u32 = fetch!(Word);
+                let reg_ref_source = fetch!(Byte);
+                let base: u32 = try_tv_into_v!(self.read_from_register(reg_ref_base)?);
+                let address = base.wrapping_add(offset);
+                trace!("Address: {:#x} Source: {:#x}", address, reg_ref_source);
+                self.instruction_store(address, reg_ref_source)?;
+            }
+            0x77 => {
+                // STORE ref+ref*literal ref
+                trace!("STORE ref+ref*literal ref");
+                let reg_ref_base = fetch!(Byte);
+                let reg_ref_index = fetch!(Byte);
+                let scale: u8 = fetch!(Byte);
+                let reg_ref_source = fetch!(Byte);
+                let address = indexed_address!(reg_ref_base, reg_ref_index, scale);
+                trace!("Address: {:#x} Source: {:#x}", address, reg_ref_source);
+                self.instruction_store(address, reg_ref_source)?;
+            }
+            0x78 => {
+                // JUMP relative
+                trace!("JUMP relative");
+                let offset: u32 = fetch!(Word);
+                let address = self.program_counter.wrapping_add(offset);
+                trace!("Jumping to {:#x}", address);
+                self.program_counter = address;
+            }
+            0x79 => {
+                // JEQUAL relative
+                cond_jump_relative!(self, jequal!(self))
+            }
+            0x7A => {
+                // JNOTEQUAL relative
+                cond_jump_relative!(self, jnotequal!(self))
+            }
+            0x7B => {
+                // SJGREATER relative
+                cond_jump_relative!(self, sjgreater!(self))
+            }
+            0x7C => {
+                // SJGREATEREQ relative
+                cond_jump_relative!(self, sjgreatereq!(self))
+            }
+            0x7D => {
+                // UJGREATER relative
+                cond_jump_relative!(self, ujgreater!(self))
+            }
+            0x7E => {
+                // UJGREATEREQ relative
+                cond_jump_relative!(self, ujgreatereq!(self))
+            }
+            0x7F => {
+                // SJLESSER relative
+                cond_jump_relative!(self, sjlesser!(self))
+            }
+            0x80 => {
+                // SJLESSEREQ relative
+                cond_jump_relative!(self, sjlessereq!(self))
+            }
+            0x81 => {
+                // UJLESSER relative
+                cond_jump_relative!(self, ujlesser!(self))
+            }
+            0x82 => {
+                // UJLESSEREQ relative
+                cond_jump_relative!(self, ujlessereq!(self))
+            }
+            0x83 => {
+                // CALL relative
+                trace!("CALL relative");
+                let offset: u32 = fetch!(Word);
+                let address = self.program_counter.wrapping_add(offset);
+                trace!("Calling {:#x}", address);
+                self.instruction_call(address)?;
+            }
+            0x84 => {
+                // LOADPC ref relative
+                trace!("LOADPC ref relative");
+                let reg_ref = fetch!(Byte);
+                let offset: u32 = fetch!(Word);
+                let address = self.program_counter.wrapping_add(offset);
+                trace!("Loading {:#x} into {:#x}", address, reg_ref);
+                self.write_to_register(reg_ref, TypedValue::Word(address))?;
+            }
+            0x85 => {
+                // SETEQUAL
+                cond_set!(self, "SETEQUAL", jequal!(self))
+            }
+            0x86 => {
+                // SETNOTEQUAL
+                cond_set!(self, "SETNOTEQUAL", jnotequal!(self))
+            }
+            0x87 => {
+                // SSETGREATER
+                cond_set!(self, "SSETGREATER", sjgreater!(self))
+            }
+            0x88 => {
+                // SSETGREATEREQ
+                cond_set!(self, "SSETGREATEREQ", sjgreatereq!(self))
+            }
+            0x89 => {
+                // USETGREATER
+                cond_set!(self, "USETGREATER", ujgreater!(self))
+            }
+            0x8A => {
+                // USETGREATEREQ
+                cond_set!(self, "USETGREATEREQ", ujgreatereq!(self))
+            }
+            0x8B => {
+                // SSETLESSER
+                cond_set!(self, "SSETLESSER", sjlesser!(self))
+            }
+            0x8C => {
+                // SSETLESSEREQ
+                cond_set!(self, "SSETLESSEREQ", sjlessereq!(self))
+            }
+            0x8D => {
+                // USETLESSER
+                cond_set!(self, "USETLESSER", ujlesser!(self))
+            }
+            0x8E => {
+                // USETLESSEREQ
+                cond_set!(self, "USETLESSEREQ", ujlessereq!(self))
+            }
+            0x8F => {
+                // CMOVEQUAL
+                cond_move!(self, "CMOVEQUAL", jequal!(self))
+            }
+            0x90 => {
+                // CMOVNOTEQUAL
+                cond_move!(self, "CMOVNOTEQUAL", jnotequal!(self))
+            }
+            0x91 => {
+                // SCMOVGREATER
+                cond_move!(self, "SCMOVGREATER", sjgreater!(self))
+            }
+            0x92 => {
+                // SCMOVGREATEREQ
+                cond_move!(self, "SCMOVGREATEREQ", sjgreatereq!(self))
+            }
+            0x93 => {
+                // UCMOVGREATER
+                cond_move!(self, "UCMOVGREATER", ujgreater!(self))
+            }
+            0x94 => {
+                // UCMOVGREATEREQ
+                cond_move!(self, "UCMOVGREATEREQ", ujgreatereq!(self))
+            }
+            0x95 => {
+                // SCMOVLESSER
+                cond_move!(self, "SCMOVLESSER", sjlesser!(self))
+            }
+            0x96 => {
+                // SCMOVLESSEREQ
+                cond_move!(self, "SCMOVLESSEREQ", sjlessereq!(self))
+            }
+            0x97 => {
+                // UCMOVLESSER
+                cond_move!(self, "UCMOVLESSER", ujlesser!(self))
+            }
+            0x98 => {
+                // UCMOVLESSEREQ
+                cond_move!(self, "UCMOVLESSEREQ", ujlessereq!(self))
+            }
+            0x99 => {
+                // POPCOUNT
+                trace!("POPCOUNT");
+                let reg_ref = fetch!(Byte);
+                reject_float!(reg_ref);
+                trace!("Count set bits in register {:#x}", reg_ref);
+                self.instruction_popcount(reg_ref)?;
+            }
+            0x9A => {
+                // LZCOUNT
+                trace!("LZCOUNT");
+                let reg_ref = fetch!(Byte);
+                reject_float!(reg_ref);
+                trace!("Count leading zeros in register {:#x}", reg_ref);
+                self.instruction_lzcount(reg_ref)?;
+            }
+            0x9B => {
+                // TZCOUNT
+                trace!("TZCOUNT");
+                let reg_ref = fetch!(Byte);
+                reject_float!(reg_ref);
+                trace!("Count trailing zeros in register {:#x}", reg_ref);
+                self.instruction_tzcount(reg_ref)?;
+            }
+            0x9C => {
+                // BITTEST literal
+                trace!("BITTEST literal");
+                let reg_ref = fetch!(Byte);
+                reject_float!(reg_ref);
+                let bit = fetch!(Byte);
+                trace!("Test bit {} of register {:#x}", bit, reg_ref);
+                self.instruction_bittest(reg_ref, bit)?;
+            }
+            0x9D => {
+                // BITTEST ref
+                trace!("BITTEST ref");
+                let reg_ref = fetch!(Byte);
+                reject_float!(reg_ref);
+                let bit_ref = fetch!(Byte);
+                let bit = try_tv_into_v!(self.read_from_register(bit_ref)?);
+                trace!("Test bit {} of register {:#x}", bit, reg_ref);
+                self.instruction_bittest(reg_ref, bit)?;
+            }
+            0x9E => {
+                // BITSET literal
+                trace!("BITSET literal");
+                let reg_ref = fetch!(Byte);
+                reject_float!(reg_ref);
+                let bit = fetch!(Byte);
+                trace!("Set bit {} of register {:#x}", bit, reg_ref);
+                self.instruction_bitset(reg_ref, bit)?;
+            }
+            0x9F => {
+                // BITSET ref
+                trace!("BITSET ref");
+                let reg_ref = fetch!(Byte);
+                reject_float!(reg_ref);
+                let bit_ref = fetch!(Byte);
+                let bit = try_tv_into_v!(self.read_from_register(bit_ref)?);
+                trace!("Set bit {} of register {:#x}", bit, reg_ref);
+                self.instruction_bitset(reg_ref, bit)?;
+            }
+            0xA0 => {
+                // BITCLEAR literal
+                trace!("BITCLEAR literal");
+                let reg_ref = fetch!(Byte);
+                reject_float!(reg_ref);
+                let bit = fetch!(Byte);
+                trace!("Clear bit {} of register {:#x}", bit, reg_ref);
+                self.instruction_bitclear(reg_ref, bit)?;
+            }
+            0xA1 => {
+                // BITCLEAR ref
+                trace!("BITCLEAR ref");
+                let reg_ref = fetch!(Byte);
+                reject_float!(reg_ref);
+                let bit_ref = fetch!(Byte);
+                let bit = try_tv_into_v!(self.read_from_register(bit_ref)?);
+                trace!("Clear bit {} of register {:#x}", bit, reg_ref);
+                self.instruction_bitclear(reg_ref, bit)?;
+            }
+            0xA2 => {
+                // BYTESWAP
+                trace!("BYTESWAP");
+                let reg_ref = fetch!(Byte);
+                reject_float!(reg_ref);
+                trace!("Byte-swap register {:#x}", reg_ref);
+                self.instruction_byteswap(reg_ref)?;
+            }
+            0xA3 => {
+                // UMULTWIDE literal
+                trace!("UMULTWIDE literal");
+                let high = fetch!(Byte);
+                let low = fetch!(Byte);
+                check_wide_registers!(high, low);
+                let value: u32 = fetch!(Word);
+                trace!(
+                    "Unsigned widening multiply registers {:#x}:{:#x} by {:#x}",
+                    high,
+                    low,
+                    value
+                );
+                self.instruction_umultwide(high, low, value)?;
+            }
+            0xA4 => {
+                // UMULTWIDE ref
+                trace!("UMULTWIDE ref");
+                let high = fetch!(Byte);
+                let low = fetch!(Byte);
+                check_wide_registers!(high, low);
+                let src = fetch!(Byte);
+                let value: u32 = try_tv_into_v!(self.read_from_register(src)?);
+                trace!(
+                    "Unsigned widening multiply registers {:#x}:{:#x} by {:#x}",
+                    high,
+                    low,
+                    value
+                );
+                self.instruction_umultwide(high, low, value)?;
+            }
+            0xA5 => {
+                // SMULTWIDE literal
+                trace!("SMULTWIDE literal");
+                let high = fetch!(Byte);
+                let low = fetch!(Byte);
+                check_wide_registers!(high, low);
+                let value: u32 = fetch!(Word);
+                trace!(
+                    "Signed widening multiply registers {:#x}:{:#x} by {:#x}",
+                    high,
+                    low,
+                    value
+                );
+                self.instruction_smultwide(high, low, value)?;
+            }
+            0xA6 => {
+                // SMULTWIDE ref
+                trace!("SMULTWIDE ref");
+                let high = fetch!(Byte);
+                let low = fetch!(Byte);
+                check_wide_registers!(high, low);
+                let src = fetch!(Byte);
+                let value: u32 = try_tv_into_v!(self.read_from_register(src)?);
+                trace!(
+                    "Signed widening multiply registers {:#x}:{:#x} by {:#x}",
+                    high,
+                    low,
+                    value
+                );
+                self.instruction_smultwide(high, low, value)?;
+            }
+            0xA7 => {
+                // UDIVWIDE literal
+                trace!("UDIVWIDE literal");
+                let high = fetch!(Byte);
+                let low = fetch!(Byte);
+                check_wide_registers!(high, low);
+                let value: u32 = fetch!(Word);
+                trace!(
+                    "Unsigned wide dividing registers {:#x}:{:#x} by {:#x}",
+                    high,
+                    low,
+                    value
+                );
+                self.instruction_udivwide(high, low, value)?;
+            }
+            0xA8 => {
+                // UDIVWIDE ref
+                trace!("UDIVWIDE ref");
+                let high = fetch!(Byte);
+                let low = fetch!(Byte);
+                check_wide_registers!(high, low);
+                let src = fetch!(Byte);
+                let value: u32 = try_tv_into_v!(self.read_from_register(src)?);
+                trace!(
+                    "Unsigned wide dividing registers {:#x}:{:#x} by {:#x}",
+                    high,
+                    low,
+                    value
+                );
+                self.instruction_udivwide(high, low, value)?;
+            }
+            0xA9 => {
+                // SDIVWIDE literal
+                trace!("SDIVWIDE literal");
+                let high = fetch!(Byte);
+                let low = fetch!(Byte);
+                check_wide_registers!(high, low);
+                let value: u32 = fetch!(Word);
+                trace!(
+                    "Signed wide dividing registers {:#x}:{:#x} by {:#x}",
+                    high,
+                    low,
+                    value
+                );
+                self.instruction_sdivwide(high, low, value)?;
+            }
+            0xAA => {
+                // SDIVWIDE ref
+                trace!("SDIVWIDE ref");
+                let high = fetch!(Byte);
+                let low = fetch!(Byte);
+                check_wide_registers!(high, low);
+                let src = fetch!(Byte);
+                let value: u32 = try_tv_into_v!(self.read_from_register(src)?);
+                trace!(
+                    "Signed wide dividing registers {:#x}:{:#x} by {:#x}",
+                    high,
+                    low,
+                    value
+                );
+                self.instruction_sdivwide(high, low, value)?;
+            }
+            0xAB => {
+                // SQRT
+                trace!("SQRT");
+                let reg_ref = fetch!(Byte);
+                require_float!(reg_ref);
+                trace!("Square root of register {:#x}", reg_ref);
+                self.instruction_sqrt(reg_ref)?;
+            }
+            0xAC => {
+                // ABS
+                trace!("ABS");
+                let reg_ref = fetch!(Byte);
+                require_float!(reg_ref);
+                trace!("Absolute value of register {:#x}", reg_ref);
+                self.instruction_abs(reg_ref)?;
+            }
+            0xAD => {
+                // MIN literal
+                trace!("MIN literal");
+                let reg_ref = fetch!(Byte);
+                require_float!(reg_ref);
+                let value = fetch_variable_size!(self.reg_ref_type(reg_ref)?);
+                trace!("Minimum of register {:#x} and {:?}", reg_ref, value);
+                self.instruction_min(reg_ref, value)?;
+            }
+            0xAE => {
+                // MIN ref
+                trace!("MIN ref");
+                let dest = fetch!(Byte);
+                require_float!(dest);
+                let src = fetch!(Byte);
+                check_same_type!(dest, src);
+                let value = self.read_from_register(src)?;
+                trace!("Minimum of register {:#x} and {:?}", dest, value);
+                self.instruction_min(dest, value)?;
+            }
+            0xAF => {
+                // MAX literal
+                trace!("MAX literal");
+                let reg_ref = fetch!(Byte);
+                require_float!(reg_ref);
+                let value = fetch_variable_size!(self.reg_ref_type(reg_ref)?);
+                trace!("Maximum of register {:#x} and {:?}", reg_ref, value);
+                self.instruction_max(reg_ref, value)?;
+            }
+            0xB0 => {
+                // MAX ref
+                trace!("MAX ref");
+                let dest = fetch!(Byte);
+                require_float!(dest);
+                let src = fetch!(Byte);
+                check_same_type!(dest, src);
+                let value = self.read_from_register(src)?;
+                trace!("Maximum of register {:#x} and {:?}", dest, value);
+                self.instruction_max(dest, value)?;
+            }
+            0xB1 => {
+                // FLOOR
+                trace!("FLOOR");
+                let reg_ref = fetch!(Byte);
+                require_float!(reg_ref);
+                trace!("Floor register {:#x}", reg_ref);
+                self.instruction_floor(reg_ref)?;
+            }
+            0xB2 => {
+                // CEIL
+                trace!("CEIL");
+                let reg_ref = fetch!(Byte);
+                require_float!(reg_ref);
+                trace!("Ceiling register {:#x}", reg_ref);
+                self.instruction_ceil(reg_ref)?;
+            }
+            0xB3 => {
+                // ROUND
+                trace!("ROUND");
+                let reg_ref = fetch!(Byte);
+                require_float!(reg_ref);
+                trace!("Round register {:#x}", reg_ref);
+                self.instruction_round(reg_ref)?;
+            }
+            0xB4 => {
+                // RAISE literal
+                trace!("RAISE literal");
+                privileged!(self)?;
+                let interrupt = fetch!(Word);
+                trace!("Raise interrupt {}", interrupt);
+                self.instruction_raise(interrupt)?;
+            }
+            0xB5 => {
+                // RAISE ref
+                trace!("RAISE ref");
+                privileged!(self)?;
+                let reg_ref = fetch!(Byte);
+                let interrupt = try_tv_into_v!(self.read_from_register(reg_ref)?);
+                trace!("Raise interrupt {}", interrupt);
+                self.instruction_raise(interrupt)?;
+            }
+            0xB6 => {
+                // SYSCALL literal
+                trace!("SYSCALL literal");
+                let number = fetch!(Word);
+                trace!("Syscall number {}", number);
+                self.instruction_syscall(number);
+            }
+            0xB7 => {
+                // SYSCALL ref
+                trace!("SYSCALL ref");
+                let reg_ref = fetch!(Byte);
+                let number = try_tv_into_v!(self.read_from_register(reg_ref)?);
+                trace!("Syscall number {}", number);
+                self.instruction_syscall(number);
             }
             _ => {
                 // Unrecognised
@@ -1704,10 +2504,29 @@ impl<D: DiskController> CPUInternal<D> {
                 return Err(CPUError::TryAgainError);
             }
         }
+        if tracing {
+            self.dsr |= DSR_TRACE;
+            self.interrupt_tx.send(INTERRUPT_DEBUG).unwrap();
+        }
         self.skip_pause = skip_pause_next;
         Ok(PostCycleAction::None)
     }
 
+    /// Read the flags register, including the trace flag.
+    fn read_flags(&self) -> u16 {
+        if self.trace {
+            self.flags | FLAG_TRACE
+        } else {
+            self.flags
+        }
+    }
+
+    /// Write the flags register, including the trace flag. Bit 15 is ignored.
+    fn write_flags(&mut self, flags: u16) {
+        self.flags = flags & 0b0111111111111111 & !FLAG_TRACE;
+        self.trace = flags & FLAG_TRACE != 0;
+    }
+
     fn instruction_load(&mut self, destination: u8, address: u32) -> CPUResult<()> {
         let dest_type = self.reg_ref_type(destination)?;
         let value = self.load(address, false, dest_type)?;
@@ -1721,9 +2540,98 @@ impl<D: DiskController> CPUInternal<D> {
 
     fn instruction_swap(&mut self, reg_ref: u8, address: u32) -> CPUResult<()> {
         let reg_value = self.read_from_register(reg_ref)?;
-        let mem_value = self.load(address, false, (&reg_value).into())?;
-        self.write_to_register(reg_ref, mem_value)?;
-        self.store(address, reg_value)
+        self.check_breakpoints(address, reg_value.size_in_bytes(), BREAK_READ | BREAK_WRITE)?;
+        // Hold the MMU lock throughout so no other core can access memory
+        // between the load and the store.
+        let mem_value = {
+            let mut mmu = self.mmu();
+            let mem_value = self.load_locked(&mut mmu, address, false, (&reg_value).into())?;
+            self.store_locked(&mut mmu, address, reg_value)?;
+            mem_value
+        };
+        self.write_to_register(reg_ref, mem_value)
+    }
+
+    fn instruction_cmpxchg(
+        &mut self,
+        expected_ref: u8,
+        new_ref: u8,
+        address: u32,
+    ) -> CPUResult<()> {
+        let expected_value = self.read_from_register(expected_ref)?;
+        let new_value = self.read_from_register(new_ref)?;
+        self.check_breakpoints(
+            address,
+            expected_value.size_in_bytes(),
+            BREAK_READ | BREAK_WRITE,
+        )?;
+        // Hold the MMU lock throughout so no other core can access memory
+        // between the comparison and the store.
+        let mem_value = {
+            let mut mmu = self.mmu();
+            let mem_value = self.load_locked(&mut mmu, address, false, (&expected_value).into())?;
+            if mem_value == expected_value {
+                self.store_locked(&mut mmu, address, new_value)?;
+            }
+            mem_value
+        };
+        if mem_value == expected_value {
+            self.flags |= FLAG_ZERO;
+            Ok(())
+        } else {
+            self.flags &= !FLAG_ZERO;
+            self.write_to_register(expected_ref, mem_value)
+        }
+    }
+
+    fn instruction_fetchadd(&mut self, reg_ref: u8, address: u32) -> CPUResult<()> {
+        let reg_value = self.read_from_register(reg_ref)?;
+        self.check_breakpoints(address, reg_value.size_in_bytes(), BREAK_READ | BREAK_WRITE)?;
+        // Hold the MMU lock throughout so no other core can access memory
+        // between the load and the store.
+        let mem_value = {
+            let mut mmu = self.mmu();
+            let mem_value = self.load_locked(&mut mmu, address, false, (&reg_value).into())?;
+            let sum = match (&mem_value, reg_value) {
+                (TypedValue::Byte(a), TypedValue::Byte(b)) => TypedValue::Byte(a.wrapping_add(b)),
+                (TypedValue::Half(a), TypedValue::Half(b)) => TypedValue::Half(a.wrapping_add(b)),
+                (TypedValue::Word(a), TypedValue::Word(b)) => TypedValue::Word(a.wrapping_add(b)),
+                _ => unreachable!(),
+            };
+            self.store_locked(&mut mmu, address, sum)?;
+            mem_value
+        };
+        self.write_to_register(reg_ref, mem_value)
+    }
+
+    fn instruction_syscall(&mut self, number: u32) {
+        self.scnr = number;
+        self.interrupt_tx.send(INTERRUPT_SYSCALL).unwrap();
+    }
+
+    fn instruction_ipi(&mut self, core: u32) -> CPUResult<()> {
+        match self.ipi_tx.get(core as usize) {
+            Some(ipi_tx) => {
+                ipi_tx.send(INTERRUPT_IPI).unwrap();
+                Ok(())
+            }
+            None => {
+                trace!("IPI to nonexistent core {}.", core);
+                self.interrupt_tx.send(INTERRUPT_ILLEGAL_OPERATION).unwrap();
+                Err(CPUError::TryAgainError)
+            }
+        }
+    }
+
+    fn instruction_raise(&mut self, interrupt: u32) -> CPUResult<()> {
+        if (interrupt as usize) < NUM_INTERRUPTS {
+            self.interrupt_tx.send(interrupt).unwrap();
+            Ok(())
+        } else {
+            trace!("Raise of nonexistent interrupt {}.", interrupt);
+            self.interrupt_tx.send(INTERRUPT_ILLEGAL_OPERATION).unwrap();
+            Err(CPUError::TryAgainError)
+        }
     }
 
     fn instruction_blockcopy(
@@ -1732,30 +2640,34 @@ impl<D: DiskController> CPUInternal<D> {
         dest_address: u32,
         source_address: u32,
     ) -> CPUResult<()> {
+        self.check_breakpoints(source_address, length, BREAK_READ)?;
+        self.check_breakpoints(dest_address, length, BREAK_WRITE)?;
         if self.kernel_mode {
             for i in 0..length {
-                let val = self.mmu.load_physical_8(source_address + i)?;
-                self.mmu.store_physical_8(dest_address + i, val)?;
+                let val = self.mmu().load_physical_8(source_address + i)?;
+                self.mmu().store_physical_8(dest_address + i, val)?;
             }
         } else {
             for i in 0..length {
                 let val = self
-                    .mmu
+                    .mmu()
                     .load_virtual_8(self.pdpr, source_address + i, false)?;
-                self.mmu.store_virtual_8(self.pdpr, dest_address + i, val)?;
+                self.mmu()
+                    .store_virtual_8(self.pdpr, dest_address + i, val)?;
             }
         }
         Ok(())
     }
 
     fn instruction_blockset(&mut self, length: u32, dest_address: u32, value: u8) -> CPUResult<()> {
+        self.check_breakpoints(dest_address, length, BREAK_WRITE)?;
         if self.kernel_mode {
             for i in 0..length {
-                self.mmu.store_physical_8(dest_address + i, value)?;
+                self.mmu().store_physical_8(dest_address + i, value)?;
             }
         } else {
             for i in 0..length {
-                self.mmu
+                self.mmu()
                     .store_virtual_8(self.pdpr, dest_address + i, value)?;
             }
         }
@@ -1811,6 +2723,65 @@ impl<D: DiskController> CPUInternal<D> {
         bin_op_unsigned!(self, reg_ref, value, overflowing_div)
     }
 
+    fn instruction_umultwide(&mut self, high: u8, low: u8, value: u32) -> CPUResult<()> {
+        // We assume that both registers have already been checked to be words.
+        let x = u32::try_from(self.read_from_register(low)?).unwrap();
+        let ans = u64::from(x) * u64::from(value);
+        let high_half = (ans >> 32) as u32;
+        self.write_to_register(low, TypedValue::Word(ans as u32))?;
+        self.write_to_register(high, TypedValue::Word(high_half))?;
+        self.flags = make_flags_int!(ans as i64, high_half != 0, false);
+        Ok(())
+    }
+
+    fn instruction_smultwide(&mut self, high: u8, low: u8, value: u32) -> CPUResult<()> {
+        // We assume that both registers have already been checked to be words.
+        let x = u32::try_from(self.read_from_register(low)?).unwrap();
+        let ans = i64::from(x as i32) * i64::from(value as i32);
+        let overflow = ans != i64::from(ans as i32);
+        self.write_to_register(low, TypedValue::Word(ans as u32))?;
+        self.write_to_register(high, TypedValue::Word((ans >> 32) as u32))?;
+        self.flags = make_flags_int!(ans, false, overflow);
+        Ok(())
+    }
+
+    fn instruction_udivwide(&mut self, high: u8, low: u8, value: u32) -> CPUResult<()> {
+        // We assume that both registers have already been checked to be words.
+        if value == 0 {
+            self.interrupt_tx.send(INTERRUPT_DIV_BY_0).unwrap();
+            return Err(CPUError::TryAgainError);
+        }
+        let x = u32::try_from(self.read_from_register(high)?).unwrap();
+        let y = u32::try_from(self.read_from_register(low)?).unwrap();
+        let dividend = (u64::from(x) << 32) | u64::from(y);
+        let quotient = dividend / u64::from(value);
+        let remainder = (dividend % u64::from(value)) as u32;
+        let carry = quotient > u64::from(u32::MAX);
+        self.write_to_register(low, TypedValue::Word(quotient as u32))?;
+        self.write_to_register(high, TypedValue::Word(remainder))?;
+        self.flags = make_flags_int!(quotient as i32, carry, false);
+        Ok(())
+    }
+
+    fn instruction_sdivwide(&mut self, high: u8, low: u8, value: u32) -> CPUResult<()> {
+        // We assume that both registers have already been checked to be words.
+        if value == 0 {
+            self.interrupt_tx.send(INTERRUPT_DIV_BY_0).unwrap();
+            return Err(CPUError::TryAgainError);
+        }
+        let x = u32::try_from(self.read_from_register(high)?).unwrap();
+        let y = u32::try_from(self.read_from_register(low)?).unwrap();
+        let dividend = ((u64::from(x) << 32) | u64::from(y)) as i64;
+        let divisor = i64::from(value as i32);
+        let (quotient, div_overflow) = dividend.overflowing_div(divisor);
+        let remainder = dividend.wrapping_rem(divisor) as i32;
+        let overflow = div_overflow || quotient != i64::from(quotient as i32);
+        self.write_to_register(low, TypedValue::Word(quotient as u32))?;
+        self.write_to_register(high, TypedValue::Word(remainder as u32))?;
+        self.flags = make_flags_int!(quotient as i32, false, overflow);
+        Ok(())
+    }
+
     fn instruction_srem(&mut self, reg_ref: u8, value: TypedValue) -> CPUResult<()> {
         // We assume that the value has already been checked to match the register type.
         if value.is_integer_zero() {
@@ -1878,7 +2849,7 @@ impl<D: DiskController> CPUInternal<D> {
                 self.write_to_register(reg_ref, TypedValue::Word(ans))?;
                 flags = make_flags_int!(ans as i32, carry, false);
             }
-            TypedValue::Float(_) => {
+            TypedValue::Float(_) | TypedValue::Double(_) => {
                 unreachable!()
             }
         }
@@ -1923,7 +2894,7 @@ impl<D: DiskController> CPUInternal<D> {
                 self.write_to_register(reg_ref, TypedValue::Word(ans))?;
                 flags = make_flags_int!(ans as i32, carry, false);
             }
-            TypedValue::Float(_) => {
+            TypedValue::Float(_) | TypedValue::Double(_) => {
                 unreachable!()
             }
         }
@@ -1965,7 +2936,7 @@ impl<D: DiskController> CPUInternal<D> {
                 self.write_to_register(reg_ref, TypedValue::Word(ans))?;
                 flags = make_flags_int!(ans as i32, carry, false);
             }
-            TypedValue::Float(_) => {
+            TypedValue::Float(_) | TypedValue::Double(_) => {
                 unreachable!()
             }
         }
@@ -2020,15 +2991,22 @@ impl<D: DiskController> CPUInternal<D> {
                 let ans = x - y;
                 make_flags_float!(ans)
             }
+            TypedValue::Double(x) => {
+                let y = f64::try_from(value).unwrap();
+                let ans = x - y;
+                make_flags_float!(ans)
+            }
         };
         Ok(())
     }
 
     fn instruction_blockcmp(&mut self, length: u32, source1: u32, source2: u32) -> CPUResult<()> {
+        self.check_breakpoints(source1, length, BREAK_READ)?;
+        self.check_breakpoints(source2, length, BREAK_READ)?;
         if self.kernel_mode {
             for i in 0..length {
-                let val1 = self.mmu.load_physical_8(source1 + i)?;
-                let val2 = self.mmu.load_physical_8(source2 + i)?;
+                let val1 = self.mmu().load_physical_8(source1 + i)?;
+                let val2 = self.mmu().load_physical_8(source2 + i)?;
                 if val1 > val2 {
                     self.flags = 0;
                     return Ok(());
@@ -2039,8 +3017,8 @@ impl<D: DiskController> CPUInternal<D> {
             }
         } else {
             for i in 0..length {
-                let val1 = self.mmu.load_virtual_8(self.pdpr, source1 + i, false)?;
-                let val2 = self.mmu.load_virtual_8(self.pdpr, source2 + i, false)?;
+                let val1 = self.mmu().load_virtual_8(self.pdpr, source1 + i, false)?;
+                let val2 = self.mmu().load_virtual_8(self.pdpr, source2 + i, false)?;
                 if val1 > val2 {
                     self.flags = 0;
                     return Ok(());
@@ -2054,12 +3032,128 @@ impl<D: DiskController> CPUInternal<D> {
         Ok(())
     }
 
+    fn instruction_popcount(&mut self, reg_ref: u8) -> CPUResult<()> {
+        un_op_count!(self, reg_ref, count_ones)
+    }
+
+    fn instruction_lzcount(&mut self, reg_ref: u8) -> CPUResult<()> {
+        un_op_count!(self, reg_ref, leading_zeros)
+    }
+
+    fn instruction_tzcount(&mut self, reg_ref: u8) -> CPUResult<()> {
+        un_op_count!(self, reg_ref, trailing_zeros)
+    }
+
+    fn instruction_bittest(&mut self, reg_ref: u8, bit: u8) -> CPUResult<()> {
+        bin_op_bit!(self, reg_ref, bit, None)
+    }
+
+    fn instruction_bitset(&mut self, reg_ref: u8, bit: u8) -> CPUResult<()> {
+        bin_op_bit!(self, reg_ref, bit, Some(true))
+    }
+
+    fn instruction_bitclear(&mut self, reg_ref: u8, bit: u8) -> CPUResult<()> {
+        bin_op_bit!(self, reg_ref, bit, Some(false))
+    }
+
+    fn instruction_byteswap(&mut self, reg_ref: u8) -> CPUResult<()> {
+        un_op_bitwise!(self, reg_ref, swap_bytes)
+    }
+
+    fn instruction_set(&mut self, reg_ref: u8, condition: bool) -> CPUResult<()> {
+        let value = match self.reg_ref_type(reg_ref)? {
+            ValueType::Byte => TypedValue::Byte(condition.into()),
+            ValueType::Half => TypedValue::Half(condition.into()),
+            ValueType::Word => TypedValue::Word(condition.into()),
+            ValueType::Float | ValueType::Double => unreachable!(),
+        };
+        self.write_to_register(reg_ref, value)
+    }
+
+    fn instruction_sqrt(&mut self, reg_ref: u8) -> CPUResult<()> {
+        un_op_float!(self, reg_ref, sqrt)
+    }
+
+    fn instruction_abs(&mut self, reg_ref: u8) -> CPUResult<()> {
+        un_op_float!(self, reg_ref, abs)
+    }
+
+    fn instruction_floor(&mut self, reg_ref: u8) -> CPUResult<()> {
+        un_op_float!(self, reg_ref, floor)
+    }
+
+    fn instruction_ceil(&mut self, reg_ref: u8) -> CPUResult<()> {
+        un_op_float!(self, reg_ref, ceil)
+    }
+
+    fn instruction_round(&mut self, reg_ref: u8) -> CPUResult<()> {
+        un_op_float!(self, reg_ref, round)
+    }
+
+    fn instruction_min(&mut self, reg_ref: u8, value: TypedValue) -> CPUResult<()> {
+        // We assume that the value has already been checked to match the register type.
+        bin_op_float!(self, reg_ref, value, min)
+    }
+
+    fn instruction_max(&mut self, reg_ref: u8, value: TypedValue) -> CPUResult<()> {
+        // We assume that the value has already been checked to match the register type.
+        bin_op_float!(self, reg_ref, value, max)
+    }
+
+    fn instruction_convert(&mut self, dest: u8, src: u8, signed: bool) -> CPUResult<()> {
+        let value = self.read_from_register(src)?;
+        let (converted, exceptions) = match (value, self.reg_ref_type(dest)?) {
+            (TypedValue::Word(u), ValueType::Float) => {
+                let (f, exceptions) = fpu::from_word(u, signed);
+                (TypedValue::Float(f), exceptions)
+            }
+            (TypedValue::Word(u), ValueType::Double) => {
+                let (d, exceptions) = fpu::from_word(u, signed);
+                (TypedValue::Double(d), exceptions)
+            }
+            (TypedValue::Float(f), ValueType::Word) => {
+                let (u, exceptions) = fpu::to_word(f, signed);
+                (TypedValue::Word(u), exceptions)
+            }
+            (TypedValue::Double(d), ValueType::Word) => {
+                let (u, exceptions) = fpu::to_word(d, signed);
+                (TypedValue::Word(u), exceptions)
+            }
+            (TypedValue::Float(f), ValueType::Double) => {
+                let (d, exceptions) = fpu::widen(f);
+                (TypedValue::Double(d), exceptions)
+            }
+            (TypedValue::Double(d), ValueType::Float) => {
+                let (f, exceptions) = fpu::narrow(d);
+                (TypedValue::Float(f), exceptions)
+            }
+            _ => {
+                self.interrupt_tx.send(INTERRUPT_ILLEGAL_OPERATION).unwrap();
+                return Err(CPUError::TryAgainError);
+            }
+        };
+        self.raise_float_exceptions(exceptions)?;
+        self.write_to_register(dest, converted)
+    }
+
     fn instruction_call(&mut self, address: u32) -> CPUResult<()> {
         self.push(TypedValue::Word(self.program_counter))?;
         self.program_counter = address;
         Ok(())
     }
 
+    /// Record the given floating-point exceptions in the FPSR. If any of them
+    /// are enabled, return with a floating-point interrupt, leaving the
+    /// instruction incomplete.
+    fn raise_float_exceptions(&mut self, exceptions: u16) -> CPUResult<()> {
+        self.fpsr |= exceptions;
+        if exceptions & (self.fpsr >> FPSR_ENABLE_SHIFT) != 0 {
+            self.interrupt_tx.send(INTERRUPT_FLOATING_POINT).unwrap();
+            return Err(CPUError::TryAgainError);
+        }
+        Ok(())
+    }
+
     /// Get the type of a register from its reference.
     fn reg_ref_type(&self, reg_ref: u8) -> CPUResult<ValueType> {
         if reg_ref < 0x08 {
@@ -2083,9 +3177,27 @@ impl<D: DiskController> CPUInternal<D> {
         } else if reg_ref == 0x24 {
             // IMR
             Ok(ValueType::Half)
-        } else if reg_ref == 0x25 {
-            // PFSR
+        } else if reg_ref < 0x27 {
+            // PFSR, CID
+            Ok(ValueType::Word)
+        } else if reg_ref == 0x27 {
+            // FPSR
+            Ok(ValueType::Half)
+        } else if reg_ref < 0x30 {
+            // d0-d7
+            Ok(ValueType::Double)
+        } else if reg_ref < 0x32 {
+            // IVBR, IPR
             Ok(ValueType::Word)
+        } else if reg_ref == 0x32 {
+            // IPND
+            Ok(ValueType::Half)
+        } else if reg_ref < 0x3A {
+            // SCNR, ESPR, DA0-DA3, DCR
+            Ok(ValueType::Word)
+        } else if reg_ref == 0x3A {
+            // DSR
+            Ok(ValueType::Half)
         } else {
             trace!("Invalid register reference: {:#x}.", reg_ref);
             self.interrupt_tx.send(INTERRUPT_ILLEGAL_OPERATION).unwrap();
@@ -2126,8 +3238,7 @@ impl<D: DiskController> CPUInternal<D> {
         } else if reg_ref == 0x20 {
             // FLAGS
             if let TypedValue::Half(h) = value {
-                let masked = h & 0b0111111111111111; // Ignore bit 15.
-                self.flags = masked;
+                self.write_flags(h);
                 return Ok(());
             }
         } else if reg_ref == 0x21 {
@@ -2162,6 +3273,80 @@ impl<D: DiskController> CPUInternal<D> {
             trace!("Illegal write to PFSR.");
             self.interrupt_tx.send(INTERRUPT_ILLEGAL_OPERATION).unwrap();
             return Err(CPUError::TryAgainError);
+        } else if reg_ref == 0x26 {
+            // CID
+            trace!("Illegal write to CID.");
+            self.interrupt_tx.send(INTERRUPT_ILLEGAL_OPERATION).unwrap();
+            return Err(CPUError::TryAgainError);
+        } else if reg_ref == 0x27 {
+            // FPSR
+            if let TypedValue::Half(h) = value {
+                self.fpsr = h & FPSR_MASK; // Ignore reserved bits.
+                return Ok(());
+            }
+        } else if reg_ref < 0x30 {
+            // d0-d7
+            if let TypedValue::Double(d) = value {
+                let index = (reg_ref - 0x28) as usize;
+                self.d[index] = d;
+                return Ok(());
+            }
+        } else if reg_ref == 0x30 {
+            // IVBR
+            if let TypedValue::Word(w) = value {
+                privileged!(self)?;
+                self.ivbr = w;
+                return Ok(());
+            }
+        } else if reg_ref == 0x31 {
+            // IPR
+            if let TypedValue::Word(w) = value {
+                privileged!(self)?;
+                self.ipr = w;
+                return Ok(());
+            }
+        } else if reg_ref == 0x32 {
+            // IPND: writing a 1 bit discards that latched interrupt.
+            if let TypedValue::Half(h) = value {
+                privileged!(self)?;
+                self.interrupts.clear(h);
+                return Ok(());
+            }
+        } else if reg_ref == 0x33 {
+            // SCNR
+            if let TypedValue::Word(w) = value {
+                privileged!(self)?;
+                self.scnr = w;
+                return Ok(());
+            }
+        } else if reg_ref == 0x34 {
+            // ESPR
+            if let TypedValue::Word(w) = value {
+                privileged!(self)?;
+                self.espr = w;
+                return Ok(());
+            }
+        } else if reg_ref < 0x39 {
+            // DA0-DA3
+            if let TypedValue::Word(w) = value {
+                privileged!(self)?;
+                self.da[(reg_ref - 0x35) as usize] = w;
+                return Ok(());
+            }
+        } else if reg_ref == 0x39 {
+            // DCR
+            if let TypedValue::Word(w) = value {
+                privileged!(self)?;
+                self.dcr = w & DCR_MASK; // Ignore reserved bits.
+                return Ok(());
+            }
+        } else if reg_ref == 0x3A {
+            // DSR
+            if let TypedValue::Half(h) = value {
+                privileged!(self)?;
+                self.dsr = h & DSR_MASK; // Ignore reserved bits.
+                return Ok(());
+            }
         } else {
             trace!("Invalid register reference: {:#x}", reg_ref);
             self.interrupt_tx.send(INTERRUPT_ILLEGAL_OPERATION).unwrap();
@@ -2192,7 +3377,7 @@ impl<D: DiskController> CPUInternal<D> {
             Ok(TypedValue::Float(self.f[(reg_ref - 0x18) as usize]))
         } else if reg_ref == 0x20 {
             // FLAGS
-            Ok(TypedValue::Half(self.flags))
+            Ok(TypedValue::Half(self.read_flags()))
         } else if reg_ref == 0x21 {
             // USPR
             Ok(TypedValue::Word(self.uspr))
@@ -2211,7 +3396,48 @@ impl<D: DiskController> CPUInternal<D> {
         } else if reg_ref == 0x25 {
             // PFSR
             privileged!(self)?;
-            Ok(TypedValue::Word(self.mmu.page_fault_status_register()))
+            Ok(TypedValue::Word(self.mmu().page_fault_status_register()))
+        } else if reg_ref == 0x26 {
+            // CID
+            Ok(TypedValue::Word(self.core_id as u32))
+        } else if reg_ref == 0x27 {
+            // FPSR
+            Ok(TypedValue::Half(self.fpsr))
+        } else if reg_ref < 0x30 {
+            // d0-d7
+            Ok(TypedValue::Double(self.d[(reg_ref - 0x28) as usize]))
+        } else if reg_ref == 0x30 {
+            // IVBR
+            privileged!(self)?;
+            Ok(TypedValue::Word(self.ivbr))
+        } else if reg_ref == 0x31 {
+            // IPR
+            privileged!(self)?;
+            Ok(TypedValue::Word(self.ipr))
+        } else if reg_ref == 0x32 {
+            // IPND
+            privileged!(self)?;
+            Ok(TypedValue::Half(self.interrupts.pending()))
+        } else if reg_ref == 0x33 {
+            // SCNR
+            privileged!(self)?;
+            Ok(TypedValue::Word(self.scnr))
+        } else if reg_ref == 0x34 {
+            // ESPR
+            privileged!(self)?;
+            Ok(TypedValue::Word(self.espr))
+        } else if reg_ref < 0x39 {
+            // DA0-DA3
+            privileged!(self)?;
+            Ok(TypedValue::Word(self.da[(reg_ref - 0x35) as usize]))
+        } else if reg_ref == 0x39 {
+            // DCR
+            privileged!(self)?;
+            Ok(TypedValue::Word(self.dcr))
+        } else if reg_ref == 0x3A {
+            // DSR
+            privileged!(self)?;
+            Ok(TypedValue::Half(self.dsr))
         } else {
             trace!("Invalid register reference: {:#x}", reg_ref);
             self.interrupt_tx.send(INTERRUPT_ILLEGAL_OPERATION).unwrap();
@@ -2219,28 +3445,83 @@ impl<D: DiskController> CPUInternal<D> {
         }
     }
 
+    /// Lock the shared MMU, selecting this core for any faults.
+    fn mmu(&self) -> MutexGuard<'_, MMU<D>> {
+        let mut mmu = self.mmu.lock().unwrap();
+        mmu.select_core(self.core_id);
+        mmu
+    }
+
     /// Store the given value to the given memory address.
     fn store(&mut self, address: u32, value: TypedValue) -> CPUResult<()> {
+        self.check_breakpoints(address, value.size_in_bytes(), BREAK_WRITE)?;
+        self.store_locked(&mut self.mmu(), address, value)
+    }
+
+    /// Load a value of the given size from the given memory address.
+    fn load(
+        &mut self,
+        address: u32,
+        is_fetch: bool,
+        value_type: ValueType,
+    ) -> CPUResult<TypedValue> {
+        let condition = if is_fetch { BREAK_EXECUTE } else { BREAK_READ };
+        self.check_breakpoints(address, value_type.size_in_bytes(), condition)?;
+        self.load_locked(&mut self.mmu(), address, is_fetch, value_type)
+    }
+
+    /// Check an access of `size` bytes at `address` against the breakpoints.
+    /// If any of them match the given condition, record them in the DSR and
+    /// raise a debug interrupt instead of allowing the access. Breakpoints
+    /// only apply in user mode.
+    fn check_breakpoints(&mut self, address: u32, size: u32, condition: u32) -> CPUResult<()> {
+        if self.kernel_mode || size == 0 {
+            return Ok(());
+        }
+        let mut hits = 0;
+        for (i, &start) in self.da.iter().enumerate() {
+            let control = self.dcr >> (i * 8);
+            if control & condition == 0 {
+                continue;
+            }
+            let length = 1 << ((control >> 3) & 0b11);
+            // Compare offsets rather than ends so that ranges can wrap around.
+            if address.wrapping_sub(start) < length || start.wrapping_sub(address) < size {
+                hits |= 1 << i;
+            }
+        }
+        if hits == 0 {
+            Ok(())
+        } else {
+            trace!("Breakpoint hit: {:#x}", hits);
+            self.dsr |= hits;
+            self.interrupt_tx.send(INTERRUPT_DEBUG).unwrap();
+            Err(CPUError::TryAgainError)
+        }
+    }
+
+    /// Like `store`, but using an MMU that is already locked.
+    fn store_locked(&self, mmu: &mut MMU<D>, address: u32, value: TypedValue) -> CPUResult<()> {
         match value {
             TypedValue::Byte(b) => {
                 if self.kernel_mode {
-                    self.mmu.store_physical_8(address, b)
+                    mmu.store_physical_8(address, b)
                 } else {
-                    self.mmu.store_virtual_8(self.pdpr, address, b)
+                    mmu.store_virtual_8(self.pdpr, address, b)
                 }
             }
             TypedValue::Half(h) => {
                 if self.kernel_mode {
-                    self.mmu.store_physical_16(address, h)
+                    mmu.store_physical_16(address, h)
                 } else {
-                    self.mmu.store_virtual_16(self.pdpr, address, h)
+                    mmu.store_virtual_16(self.pdpr, address, h)
                 }
             }
             TypedValue::Word(w) => {
                 if self.kernel_mode {
-                    self.mmu.store_physical_32(address, w)
+                    mmu.store_physical_32(address, w)
                 } else {
-                    self.mmu.store_virtual_32(self.pdpr, address, w)
+                    mmu.store_virtual_32(self.pdpr, address, w)
                 }
             }
             TypedValue::Float(f) => {
@@ -2248,17 +3529,26 @@ impl<D: DiskController> CPUInternal<D> {
                 // This is exactly what we want to let us store float values in RAM.
                 let converted = f.to_bits();
                 if self.kernel_mode {
-                    self.mmu.store_physical_32(address, converted)
+                    mmu.store_physical_32(address, converted)
+                } else {
+                    mmu.store_virtual_32(self.pdpr, address, converted)
+                }
+            }
+            TypedValue::Double(d) => {
+                let converted = d.to_bits();
+                if self.kernel_mode {
+                    mmu.store_physical_64(address, converted)
                 } else {
-                    self.mmu.store_virtual_32(self.pdpr, address, converted)
+                    mmu.store_virtual_64(self.pdpr, address, converted)
                 }
             }
         }
     }
 
-    /// Load a value of the given size from the given memory address.
-    fn load(
-        &mut self,
+    /// Like `load`, but using an MMU that is already locked.
+    fn load_locked(
+        &self,
+        mmu: &mut MMU<D>,
         address: u32,
         is_fetch: bool,
         value_type: ValueType,
@@ -2266,42 +3556,46 @@ impl<D: DiskController> CPUInternal<D> {
         match value_type {
             ValueType::Byte => {
                 if self.kernel_mode {
-                    self.mmu.load_physical_8(address).map(TypedValue::Byte)
+                    mmu.load_physical_8(address).map(TypedValue::Byte)
                 } else {
-                    self.mmu
-                        .load_virtual_8(self.pdpr, address, is_fetch)
+                    mmu.load_virtual_8(self.pdpr, address, is_fetch)
                         .map(TypedValue::Byte)
                 }
             }
             ValueType::Half => {
                 if self.kernel_mode {
-                    self.mmu.load_physical_16(address).map(TypedValue::Half)
+                    mmu.load_physical_16(address).map(TypedValue::Half)
                 } else {
-                    self.mmu
-                        .load_virtual_16(self.pdpr, address, is_fetch)
+                    mmu.load_virtual_16(self.pdpr, address, is_fetch)
                         .map(TypedValue::Half)
                 }
             }
             ValueType::Word => {
                 if self.kernel_mode {
-                    self.mmu.load_physical_32(address).map(TypedValue::Word)
+                    mmu.load_physical_32(address).map(TypedValue::Word)
                 } else {
-                    self.mmu
-                        .load_virtual_32(self.pdpr, address, is_fetch)
+                    mmu.load_virtual_32(self.pdpr, address, is_fetch)
                         .map(TypedValue::Word)
                 }
             }
             ValueType::Float => {
                 if self.kernel_mode {
-                    self.mmu
-                        .load_physical_32(address)
+                    mmu.load_physical_32(address)
                         .map(|f| TypedValue::Float(f32::from_bits(f)))
                 } else {
-                    self.mmu
-                        .load_virtual_32(self.pdpr, address, is_fetch)
+                    mmu.load_virtual_32(self.pdpr, address, is_fetch)
                         .map(|f| TypedValue::Float(f32::from_bits(f)))
                 }
             }
+            ValueType::Double => {
+                if self.kernel_mode {
+                    mmu.load_physical_64(address)
+                        .map(|d| TypedValue::Double(f64::from_bits(d)))
+                } else {
+                    mmu.load_virtual_64(self.pdpr, address, is_fetch)
+                        .map(|d| TypedValue::Double(f64::from_bits(d)))
+                }
+            }
         }
     }
 
@@ -2316,6 +3610,50 @@ impl<D: DiskController> CPUInternal<D> {
         }
     }
 
+    /// Enter the handler for the given interrupt: push the given extra words,
+    /// the flags, the program counter, and the IMR to the kernel stack, mask
+    /// the IMR, and jump to the handler. If this fails, the KSPR is restored
+    /// and nothing else is changed.
+    fn enter_handler(
+        &mut self,
+        interrupt: u32,
+        flags: u16,
+        imr_mask: u16,
+        extra: &[u32],
+    ) -> CPUResult<()> {
+        let kspr = self.kspr;
+        match self.push_interrupt_frame(interrupt, flags, extra) {
+            Ok(handler) => {
+                self.imr &= imr_mask;
+                self.program_counter = handler;
+                Ok(())
+            }
+            Err(e) => {
+                self.kspr = kspr;
+                Err(e)
+            }
+        }
+    }
+
+    /// Push an interrupt stack frame as described in `enter_handler`, and
+    /// return the address of the handler.
+    fn push_interrupt_frame(
+        &mut self,
+        interrupt: u32,
+        flags: u16,
+        extra: &[u32],
+    ) -> CPUResult<u32> {
+        for &word in extra {
+            self.push(TypedValue::Word(word))?;
+        }
+        self.push(TypedValue::Half(flags))?;
+        self.push(TypedValue::Word(self.program_counter))?;
+        self.push(TypedValue::Half(self.imr))?;
+        let handler_address = self.ivbr.wrapping_add(interrupt * 4);
+        let handler = self.load(handler_address, false, ValueType::Word)?;
+        Ok(handler.try_into().unwrap())
+    }
+
     /// Pop the given value from the stack.
     fn pop(&mut self, value_type: ValueType) -> CPUResult<TypedValue> {
         if self.kernel_mode {
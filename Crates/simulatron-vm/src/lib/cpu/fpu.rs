@@ -0,0 +1,299 @@
+use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
+
+// Floating-point operations that also report the IEEE 754 exceptions they
+// raise, as bits of the FPSR.
+pub const FP_INVALID: u16 = 0x01;
+pub const FP_DIV_BY_0: u16 = 0x02;
+pub const FP_OVERFLOW: u16 = 0x04;
+pub const FP_UNDERFLOW: u16 = 0x08;
+pub const FP_INEXACT: u16 = 0x10;
+
+// The FPSR holds the exception bits in its low byte, and the corresponding
+// enable bits in its high byte.
+pub const FPSR_MASK: u16 = 0x1F1F;
+pub const FPSR_ENABLE_SHIFT: u16 = 8;
+
+/// The floating-point types that Simulatron supports.
+pub trait Float:
+    Copy
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Rem<Output = Self>
+    + Neg<Output = Self>
+{
+    const ZERO: Self;
+    fn is_nan(self) -> bool;
+    fn is_finite(self) -> bool;
+    fn is_infinite(self) -> bool;
+    fn is_subnormal(self) -> bool;
+    fn sqrt(self) -> Self;
+    fn abs(self) -> Self;
+    fn floor(self) -> Self;
+    fn ceil(self) -> Self;
+    fn round_ties_even(self) -> Self;
+    fn min(self, other: Self) -> Self;
+    fn max(self, other: Self) -> Self;
+    fn from_i32(i: i32) -> Self;
+    fn from_u32(u: u32) -> Self;
+    fn to_f64(self) -> f64;
+    /// Is `a * b` exactly equal to `c`?
+    fn product_is(a: Self, b: Self, c: Self) -> bool;
+}
+
+macro_rules! impl_float {
+    ($t:ident) => {
+        const ZERO: Self = 0.0;
+        fn is_nan(self) -> bool {
+            $t::is_nan(self)
+        }
+        fn is_finite(self) -> bool {
+            $t::is_finite(self)
+        }
+        fn is_infinite(self) -> bool {
+            $t::is_infinite(self)
+        }
+        fn is_subnormal(self) -> bool {
+            $t::is_subnormal(self)
+        }
+        fn sqrt(self) -> Self {
+            $t::sqrt(self)
+        }
+        fn abs(self) -> Self {
+            $t::abs(self)
+        }
+        fn floor(self) -> Self {
+            $t::floor(self)
+        }
+        fn ceil(self) -> Self {
+            $t::ceil(self)
+        }
+        fn round_ties_even(self) -> Self {
+            $t::round_ties_even(self)
+        }
+        fn min(self, other: Self) -> Self {
+            $t::min(self, other)
+        }
+        fn max(self, other: Self) -> Self {
+            $t::max(self, other)
+        }
+        fn from_i32(i: i32) -> Self {
+            i as $t
+        }
+        fn from_u32(u: u32) -> Self {
+            u as $t
+        }
+        fn to_f64(self) -> f64 {
+            self as f64
+        }
+    };
+}
+
+impl Float for f32 {
+    impl_float!(f32);
+    fn product_is(a: Self, b: Self, c: Self) -> bool {
+        // The product of two binary32 values is always exact in binary64.
+        f64::from(a) * f64::from(b) == f64::from(c)
+    }
+}
+
+impl Float for f64 {
+    impl_float!(f64);
+    fn product_is(a: Self, b: Self, c: Self) -> bool {
+        // A fused multiply-add recovers the rounding error of the product.
+        a.mul_add(b, -c) == 0.0
+    }
+}
+
+/// Work out the exceptions raised by an operation from its operands and
+/// result, given whether the result was rounded and whether it was a division
+/// of a finite non-zero number by zero.
+fn exceptions<T: Float>(
+    nan_operand: bool,
+    finite_operands: bool,
+    ans: T,
+    inexact: bool,
+    div_by_zero: bool,
+) -> u16 {
+    if ans.is_nan() {
+        return if nan_operand { 0 } else { FP_INVALID };
+    }
+    if div_by_zero {
+        return FP_DIV_BY_0;
+    }
+    if ans.is_infinite() && finite_operands {
+        return FP_OVERFLOW | FP_INEXACT;
+    }
+    if inexact {
+        // Tininess is detected after rounding.
+        if ans == T::ZERO || ans.is_subnormal() {
+            FP_UNDERFLOW | FP_INEXACT
+        } else {
+            FP_INEXACT
+        }
+    } else {
+        0
+    }
+}
+
+/// Work out the exceptions raised by a binary operation.
+fn binary_exceptions<T: Float>(x: T, y: T, ans: T, inexact: bool, div_by_zero: bool) -> u16 {
+    exceptions(
+        x.is_nan() || y.is_nan(),
+        x.is_finite() && y.is_finite(),
+        ans,
+        inexact,
+        div_by_zero,
+    )
+}
+
+pub fn add<T: Float>(x: T, y: T) -> (T, u16) {
+    let ans = x + y;
+    let inexact = if ans.is_finite() {
+        // Knuth's TwoSum: recover the rounding error exactly.
+        let y_part = ans - x;
+        let error = (x - (ans - y_part)) + (y - y_part);
+        error != T::ZERO
+    } else {
+        false
+    };
+    (ans, binary_exceptions(x, y, ans, inexact, false))
+}
+
+pub fn sub<T: Float>(x: T, y: T) -> (T, u16) {
+    add(x, -y)
+}
+
+pub fn mul<T: Float>(x: T, y: T) -> (T, u16) {
+    let ans = x * y;
+    let inexact = ans.is_finite() && !T::product_is(x, y, ans);
+    (ans, binary_exceptions(x, y, ans, inexact, false))
+}
+
+pub fn div<T: Float>(x: T, y: T) -> (T, u16) {
+    let ans = x / y;
+    let div_by_zero = y == T::ZERO && x.is_finite() && x != T::ZERO;
+    let inexact = x.is_finite()
+        && y.is_finite()
+        && y != T::ZERO
+        && ans.is_finite()
+        && !T::product_is(ans, y, x);
+    (ans, binary_exceptions(x, y, ans, inexact, div_by_zero))
+}
+
+pub fn rem<T: Float>(x: T, y: T) -> (T, u16) {
+    // The remainder is always exact.
+    let ans = x % y;
+    (ans, binary_exceptions(x, y, ans, false, false))
+}
+
+pub fn sqrt<T: Float>(x: T) -> (T, u16) {
+    let ans = x.sqrt();
+    let inexact = ans.is_finite() && !T::product_is(ans, ans, x);
+    (
+        ans,
+        exceptions(x.is_nan(), x.is_finite(), ans, inexact, false),
+    )
+}
+
+pub fn abs<T: Float>(x: T) -> (T, u16) {
+    (x.abs(), 0)
+}
+
+pub fn floor<T: Float>(x: T) -> (T, u16) {
+    (x.floor(), 0)
+}
+
+pub fn ceil<T: Float>(x: T) -> (T, u16) {
+    (x.ceil(), 0)
+}
+
+pub fn round<T: Float>(x: T) -> (T, u16) {
+    (x.round_ties_even(), 0)
+}
+
+pub fn min<T: Float>(x: T, y: T) -> (T, u16) {
+    (x.min(y), 0)
+}
+
+pub fn max<T: Float>(x: T, y: T) -> (T, u16) {
+    (x.max(y), 0)
+}
+
+fn from_signed<T: Float>(i: i32) -> (T, u16) {
+    let ans = T::from_i32(i);
+    let inexact = ans.to_f64() != f64::from(i);
+    (ans, if inexact { FP_INEXACT } else { 0 })
+}
+
+fn from_unsigned<T: Float>(u: u32) -> (T, u16) {
+    let ans = T::from_u32(u);
+    let inexact = ans.to_f64() != f64::from(u);
+    (ans, if inexact { FP_INEXACT } else { 0 })
+}
+
+/// Convert to a signed integer, rounding towards zero. Out-of-range values
+/// and NaNs saturate and are invalid.
+fn to_signed<T: Float>(f: T) -> (i32, u16) {
+    let f = f.to_f64();
+    let ans = f as i32;
+    if f.is_nan() || f <= i32::MIN as f64 - 1.0 || f >= -(i32::MIN as f64) {
+        (ans, FP_INVALID)
+    } else if f.trunc() != f {
+        (ans, FP_INEXACT)
+    } else {
+        (ans, 0)
+    }
+}
+
+/// Convert to an unsigned integer, rounding towards zero. Out-of-range values
+/// and NaNs saturate and are invalid.
+fn to_unsigned<T: Float>(f: T) -> (u32, u16) {
+    let f = f.to_f64();
+    let ans = f as u32;
+    if f.is_nan() || f <= -1.0 || f >= u32::MAX as f64 + 1.0 {
+        (ans, FP_INVALID)
+    } else if f.trunc() != f {
+        (ans, FP_INEXACT)
+    } else {
+        (ans, 0)
+    }
+}
+
+/// Convert a 32-bit integer to a float or double, treating it as signed or
+/// unsigned.
+pub fn from_word<T: Float>(u: u32, signed: bool) -> (T, u16) {
+    if signed {
+        from_signed(u as i32)
+    } else {
+        from_unsigned(u)
+    }
+}
+
+/// Convert a float or double to a 32-bit integer, treating it as signed or
+/// unsigned.
+pub fn to_word<T: Float>(f: T, signed: bool) -> (u32, u16) {
+    if signed {
+        let (i, exceptions) = to_signed(f);
+        (i as u32, exceptions)
+    } else {
+        to_unsigned(f)
+    }
+}
+
+/// Convert a double to a float, rounding to nearest.
+pub fn narrow(d: f64) -> (f32, u16) {
+    let ans = d as f32;
+    let inexact = d.is_finite() && f64::from(ans) != d;
+    (
+        ans,
+        exceptions(d.is_nan(), d.is_finite(), ans, inexact, false),
+    )
+}
+
+/// Convert a float to a double, which is always exact.
+pub fn widen(f: f32) -> (f64, u16) {
+    (f64::from(f), 0)
+}
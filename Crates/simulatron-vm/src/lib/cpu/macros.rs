@@ -77,10 +77,18 @@ macro_rules! bin_op_multisigned {
             }
             TypedValue::Float(x) => {
                 let y = f32::try_from($value).unwrap();
-                let ans = x.$float_op(y);
+                let (ans, exceptions) = fpu::$float_op(x, y);
+                $self.raise_float_exceptions(exceptions)?;
                 $self.write_to_register($reg_ref, TypedValue::Float(ans))?;
                 flags = make_flags_float!(ans);
             }
+            TypedValue::Double(x) => {
+                let y = f64::try_from($value).unwrap();
+                let (ans, exceptions) = fpu::$float_op(x, y);
+                $self.raise_float_exceptions(exceptions)?;
+                $self.write_to_register($reg_ref, TypedValue::Double(ans))?;
+                flags = make_flags_float!(ans);
+            }
         }
         $self.flags = flags;
         Ok(())
@@ -110,7 +118,7 @@ macro_rules! bin_op_unsigned {
                 $self.write_to_register($reg_ref, TypedValue::Word(ans.0))?;
                 flags = make_flags_int!(ans.0 as i32, ans.1, false);
             }
-            TypedValue::Float(_) => {
+            TypedValue::Float(_) | TypedValue::Double(_) => {
                 unreachable!()
             }
         }
@@ -144,10 +152,18 @@ macro_rules! bin_op_signed {
             }
             TypedValue::Float(x) => {
                 let y = f32::try_from($value).unwrap();
-                let ans = x.$float_op(y);
+                let (ans, exceptions) = fpu::$float_op(x, y);
+                $self.raise_float_exceptions(exceptions)?;
                 $self.write_to_register($reg_ref, TypedValue::Float(ans))?;
                 flags = make_flags_float!(ans);
             }
+            TypedValue::Double(x) => {
+                let y = f64::try_from($value).unwrap();
+                let (ans, exceptions) = fpu::$float_op(x, y);
+                $self.raise_float_exceptions(exceptions)?;
+                $self.write_to_register($reg_ref, TypedValue::Double(ans))?;
+                flags = make_flags_float!(ans);
+            }
         }
         $self.flags = flags;
         Ok(())
@@ -177,7 +193,7 @@ macro_rules! bin_op_bitwise {
                 $self.write_to_register($reg_ref, TypedValue::Word(ans))?;
                 flags = make_flags_int!(ans as i32, false, false);
             }
-            TypedValue::Float(_) => {
+            TypedValue::Float(_) | TypedValue::Double(_) => {
                 unreachable!()
             }
         }
@@ -206,7 +222,7 @@ macro_rules! bin_op_rotate {
                 $self.write_to_register($reg_ref, TypedValue::Word(ans))?;
                 flags = make_flags_int!(ans as i32, false, false);
             }
-            TypedValue::Float(_) => {
+            TypedValue::Float(_) | TypedValue::Double(_) => {
                 unreachable!()
             }
         }
@@ -253,7 +269,86 @@ macro_rules! bin_op_rotate_carry {
                 $self.write_to_register($reg_ref, TypedValue::Word(ans))?;
                 flags = make_flags_int!(ans as i32, carry, false);
             }
-            TypedValue::Float(_) => {
+            TypedValue::Float(_) | TypedValue::Double(_) => {
+                unreachable!()
+            }
+        }
+        $self.flags = flags;
+        Ok(())
+    }};
+}
+
+/// Create a floating-point unary operation.
+macro_rules! un_op_float {
+    ($self:expr, $reg_ref:expr, $op:ident) => {{
+        let flags: u16;
+        match $self.read_from_register($reg_ref)? {
+            TypedValue::Float(x) => {
+                let (ans, exceptions) = fpu::$op(x);
+                $self.raise_float_exceptions(exceptions)?;
+                $self.write_to_register($reg_ref, TypedValue::Float(ans))?;
+                flags = make_flags_float!(ans);
+            }
+            TypedValue::Double(x) => {
+                let (ans, exceptions) = fpu::$op(x);
+                $self.raise_float_exceptions(exceptions)?;
+                $self.write_to_register($reg_ref, TypedValue::Double(ans))?;
+                flags = make_flags_float!(ans);
+            }
+            _ => unreachable!(),
+        }
+        $self.flags = flags;
+        Ok(())
+    }};
+}
+
+/// Create a floating-point binary operation.
+macro_rules! bin_op_float {
+    ($self:expr, $reg_ref:expr, $value:expr, $op:ident) => {{
+        let flags: u16;
+        match $self.read_from_register($reg_ref)? {
+            TypedValue::Float(x) => {
+                let y = f32::try_from($value).unwrap();
+                let (ans, exceptions) = fpu::$op(x, y);
+                $self.raise_float_exceptions(exceptions)?;
+                $self.write_to_register($reg_ref, TypedValue::Float(ans))?;
+                flags = make_flags_float!(ans);
+            }
+            TypedValue::Double(x) => {
+                let y = f64::try_from($value).unwrap();
+                let (ans, exceptions) = fpu::$op(x, y);
+                $self.raise_float_exceptions(exceptions)?;
+                $self.write_to_register($reg_ref, TypedValue::Double(ans))?;
+                flags = make_flags_float!(ans);
+            }
+            _ => unreachable!(),
+        }
+        $self.flags = flags;
+        Ok(())
+    }};
+}
+
+/// Create a bitwise unary operation.
+macro_rules! un_op_bitwise {
+    ($self:expr, $reg_ref:expr, $op:ident) => {{
+        let flags: u16;
+        match $self.read_from_register($reg_ref)? {
+            TypedValue::Byte(x) => {
+                let ans = x.$op();
+                $self.write_to_register($reg_ref, TypedValue::Byte(ans))?;
+                flags = make_flags_int!(ans as i8, false, false);
+            }
+            TypedValue::Half(x) => {
+                let ans = x.$op();
+                $self.write_to_register($reg_ref, TypedValue::Half(ans))?;
+                flags = make_flags_int!(ans as i16, false, false);
+            }
+            TypedValue::Word(x) => {
+                let ans = x.$op();
+                $self.write_to_register($reg_ref, TypedValue::Word(ans))?;
+                flags = make_flags_int!(ans as i32, false, false);
+            }
+            TypedValue::Float(_) | TypedValue::Double(_) => {
                 unreachable!()
             }
         }
@@ -262,6 +357,89 @@ macro_rules! bin_op_rotate_carry {
     }};
 }
 
+/// Create a bit counting operation. The carry flag is set if the operand
+/// was zero.
+macro_rules! un_op_count {
+    ($self:expr, $reg_ref:expr, $op:ident) => {{
+        let flags: u16;
+        match $self.read_from_register($reg_ref)? {
+            TypedValue::Byte(x) => {
+                let ans = x.$op() as u8;
+                $self.write_to_register($reg_ref, TypedValue::Byte(ans))?;
+                flags = make_flags_int!(ans as i8, x == 0, false);
+            }
+            TypedValue::Half(x) => {
+                let ans = x.$op() as u16;
+                $self.write_to_register($reg_ref, TypedValue::Half(ans))?;
+                flags = make_flags_int!(ans as i16, x == 0, false);
+            }
+            TypedValue::Word(x) => {
+                let ans = x.$op();
+                $self.write_to_register($reg_ref, TypedValue::Word(ans))?;
+                flags = make_flags_int!(ans as i32, x == 0, false);
+            }
+            TypedValue::Float(_) | TypedValue::Double(_) => {
+                unreachable!()
+            }
+        }
+        $self.flags = flags;
+        Ok(())
+    }};
+}
+
+/// Create a single-bit operation, which optionally sets the bit to a new value.
+/// The bit index is taken modulo the register width, and the original value of
+/// the bit is reported in the flags: zero if it was clear, carry if it was set.
+macro_rules! bin_op_bit {
+    ($self:expr, $reg_ref:expr, $bit:expr, $new_value:expr) => {{
+        let new_value: Option<bool> = $new_value;
+        let bit_set: bool;
+        match $self.read_from_register($reg_ref)? {
+            TypedValue::Byte(x) => {
+                let mask = 1u8 << ($bit as u32 % u8::BITS);
+                bit_set = x & mask != 0;
+                $self.write_to_register(
+                    $reg_ref,
+                    TypedValue::Byte(match new_value {
+                        Some(true) => x | mask,
+                        Some(false) => x & !mask,
+                        None => x,
+                    }),
+                )?;
+            }
+            TypedValue::Half(x) => {
+                let mask = 1u16 << ($bit as u32 % u16::BITS);
+                bit_set = x & mask != 0;
+                $self.write_to_register(
+                    $reg_ref,
+                    TypedValue::Half(match new_value {
+                        Some(true) => x | mask,
+                        Some(false) => x & !mask,
+                        None => x,
+                    }),
+                )?;
+            }
+            TypedValue::Word(x) => {
+                let mask = 1u32 << ($bit as u32 % u32::BITS);
+                bit_set = x & mask != 0;
+                $self.write_to_register(
+                    $reg_ref,
+                    TypedValue::Word(match new_value {
+                        Some(true) => x | mask,
+                        Some(false) => x & !mask,
+                        None => x,
+                    }),
+                )?;
+            }
+            TypedValue::Float(_) | TypedValue::Double(_) => {
+                unreachable!()
+            }
+        }
+        $self.flags = make_flags_int!(bit_set as i32, bit_set, false);
+        Ok(())
+    }};
+}
+
 /// Create a conditional jump to literal opcode.
 macro_rules! cond_jump_literal {
     ($self:ident, $condition:expr) => {{
@@ -288,6 +466,45 @@ macro_rules! cond_jump_reference {
     }};
 }
 
+/// Create a conditional jump to relative opcode.
+macro_rules! cond_jump_relative {
+    ($self:ident, $condition:expr) => {{
+        trace!("{} relative", $condition.0);
+        let offset: u32 = fetch!(Word);
+        if $condition.1 {
+            let address = $self.program_counter.wrapping_add(offset);
+            trace!("Jumping to {:#x}", address);
+            $self.program_counter = address;
+        }
+    }};
+}
+
+/// Create a conditional set opcode.
+macro_rules! cond_set {
+    ($self:ident, $name:expr, $condition:expr) => {{
+        trace!("{}", $name);
+        let reg_ref = fetch!(Byte);
+        reject_float!(reg_ref);
+        trace!("Setting {:#x} to {}", reg_ref, $condition.1);
+        $self.instruction_set(reg_ref, $condition.1)?;
+    }};
+}
+
+/// Create a conditional move opcode.
+macro_rules! cond_move {
+    ($self:ident, $name:expr, $condition:expr) => {{
+        trace!("{}", $name);
+        let reg_ref_dest = fetch!(Byte);
+        let reg_ref_source = fetch!(Byte);
+        check_same_type!(reg_ref_dest, reg_ref_source);
+        if $condition.1 {
+            trace!("Copying from {:#x} to {:#x}", reg_ref_source, reg_ref_dest);
+            let value = $self.read_from_register(reg_ref_source)?;
+            $self.write_to_register(reg_ref_dest, value)?;
+        }
+    }};
+}
+
 // Conditional jump definitions.
 macro_rules! jequal {
     ($self:ident) => {
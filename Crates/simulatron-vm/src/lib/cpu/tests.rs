@@ -5,8 +5,11 @@ use ntest::{assert_about_eq, timeout};
 use crate::disk::MockDiskController;
 use crate::display::DisplayController;
 use crate::init_test_logging;
+use crate::interval_timer::IntervalTimerController;
 use crate::keyboard::{KeyMessage, KeyboardController};
 use crate::mmu::{MMU, ROM, ROM_SIZE};
+use crate::mouse::MouseController;
+use crate::sound::SoundController;
 use crate::ui::UICommand;
 
 fn run(
@@ -14,24 +17,50 @@ fn run(
     keypress: Option<KeyMessage>,
     interrupt_tx: Sender<u32>,
     interrupt_rx: Receiver<u32>,
+) -> (CPU<MockDiskController>, Vec<UICommand>) {
+    run_cores(rom, keypress, interrupt_tx, interrupt_rx, 1)
+}
+
+fn run_cores(
+    rom: ROM,
+    keypress: Option<KeyMessage>,
+    interrupt_tx: Sender<u32>,
+    interrupt_rx: Receiver<u32>,
+    num_cores: usize,
 ) -> (CPU<MockDiskController>, Vec<UICommand>) {
     init_test_logging();
 
     // Create communication channels.
     let interrupt_tx_keyboard = interrupt_tx.clone();
+    let interrupt_tx_mouse = interrupt_tx.clone();
+    let interrupt_tx_timer = interrupt_tx.clone();
     let interrupt_tx_mmu = interrupt_tx.clone();
     let (ui_tx, ui_rx) = mpsc::channel();
     let ui_tx_display = ui_tx.clone();
     let (keyboard_tx, keyboard_rx) = mpsc::channel();
     let keyboard_tx_manual = keyboard_tx.clone();
+    let (mouse_tx, mouse_rx) = mpsc::channel();
 
     // Create components.
     let display = DisplayController::new(ui_tx_display);
     let keyboard = KeyboardController::new(keyboard_tx, keyboard_rx, interrupt_tx_keyboard);
     let disk_a = MockDiskController;
     let disk_b = MockDiskController;
-    let mmu = MMU::new(interrupt_tx_mmu, disk_a, disk_b, display, keyboard, rom);
-    let mut cpu = CPU::new(ui_tx, mmu, interrupt_tx, interrupt_rx);
+    let mouse = MouseController::new(mouse_tx, mouse_rx, interrupt_tx_mouse);
+    let sound = SoundController::new(None);
+    let timer = IntervalTimerController::new(interrupt_tx_timer);
+    let mmu = MMU::new(
+        interrupt_tx_mmu,
+        disk_a,
+        disk_b,
+        display,
+        keyboard,
+        mouse,
+        sound,
+        timer,
+        rom,
+    );
+    let mut cpu = CPU::new(ui_tx, mmu, interrupt_tx, interrupt_rx, num_cores);
 
     // Run the CPU till halt.
     cpu.start();
@@ -51,7 +80,10 @@ fn run_default(rom: ROM) -> (CPU<MockDiskController>, Vec<UICommand>) {
 
 macro_rules! internal {
     ($cpu:ident) => {
-        $cpu.internal.as_ref().unwrap()
+        internal!($cpu, 0)
+    };
+    ($cpu:ident, $core:expr) => {
+        $cpu.internal.as_ref().unwrap()[$core]
     };
 }
 
@@ -91,7 +123,7 @@ fn test_pause() {
     rom[16] = 0x01; // Pause (should be skipped due to interrupt occurring directly before).
 
     let (tx, rx) = mpsc::channel();
-    let (_cpu, ui_commands) = run(rom, Some(KeyMessage::Key(b'a', false, false)), tx, rx);
+    let (_cpu, ui_commands) = run(rom, Some(KeyMessage::Key(b'a', 0)), tx, rx);
     assert_eq!(ui_commands.len(), 1);
 }
 
@@ -153,7 +185,11 @@ fn test_store_literal_address() {
     let (cpu, ui_commands) = run_default(rom);
     assert_eq!(ui_commands.len(), 1);
     assert_eq!(
-        internal!(cpu).mmu.load_physical_32(0x00004ABC),
+        internal!(cpu)
+            .mmu
+            .lock()
+            .unwrap()
+            .load_physical_32(0x00004ABC),
         Ok(0x12345678)
     );
 }
@@ -183,7 +219,11 @@ fn test_store_reg_address() {
     let (cpu, ui_commands) = run_default(rom);
     assert_eq!(ui_commands.len(), 1);
     assert_eq!(
-        internal!(cpu).mmu.load_physical_32(0x00004ABC),
+        internal!(cpu)
+            .mmu
+            .lock()
+            .unwrap()
+            .load_physical_32(0x00004ABC),
         Ok(0xABCDEF00)
     );
 }
@@ -240,6 +280,164 @@ fn test_load_reg_address() {
     assert_eq!(internal!(cpu).r[6], 0x0000FF34);
 }
 
+#[test]
+#[timeout(100)]
+fn test_load_base_offset() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x01; // into r1
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x00;
+    rom[5] = 0x88; // ROM byte 0x48 (72).
+
+    rom[6] = 0x74; // Load with base and offset
+    rom[7] = 0x00; // into r0
+    rom[8] = 0x01; // base r1
+    rom[9] = 0xFF;
+    rom[10] = 0xFF;
+    rom[11] = 0xFF;
+    rom[12] = 0xF8; // offset -8.
+
+    rom[13] = 0x74; // Load with base and offset
+    rom[14] = 0x12; // into r2b
+    rom[15] = 0x01; // base r1
+    rom[16] = 0x00;
+    rom[17] = 0x00;
+    rom[18] = 0x00;
+    rom[19] = 0x04; // offset 4.
+
+    rom[64] = 0x12;
+    rom[65] = 0x34;
+    rom[66] = 0x56;
+    rom[67] = 0x78;
+    rom[76] = 0x9A;
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[0], 0x12345678);
+    assert_eq!(internal!(cpu).r[2], 0x0000009A);
+}
+
+#[test]
+#[timeout(100)]
+fn test_load_indexed() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x01; // into r1
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x00;
+    rom[5] = 0x80; // ROM byte 0x40 (64).
+
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x02; // into r2
+    rom[8] = 0x00;
+    rom[9] = 0x00;
+    rom[10] = 0x00;
+    rom[11] = 0x03; // 3.
+
+    rom[12] = 0x75; // Load with base and index
+    rom[13] = 0x08; // into r0h
+    rom[14] = 0x01; // base r1
+    rom[15] = 0x02; // index r2
+    rom[16] = 0x02; // scale 2.
+
+    rom[17] = 0x75; // Load with base and index
+    rom[18] = 0x03; // into r3
+    rom[19] = 0x01; // base r1
+    rom[20] = 0x02; // index r2
+    rom[21] = 0x08; // scale 8.
+
+    rom[70] = 0xAB;
+    rom[71] = 0xCD;
+    rom[88] = 0x01;
+    rom[89] = 0x02;
+    rom[90] = 0x03;
+    rom[91] = 0x04;
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[0], 0x0000ABCD);
+    assert_eq!(internal!(cpu).r[3], 0x01020304);
+}
+
+#[test]
+#[timeout(100)]
+fn test_store_indexed() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x00; // into r0
+    rom[2] = 0xAB;
+    rom[3] = 0xCD;
+    rom[4] = 0xEF;
+    rom[5] = 0x00; // some random number.
+
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x01; // into r1
+    rom[8] = 0x00;
+    rom[9] = 0x00;
+    rom[10] = 0x50;
+    rom[11] = 0x00; // address 0x00005000.
+
+    rom[12] = 0x0A; // Copy literal
+    rom[13] = 0x02; // into r2
+    rom[14] = 0xFF;
+    rom[15] = 0xFF;
+    rom[16] = 0xFF;
+    rom[17] = 0xFF; // -1.
+
+    rom[18] = 0x76; // Store with base and offset
+    rom[19] = 0x01; // base r1
+    rom[20] = 0x00;
+    rom[21] = 0x00;
+    rom[22] = 0x01;
+    rom[23] = 0x00; // offset 0x100
+    rom[24] = 0x00; // r0.
+
+    rom[25] = 0x77; // Store with base and index
+    rom[26] = 0x01; // base r1
+    rom[27] = 0x02; // index r2
+    rom[28] = 0x04; // scale 4
+    rom[29] = 0x00; // r0.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    let mmu = internal!(cpu).mmu.lock().unwrap();
+    assert_eq!(mmu.load_physical_32(0x00005100), Ok(0xABCDEF00));
+    assert_eq!(mmu.load_physical_32(0x00004FFC), Ok(0xABCDEF00));
+}
+
+#[test]
+#[timeout(100)]
+fn test_indexed_bad_scale() {
+    let mut rom = [0; ROM_SIZE];
+    // Try an indexed load with an invalid scale. Should raise an interrupt.
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x22; // into kspr
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x50;
+    rom[5] = 0x00; // address 0x00005000.
+
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x24; // into imr
+    rom[8] = 0x00;
+    rom[9] = 0x01; // illegal operation interrupt only.
+
+    rom[10] = 0x75; // Load with base and index
+    rom[11] = 0x00; // into r0
+    rom[12] = 0x01; // base r1
+    rom[13] = 0x02; // index r2
+    rom[14] = 0x03; // scale 3.
+
+    rom[15] = 0x01; // Pause. We should never hit this.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[0], 0x00000000);
+}
+
 #[test]
 #[timeout(100)]
 fn test_swap_literal() {
@@ -258,7 +456,14 @@ fn test_swap_literal() {
     let (cpu, ui_commands) = run_default(rom);
     assert_eq!(ui_commands.len(), 1);
     assert_eq!(internal!(cpu).r[0], 0x00000000);
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x00004000), Ok(0x66));
+    assert_eq!(
+        internal!(cpu)
+            .mmu
+            .lock()
+            .unwrap()
+            .load_physical_8(0x00004000),
+        Ok(0x66)
+    );
 }
 
 #[test]
@@ -283,7 +488,182 @@ fn test_swap_reg() {
     let (cpu, ui_commands) = run_default(rom);
     assert_eq!(ui_commands.len(), 1);
     assert_eq!(internal!(cpu).r[0], 0x00000000);
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x00005000), Ok(0x77));
+    assert_eq!(
+        internal!(cpu)
+            .mmu
+            .lock()
+            .unwrap()
+            .load_physical_8(0x00005000),
+        Ok(0x77)
+    );
+}
+
+#[test]
+#[timeout(100)]
+fn test_cmpxchg_equal() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x08; // into r0h
+    rom[2] = 0x00;
+    rom[3] = 0x00; // the current memory value.
+
+    rom[4] = 0x0A; // Copy literal
+    rom[5] = 0x09; // into r1h
+    rom[6] = 0x12;
+    rom[7] = 0x34; // some random number.
+
+    rom[8] = 0x70; // Compare and exchange with literal address
+    rom[9] = 0x08; // expecting r0h
+    rom[10] = 0x09; // replacing with r1h
+    rom[11] = 0x00;
+    rom[12] = 0x00;
+    rom[13] = 0x40;
+    rom[14] = 0x00; // address 0x00004000.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[0], 0x00000000);
+    assert_eq!(internal!(cpu).flags, FLAG_ZERO);
+    assert_eq!(
+        internal!(cpu)
+            .mmu
+            .lock()
+            .unwrap()
+            .load_physical_16(0x00004000),
+        Ok(0x1234)
+    );
+}
+
+#[test]
+#[timeout(100)]
+fn test_cmpxchg_not_equal() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x10; // into r0b
+    rom[2] = 0x55; // something other than the current memory value.
+
+    rom[3] = 0x0A; // Copy literal
+    rom[4] = 0x11; // into r1b
+    rom[5] = 0x66; // some random number.
+
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x02; // into r2
+    rom[8] = 0x00;
+    rom[9] = 0x00;
+    rom[10] = 0x50;
+    rom[11] = 0x00; // address 0x00005000.
+
+    rom[12] = 0x4A; // Compare
+    rom[13] = 0x03; // r3
+    rom[14] = 0x00;
+    rom[15] = 0x00;
+    rom[16] = 0x00;
+    rom[17] = 0x00; // with zero, setting the zero flag.
+
+    rom[18] = 0x71; // Compare and exchange with reg ref address
+    rom[19] = 0x10; // expecting r0b
+    rom[20] = 0x11; // replacing with r1b
+    rom[21] = 0x02; // address in r2.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    // The current value should have been loaded instead.
+    assert_eq!(internal!(cpu).r[0], 0x00000000);
+    assert_eq!(internal!(cpu).flags & FLAG_ZERO, 0);
+    assert_eq!(
+        internal!(cpu)
+            .mmu
+            .lock()
+            .unwrap()
+            .load_physical_8(0x00005000),
+        Ok(0x00)
+    );
+}
+
+#[test]
+#[timeout(100)]
+fn test_fetchadd_literal() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x00; // into r0
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x00;
+    rom[5] = 0x05; // 5.
+
+    rom[6] = 0x72; // Fetch and add with literal address
+    rom[7] = 0x00; // r0
+    rom[8] = 0x00;
+    rom[9] = 0x00;
+    rom[10] = 0x40;
+    rom[11] = 0x00; // address 0x00004000.
+
+    rom[12] = 0x0A; // Copy literal
+    rom[13] = 0x00; // into r0
+    rom[14] = 0x00;
+    rom[15] = 0x00;
+    rom[16] = 0x00;
+    rom[17] = 0x07; // 7.
+
+    rom[18] = 0x72; // Fetch and add with literal address
+    rom[19] = 0x00; // r0
+    rom[20] = 0x00;
+    rom[21] = 0x00;
+    rom[22] = 0x40;
+    rom[23] = 0x00; // address 0x00004000.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[0], 0x00000005);
+    assert_eq!(
+        internal!(cpu)
+            .mmu
+            .lock()
+            .unwrap()
+            .load_physical_32(0x00004000),
+        Ok(12)
+    );
+}
+
+#[test]
+#[timeout(100)]
+fn test_fetchadd_reg() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x01; // into r1
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x50;
+    rom[5] = 0x00; // address 0x00005000.
+
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x10; // into r0b
+    rom[8] = 0xFF; // 255.
+
+    rom[9] = 0x73; // Fetch and add with reg ref address
+    rom[10] = 0x10; // r0b
+    rom[11] = 0x01; // address in r1.
+
+    rom[12] = 0x0A; // Copy literal
+    rom[13] = 0x10; // into r0b
+    rom[14] = 0x02; // 2.
+
+    rom[15] = 0x73; // Fetch and add with reg ref address
+    rom[16] = 0x10; // r0b
+    rom[17] = 0x01; // address in r1.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[0], 0x000000FF);
+    // The addition should have wrapped.
+    assert_eq!(
+        internal!(cpu)
+            .mmu
+            .lock()
+            .unwrap()
+            .load_physical_8(0x00005000),
+        Ok(0x01)
+    );
 }
 
 #[test]
@@ -320,7 +700,11 @@ fn test_kernel_stack() {
     assert_eq!(internal!(cpu).r[1], 0x0000AAFF);
     assert_eq!(internal!(cpu).kspr, 0x00007FFF);
     assert_eq!(
-        internal!(cpu).mmu.load_physical_32(0x00007FFC),
+        internal!(cpu)
+            .mmu
+            .lock()
+            .unwrap()
+            .load_physical_32(0x00007FFC),
         Ok(0x00AAFFFF)
     );
 }
@@ -472,7 +856,14 @@ fn test_user_mode() {
     assert_eq!(ui_commands.len(), 1);
     // Assert the user mode process stored in its stack correctly.
     assert_eq!(internal!(cpu).uspr, 0x00000063);
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x00004063), Ok(0x99));
+    assert_eq!(
+        internal!(cpu)
+            .mmu
+            .lock()
+            .unwrap()
+            .load_physical_8(0x00004063),
+        Ok(0x99)
+    );
 }
 
 #[test]
@@ -514,93 +905,26 @@ fn test_keyboard() {
     let (interrupt_tx, interrupt_rx) = mpsc::channel();
     let (cpu, ui_commands) = run(
         rom,
-        Some(KeyMessage::Key(KEY, false, false)),
+        Some(KeyMessage::Key(KEY, 0)),
         interrupt_tx,
         interrupt_rx,
     );
     assert_eq!(ui_commands.len(), 1);
 
     // Assert that the key was correctly detected.
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x19B0), Ok(KEY));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x19B1), Ok(0));
-}
-
-#[test]
-#[timeout(100)]
-fn test_display() {
-    let mut rom = [0; ROM_SIZE];
-    // Set character 5,32 to '#'.
-    rom[0] = 0x0A; // Copy literal
-    rom[1] = 0x10; // into r0b
-    rom[2] = 0x21; // character '!'.
-
-    rom[3] = 0x08; // Store into
-    rom[4] = 0x00;
-    rom[5] = 0x00;
-    rom[6] = 0x03;
-    rom[7] = 0xF0; // display cell (r5,c32)
-    rom[8] = 0x10; // r0b.
-
-    // Set foreground colour 20, 50 to dark red.
-    rom[9] = 0x0A; // Copy literal
-    rom[10] = 0x10; // into r0b
-    rom[11] = 0x10; // RGB(85, 0, 0).
-
-    rom[12] = 0x08; // Store into
-    rom[13] = 0x00;
-    rom[14] = 0x00;
-    rom[15] = 0x10;
-    rom[16] = 0x82; // foreground colour cell (r20,c50)
-    rom[17] = 0x10; // r0b.
-
-    // Set background colour 0, 0 to yellow.
-    rom[18] = 0x0A; // Copy literal
-    rom[19] = 0x10; // into r0b
-    rom[20] = 0x3C; // RGB(255, 255, 0).
-
-    rom[21] = 0x08; // Store into
-    rom[22] = 0x00;
-    rom[23] = 0x00;
-    rom[24] = 0x11;
-    rom[25] = 0xE0; // background colour cell (r0,c0)
-    rom[26] = 0x10; // r0b.
-
-    let (_cpu, ui_commands) = run_default(rom);
-    assert_eq!(ui_commands.len(), 4);
-    assert_eq!(
-        ui_commands[0],
-        UICommand::SetChar {
-            row: 5,
-            col: 32,
-            character: '!'
-        }
-    );
     assert_eq!(
-        ui_commands[1],
-        UICommand::SetFg {
-            row: 20,
-            col: 50,
-            r: 85,
-            g: 0,
-            b: 0
-        }
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x19B0),
+        Ok(KEY)
     );
     assert_eq!(
-        ui_commands[2],
-        UICommand::SetBg {
-            row: 0,
-            col: 0,
-            r: 255,
-            g: 255,
-            b: 0
-        }
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x19B1),
+        Ok(0)
     );
-    assert_eq!(ui_commands[3], UICommand::CPUHalted);
 }
 
 #[test]
 #[timeout(100)]
-fn test_syscall() {
+fn test_mouse_interrupt() {
     let mut rom = [0; ROM_SIZE];
     rom[0] = 0x0A; // Copy literal
     rom[1] = 0x22; // into kspr
@@ -613,202 +937,118 @@ fn test_syscall() {
     rom[7] = 0x00; // into r0
     rom[8] = 0x00;
     rom[9] = 0x00;
-    rom[10] = 0x00;
-    rom[11] = 0xC0; // ROM byte 0x80 (128).
+    rom[10] = 0x40;
+    rom[11] = 0x00; // address 0x00004000.
 
     rom[12] = 0x08; // Store into
     rom[13] = 0x00;
     rom[14] = 0x00;
     rom[15] = 0x00;
-    rom[16] = 0x1C; // syscall interrupt handler
+    rom[16] = 0x20; // mouse interrupt handler
     rom[17] = 0x00; // r0.
 
+    // Address 0x4000 is HALT, so we should halt on interrupt.
+
     rom[18] = 0x0A; // Copy literal
     rom[19] = 0x24; // into imr
-    rom[20] = 0x00;
-    rom[21] = 0x80; // syscall only.
-
-    rom[22] = 0x6B; // SYSCALL.
-
-    rom[23] = 0x01; // Pause (fail condition).
+    rom[20] = 0x01;
+    rom[21] = 0x00; // mouse interrupt only.
 
-    // Interrupt handler.
-    rom[128] = 0x0A; // Copy literal
-    rom[129] = 0x17; // into r7b
-    rom[130] = 0x42; // some number.
-
-    rom[131] = 0x00; // HALT.
+    rom[22] = 0x01; // Pause (will only be reached if this happens before interrupt sent).
+    rom[23] = 0x01; // Pause (should never happen, acts as a fail condition).
 
-    let (cpu, ui_commands) = run_default(rom);
+    let (interrupt_tx, interrupt_rx) = mpsc::channel();
+    interrupt_tx.send(INTERRUPT_MOUSE).unwrap();
+    let (cpu, ui_commands) = run(rom, None, interrupt_tx, interrupt_rx);
     assert_eq!(ui_commands.len(), 1);
-    assert_eq!(internal!(cpu).r[7], 0x42);
+    // The handler should have been reached.
+    assert_eq!(internal!(cpu).program_counter, 0x4001);
 }
 
 #[test]
 #[timeout(100)]
-fn test_bad_reg_ref() {
+fn test_core_id() {
     let mut rom = [0; ROM_SIZE];
-    // Try a copy with unmatched sizes. Should raise an interrupt.
-    rom[0] = 0x0A; // Copy literal
-    rom[1] = 0x22; // into kspr
-    rom[2] = 0x00;
-    rom[3] = 0x00;
-    rom[4] = 0x50;
-    rom[5] = 0x00; // address 0x00005000.
-
-    rom[6] = 0x0A; // Copy literal
-    rom[7] = 0x24; // into imr
-    rom[8] = 0x00;
-    rom[9] = 0x01; // illegal operation interrupt only.
-
-    rom[10] = 0x0B; // Copy register
-    rom[11] = 0x01; // into r1
-    rom[12] = 0x0B; // from r3h.
+    rom[0] = 0x0B; // Copy
+    rom[1] = 0x00; // into r0
+    rom[2] = 0x26; // the core ID.
 
-    rom[13] = 0x01; // Pause. We should never hit this.
+    // Both cores run the same code, then halt.
 
-    let (cpu, ui_commands) = run_default(rom);
+    let (interrupt_tx, interrupt_rx) = mpsc::channel();
+    let (cpu, ui_commands) = run_cores(rom, None, interrupt_tx, interrupt_rx, 2);
+    // Only the last core to halt should report it.
     assert_eq!(ui_commands.len(), 1);
-    assert_eq!(internal!(cpu).r[1], 0x00000000);
+    assert_eq!(internal!(cpu, 0).r[0], 0);
+    assert_eq!(internal!(cpu, 1).r[0], 1);
 }
 
 #[test]
 #[timeout(100)]
-fn test_pfsr() {
+fn test_ipi() {
     let mut rom = [0; ROM_SIZE];
-    // Set the page directory pointer.
     rom[0] = 0x0A; // Copy literal
-    rom[1] = 0x23; // into pdpr
+    rom[1] = 0x22; // into kspr
     rom[2] = 0x00;
     rom[3] = 0x00;
-    rom[4] = 0x00;
-    rom[5] = 0xC0; // ROM byte 0x80 (128).
+    rom[4] = 0x50;
+    rom[5] = 0x00; // address 0x00005000.
 
-    // Write the page table entry.
     rom[6] = 0x0A; // Copy literal
     rom[7] = 0x00; // into r0
     rom[8] = 0x00;
     rom[9] = 0x00;
-    rom[10] = 0x50;
-    rom[11] = 0x13; // Valid, Present, Execute entry at 0x00005000.
+    rom[10] = 0x40;
+    rom[11] = 0x00; // address 0x00004000.
 
     rom[12] = 0x08; // Store into
     rom[13] = 0x00;
     rom[14] = 0x00;
-    rom[15] = 0xB0;
-    rom[16] = 0x00; // address 0x0000B000
+    rom[15] = 0x00;
+    rom[16] = 0x28; // IPI handler
     rom[17] = 0x00; // r0.
 
-    // Write the user mode instructions to RAM.
+    // Address 0x4000 is HALT, so we should halt on interrupt.
 
-    // (Load an execute-only address, which is not allowed).
     rom[18] = 0x0A; // Copy literal
-    rom[19] = 0x08; // into r0h
-    rom[20] = 0x06; // LOAD instruction
-    rom[21] = 0x17; // into r7b.
-
-    rom[22] = 0x08; // Store into
-    rom[23] = 0x00;
-    rom[24] = 0x00;
-    rom[25] = 0x50;
-    rom[26] = 0x00; // address 0x00005000
-    rom[27] = 0x08; // r0h.
-
-    rom[28] = 0x0A; // Copy literal
-    rom[29] = 0x00; // into r0
-    rom[30] = 0x00;
-    rom[31] = 0x00;
-    rom[32] = 0x00;
-    rom[33] = 0x04; // virtual address 0x00000004.
-
-    rom[34] = 0x08; // Store into
-    rom[35] = 0x00;
-    rom[36] = 0x00;
-    rom[37] = 0x50;
-    rom[38] = 0x02; // address 0x00005002
-    rom[39] = 0x00; // r0.
-
-    // (Pause, which should never be hit).
-    rom[40] = 0x0A; // Copy literal
-    rom[41] = 0x10; // into r0b
-    rom[42] = 0x01; // PAUSE instruction.
-
-    rom[43] = 0x08; // Store into
-    rom[44] = 0x00;
-    rom[45] = 0x00;
-    rom[46] = 0x50;
-    rom[47] = 0x06; // address 0x00005006.
-    rom[48] = 0x10; // r0b.
-
-    // Set the kernel stack pointer.
-    rom[49] = 0x0A; // Copy literal
-    rom[50] = 0x22; // into kspr
-    rom[51] = 0x00;
-    rom[52] = 0x00;
-    rom[53] = 0xA0;
-    rom[54] = 0x00; // address 0x0000A000.
-
-    // Enable page fault interrupts.
-    rom[55] = 0x0A; // Copy literal
-    rom[56] = 0x24; // into imr
-    rom[57] = 0x00;
-    rom[58] = 0x04; // page fault interrupt only.
-
-    // Set page fault interrupt handler
-    rom[59] = 0x0A; // Copy literal
-    rom[60] = 0x00; // into r0
-    rom[61] = 0x00;
-    rom[62] = 0x00;
-    rom[63] = 0x01;
-    rom[64] = 0x40; // ROM byte 0x100 (256).
-
-    rom[65] = 0x08; // Store into
-    rom[66] = 0x00;
-    rom[67] = 0x00;
-    rom[68] = 0x00;
-    rom[69] = 0x08; // page fault handler
-    rom[70] = 0x00; // r0.
+    rom[19] = 0x00; // into r0
+    rom[20] = 0x00;
+    rom[21] = 0x00;
+    rom[22] = 0x00;
+    rom[23] = 0x01; // 1.
 
-    // Push the user mode address to the stack.
-    rom[71] = 0x0A; // Copy literal
-    rom[72] = 0x00; // into r0
-    rom[73] = 0x00;
-    rom[74] = 0x00;
-    rom[75] = 0x00;
-    rom[76] = 0x00; // virtual address 0x0.
+    rom[24] = 0x0B; // Copy
+    rom[25] = 0x01; // into r1
+    rom[26] = 0x26; // the core ID.
 
-    rom[77] = 0x0E; // Push to the stack
-    rom[78] = 0x00; // r0.
+    rom[27] = 0x26; // Subtract
+    rom[28] = 0x00; // from r0
+    rom[29] = 0x01; // r1, giving the other core's ID.
 
-    // Enter user mode!
-    rom[79] = 0x04;
+    rom[30] = 0x6F; // Send an IPI
+    rom[31] = 0x00; // to the core in r0.
 
-    // Page directory entry.
-    rom[128] = 0x00;
-    rom[129] = 0x00;
-    rom[130] = 0xB0;
-    rom[131] = 0x01; // Valid entry at 0x0000B000.
+    // Any IPI received so far will have been latched.
 
-    // Page fault handler.
-    rom[256] = 0x0B; // Copy between registers
-    rom[257] = 0x05; // into r5
-    rom[258] = 0x25; // from pfsr.
+    rom[32] = 0x0A; // Copy literal
+    rom[33] = 0x24; // into imr
+    rom[34] = 0x04;
+    rom[35] = 0x00; // IPI only.
 
-    rom[259] = 0x00; // Halt.
+    rom[36] = 0x01; // Pause.
+    rom[37] = 0x01; // Pause (should never happen, acts as a fail condition).
 
-    let (cpu, ui_commands) = run_default(rom);
+    let (interrupt_tx, interrupt_rx) = mpsc::channel();
+    let (cpu, ui_commands) = run_cores(rom, None, interrupt_tx, interrupt_rx, 2);
     assert_eq!(ui_commands.len(), 1);
-    // Assert the user mode process stored in its stack correctly.
-    assert_eq!(
-        internal!(cpu).r[5],
-        internal!(cpu).mmu.page_fault_status_register()
-    );
-    assert_eq!(internal!(cpu).r[5], crate::mmu::PAGE_FAULT_ILLEGAL_ACCESS);
+    // Each core should have interrupted the other.
+    assert_eq!(internal!(cpu, 0).program_counter, 0x4001);
+    assert_eq!(internal!(cpu, 1).program_counter, 0x4001);
 }
 
 #[test]
-#[timeout(200)]
-fn test_timer_literal_interval() {
+#[timeout(100)]
+fn test_ipi_bad_core() {
     let mut rom = [0; ROM_SIZE];
     rom[0] = 0x0A; // Copy literal
     rom[1] = 0x22; // into kspr
@@ -828,7 +1068,7 @@ fn test_timer_literal_interval() {
     rom[13] = 0x00;
     rom[14] = 0x00;
     rom[15] = 0x00;
-    rom[16] = 0x18; // timer interrupt handler
+    rom[16] = 0x00; // illegal operation handler
     rom[17] = 0x00; // r0.
 
     // Address 0x4000 is HALT, so we should halt on interrupt.
@@ -836,594 +1076,2943 @@ fn test_timer_literal_interval() {
     rom[18] = 0x0A; // Copy literal
     rom[19] = 0x24; // into imr
     rom[20] = 0x00;
-    rom[21] = 0x40; // timer interrupt only.
+    rom[21] = 0x01; // illegal operation only.
 
-    rom[22] = 0x02; // Set timer to
+    rom[22] = 0x6E; // Send an IPI
     rom[23] = 0x00;
     rom[24] = 0x00;
     rom[25] = 0x00;
-    rom[26] = 0x64; // 100 milliseconds.
+    rom[26] = 0x01; // to core 1, which doesn't exist.
 
-    rom[27] = 0x01; // Pause.
+    rom[27] = 0x01; // Pause (should never happen, acts as a fail condition).
 
-    let (_cpu, ui_commands) = run_default(rom);
+    let (cpu, ui_commands) = run_default(rom);
     assert_eq!(ui_commands.len(), 1);
-    // Simply by halting we confirm that the test was successful.
+    assert_eq!(internal!(cpu).program_counter, 0x4001);
 }
 
 #[test]
-#[timeout(200)]
-fn test_timer_reg_interval() {
+#[timeout(100)]
+fn test_display() {
     let mut rom = [0; ROM_SIZE];
+    // Set character 5,32 to '#'.
     rom[0] = 0x0A; // Copy literal
-    rom[1] = 0x22; // into kspr
-    rom[2] = 0x00;
-    rom[3] = 0x00;
-    rom[4] = 0x50;
-    rom[5] = 0x00; // address 0x00005000.
+    rom[1] = 0x10; // into r0b
+    rom[2] = 0x21; // character '!'.
 
-    rom[6] = 0x0A; // Copy literal
-    rom[7] = 0x00; // into r0
-    rom[8] = 0x00;
-    rom[9] = 0x00;
-    rom[10] = 0x40;
-    rom[11] = 0x00; // address 0x00004000.
+    rom[3] = 0x08; // Store into
+    rom[4] = 0x00;
+    rom[5] = 0x00;
+    rom[6] = 0x03;
+    rom[7] = 0xF0; // display cell (r5,c32)
+    rom[8] = 0x10; // r0b.
+
+    // Set foreground colour 20, 50 to dark red.
+    rom[9] = 0x0A; // Copy literal
+    rom[10] = 0x10; // into r0b
+    rom[11] = 0x10; // RGB(85, 0, 0).
 
     rom[12] = 0x08; // Store into
     rom[13] = 0x00;
     rom[14] = 0x00;
-    rom[15] = 0x00;
-    rom[16] = 0x18; // timer interrupt handler
-    rom[17] = 0x00; // r0.
-
-    // Address 0x4000 is HALT, so we should halt on interrupt.
+    rom[15] = 0x10;
+    rom[16] = 0x82; // foreground colour cell (r20,c50)
+    rom[17] = 0x10; // r0b.
 
+    // Set background colour 0, 0 to yellow.
     rom[18] = 0x0A; // Copy literal
-    rom[19] = 0x24; // into imr
-    rom[20] = 0x00;
-    rom[21] = 0x40; // timer interrupt only.
-
-    rom[22] = 0x0A; // Copy literal
-    rom[23] = 0x00; // into r0
-    rom[24] = 0x00;
-    rom[25] = 0x00;
-    rom[26] = 0x00;
-    rom[27] = 0x64; // 100 milliseconds.
-
-    rom[28] = 0x03; // Set timer to
-    rom[29] = 0x00; // interval in r0.
+    rom[19] = 0x10; // into r0b
+    rom[20] = 0x3C; // RGB(255, 255, 0).
 
-    rom[30] = 0x01; // Pause.
+    rom[21] = 0x08; // Store into
+    rom[22] = 0x00;
+    rom[23] = 0x00;
+    rom[24] = 0x11;
+    rom[25] = 0xE0; // background colour cell (r0,c0)
+    rom[26] = 0x10; // r0b.
 
-    let (_cpu, ui_commands) = run_default(rom);
-    assert_eq!(ui_commands.len(), 1);
-    // Simply by halting we confirm that the test was successful.
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 4);
+    assert_eq!(ui_commands[0], UICommand::RedrawCell { row: 5, col: 32 });
+    assert_eq!(ui_commands[1], UICommand::RedrawCell { row: 20, col: 50 });
+    assert_eq!(ui_commands[2], UICommand::RedrawCell { row: 0, col: 0 });
+    assert_eq!(ui_commands[3], UICommand::CPUHalted);
+    // Display memory should read back.
+    let mmu = internal!(cpu).mmu.lock().unwrap();
+    assert_eq!(mmu.load_physical_8(0x03F0), Ok(0x21));
+    assert_eq!(mmu.load_physical_8(0x1082), Ok(0x10));
+    assert_eq!(mmu.load_physical_8(0x11E0), Ok(0x3C));
+    // Untouched cells should read as the defaults: white on black.
+    assert_eq!(mmu.load_physical_8(0x0240), Ok(0x00));
+    assert_eq!(mmu.load_physical_8(0x0A10), Ok(0x3F));
+    assert_eq!(mmu.load_physical_8(0x11E1), Ok(0x00));
 }
 
 #[test]
 #[timeout(100)]
-fn test_blockcopy() {
+fn test_display_graphics() {
     let mut rom = [0; ROM_SIZE];
+    // Switch to graphics mode.
     rom[0] = 0x0A; // Copy literal
-    rom[1] = 0x00; // into r0
-    rom[2] = 0x00;
-    rom[3] = 0x00;
-    rom[4] = 0x40;
-    rom[5] = 0x00; // address 0x00004000.
+    rom[1] = 0x10; // into r0b
+    rom[2] = 0x01; // graphics mode.
 
-    rom[6] = 0x0A; // Copy literal
-    rom[7] = 0x01; // into r1
-    rom[8] = 0x00;
-    rom[9] = 0x00;
-    rom[10] = 0x00;
-    rom[11] = 0x80; // ROM byte 0x40 (64).
+    rom[3] = 0x08; // Store into
+    rom[4] = 0x00;
+    rom[5] = 0x00;
+    rom[6] = 0x19;
+    rom[7] = 0xC0; // display mode register
+    rom[8] = 0x10; // r0b.
 
-    rom[12] = 0x13; // Blockcopy literal length, ref source and dest
+    // Set pixel 49, 79 to blue.
+    rom[9] = 0x0A; // Copy literal
+    rom[10] = 0x10; // into r0b
+    rom[11] = 0x03; // RGB(0, 0, 255).
+
+    rom[12] = 0x08; // Store into
     rom[13] = 0x00;
     rom[14] = 0x00;
-    rom[15] = 0x00;
-    rom[16] = 0x40; // 64 bytes
+    rom[15] = 0x11;
+    rom[16] = 0xDF; // framebuffer pixel (r49,c79)
+    rom[17] = 0x10; // r0b.
+
+    // Writing past the end of the framebuffer should do nothing.
+    rom[18] = 0x08; // Store into
+    rom[19] = 0x00;
+    rom[20] = 0x00;
+    rom[21] = 0x11;
+    rom[22] = 0xE0; // first byte after the framebuffer
+    rom[23] = 0x10; // r0b.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 3);
+    assert_eq!(ui_commands[0], UICommand::RedrawAll);
+    // Pixel row 49 is in the lower half of cell row 24.
+    assert_eq!(ui_commands[1], UICommand::RedrawCell { row: 24, col: 79 });
+    assert_eq!(ui_commands[2], UICommand::CPUHalted);
+    // The mode register should read back.
+    let mmu = internal!(cpu).mmu.lock().unwrap();
+    assert_eq!(mmu.load_physical_8(0x19C0), Ok(0x01));
+    // As should the framebuffer, with the unused range reading as zero.
+    assert_eq!(mmu.load_physical_8(0x11DF), Ok(0x03));
+    assert_eq!(mmu.load_physical_8(0x11E0), Ok(0x00));
+}
+
+#[test]
+#[timeout(100)]
+fn test_display_cursor() {
+    let mut rom = [0; ROM_SIZE];
+    // Position the cursor at 5, 10 while it's still hidden.
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x10; // into r0b
+    rom[2] = 0x05; // row 5.
+    rom[3] = 0x08; // Store into
+    rom[4] = 0x00;
+    rom[5] = 0x00;
+    rom[6] = 0x19;
+    rom[7] = 0xC1; // cursor row register
+    rom[8] = 0x10; // r0b.
+    rom[9] = 0x0A; // Copy literal
+    rom[10] = 0x10; // into r0b
+    rom[11] = 0x0A; // column 10.
+    rom[12] = 0x08; // Store into
+    rom[13] = 0x00;
+    rom[14] = 0x00;
+    rom[15] = 0x19;
+    rom[16] = 0xC2; // cursor column register
+    rom[17] = 0x10; // r0b.
+
+    // Show it.
+    rom[18] = 0x0A; // Copy literal
+    rom[19] = 0x10; // into r0b
+    rom[20] = 0x01; // visible.
+    rom[21] = 0x08; // Store into
+    rom[22] = 0x00;
+    rom[23] = 0x00;
+    rom[24] = 0x19;
+    rom[25] = 0xC3; // cursor visibility register
+    rom[26] = 0x10; // r0b.
+
+    // Move it one column right.
+    rom[27] = 0x0A; // Copy literal
+    rom[28] = 0x10; // into r0b
+    rom[29] = 0x0B; // column 11.
+    rom[30] = 0x08; // Store into
+    rom[31] = 0x00;
+    rom[32] = 0x00;
+    rom[33] = 0x19;
+    rom[34] = 0xC2; // cursor column register
+    rom[35] = 0x10; // r0b.
+
+    // Change the style; it stays in the same cell.
+    rom[36] = 0x0A; // Copy literal
+    rom[37] = 0x10; // into r0b
+    rom[38] = 0x03; // block style.
+    rom[39] = 0x08; // Store into
+    rom[40] = 0x00;
+    rom[41] = 0x00;
+    rom[42] = 0x19;
+    rom[43] = 0xC4; // cursor style register
+    rom[44] = 0x10; // r0b.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 5);
+    assert_eq!(ui_commands[0], UICommand::RedrawCell { row: 5, col: 10 });
+    assert_eq!(ui_commands[1], UICommand::RedrawCell { row: 5, col: 10 });
+    assert_eq!(ui_commands[2], UICommand::RedrawCell { row: 5, col: 11 });
+    assert_eq!(ui_commands[3], UICommand::RedrawCell { row: 5, col: 11 });
+    assert_eq!(ui_commands[4], UICommand::CPUHalted);
+    // The cursor registers should read back.
+    let mmu = internal!(cpu).mmu.lock().unwrap();
+    assert_eq!(mmu.load_physical_8(0x19C1), Ok(5));
+    assert_eq!(mmu.load_physical_8(0x19C2), Ok(11));
+    assert_eq!(mmu.load_physical_8(0x19C3), Ok(1));
+    assert_eq!(mmu.load_physical_8(0x19C4), Ok(3));
+}
+
+#[test]
+#[timeout(100)]
+fn test_display_pages() {
+    let mut rom = [0; ROM_SIZE];
+    // Scroll down by one row.
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x10; // into r0b
+    rom[2] = 0x01; // 1 row.
+    rom[3] = 0x08; // Store into
+    rom[4] = 0x00;
+    rom[5] = 0x00;
+    rom[6] = 0x19;
+    rom[7] = 0xC7; // scroll offset register
+    rom[8] = 0x10; // r0b.
+
+    // Write to the top row of the buffer, which is now at the bottom of the screen.
+    rom[9] = 0x0A; // Copy literal
+    rom[10] = 0x10; // into r0b
+    rom[11] = 0x41; // character 'A'.
+    rom[12] = 0x08; // Store into
+    rom[13] = 0x00;
+    rom[14] = 0x00;
+    rom[15] = 0x02;
+    rom[16] = 0x40; // display cell (r0,c0)
+    rom[17] = 0x10; // r0b.
+
+    // Select page 1 for access, and write to it while it's not visible.
+    rom[18] = 0x0A; // Copy literal
+    rom[19] = 0x10; // into r0b
+    rom[20] = 0x01; // page 1.
+    rom[21] = 0x08; // Store into
+    rom[22] = 0x00;
+    rom[23] = 0x00;
+    rom[24] = 0x19;
+    rom[25] = 0xC6; // memory page register
+    rom[26] = 0x10; // r0b.
+    rom[27] = 0x0A; // Copy literal
+    rom[28] = 0x10; // into r0b
+    rom[29] = 0x42; // character 'B'.
+    rom[30] = 0x08; // Store into
+    rom[31] = 0x00;
+    rom[32] = 0x00;
+    rom[33] = 0x02;
+    rom[34] = 0x40; // display cell (r0,c0)
+    rom[35] = 0x10; // r0b.
+
+    // Flip to page 1.
+    rom[36] = 0x0A; // Copy literal
+    rom[37] = 0x10; // into r0b
+    rom[38] = 0x01; // page 1.
+    rom[39] = 0x08; // Store into
+    rom[40] = 0x00;
+    rom[41] = 0x00;
+    rom[42] = 0x19;
+    rom[43] = 0xC5; // visible page register
+    rom[44] = 0x10; // r0b.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 4);
+    assert_eq!(ui_commands[0], UICommand::RedrawAll);
+    assert_eq!(ui_commands[1], UICommand::RedrawCell { row: 24, col: 0 });
+    assert_eq!(ui_commands[2], UICommand::RedrawAll);
+    assert_eq!(ui_commands[3], UICommand::CPUHalted);
+    // The registers should read back.
+    let mmu = internal!(cpu).mmu.lock().unwrap();
+    assert_eq!(mmu.load_physical_8(0x19C5), Ok(1));
+    assert_eq!(mmu.load_physical_8(0x19C6), Ok(1));
+    assert_eq!(mmu.load_physical_8(0x19C7), Ok(1));
+    // Reads should come from the selected memory page.
+    assert_eq!(mmu.load_physical_8(0x0240), Ok(0x42));
+}
+
+#[test]
+#[timeout(100)]
+fn test_syscall() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x22; // into kspr
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x50;
+    rom[5] = 0x00; // address 0x00005000.
+
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x00; // into r0
+    rom[8] = 0x00;
+    rom[9] = 0x00;
+    rom[10] = 0x00;
+    rom[11] = 0xC0; // ROM byte 0x80 (128).
+
+    rom[12] = 0x08; // Store into
+    rom[13] = 0x00;
+    rom[14] = 0x00;
+    rom[15] = 0x00;
+    rom[16] = 0x1C; // syscall interrupt handler
+    rom[17] = 0x00; // r0.
+
+    rom[18] = 0x0A; // Copy literal
+    rom[19] = 0x24; // into imr
+    rom[20] = 0x00;
+    rom[21] = 0x80; // syscall only.
+
+    rom[22] = 0x6B; // SYSCALL.
+
+    rom[23] = 0x01; // Pause (fail condition).
+
+    // Interrupt handler.
+    rom[128] = 0x0A; // Copy literal
+    rom[129] = 0x17; // into r7b
+    rom[130] = 0x42; // some number.
+
+    rom[131] = 0x00; // HALT.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[7], 0x42);
+}
+
+#[test]
+#[timeout(100)]
+fn test_syscall_number() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x22; // into kspr
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x50;
+    rom[5] = 0x00; // address 0x00005000.
+
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x10; // into r0b
+    rom[8] = 0xC0; // ROM byte 0x80 (128).
+
+    rom[9] = 0x08; // Store into
+    rom[10] = 0x00;
+    rom[11] = 0x00;
+    rom[12] = 0x00;
+    rom[13] = 0x1C; // syscall interrupt handler
+    rom[14] = 0x00; // r0.
+
+    rom[15] = 0x0A; // Copy literal
+    rom[16] = 0x24; // into imr
+    rom[17] = 0x00;
+    rom[18] = 0x80; // syscall only.
+
+    rom[19] = 0x0A; // Copy literal
+    rom[20] = 0x11; // into r1b
+    rom[21] = 0x05; // 5.
+
+    rom[22] = 0xB6; // SYSCALL
+    rom[23] = 0x00;
+    rom[24] = 0x00;
+    rom[25] = 0x01;
+    rom[26] = 0x00; // number 0x100.
+
+    rom[27] = 0xB7; // SYSCALL
+    rom[28] = 0x01; // number in r1.
+
+    rom[29] = 0x6B; // SYSCALL without a number.
+
+    rom[30] = 0x0B; // Copy register
+    rom[31] = 0x05; // into r5
+    rom[32] = 0x33; // from scnr.
+
+    rom[33] = 0x00; // HALT.
+
+    // Interrupt handler.
+    rom[128] = 0x22; // Add register
+    rom[129] = 0x07; // to r7
+    rom[130] = 0x33; // scnr.
+
+    rom[131] = 0x05; // IRETURN.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[7], 0x105);
+    assert_eq!(internal!(cpu).r[5], 0);
+}
+
+#[test]
+#[timeout(100)]
+fn test_interrupt_vector_base() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x22; // into kspr
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x50;
+    rom[5] = 0x00; // address 0x00005000.
+
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x30; // into ivbr
+    rom[8] = 0x00;
+    rom[9] = 0x00;
+    rom[10] = 0x60;
+    rom[11] = 0x00; // address 0x00006000.
+
+    rom[12] = 0x0A; // Copy literal
+    rom[13] = 0x10; // into r0b
+    rom[14] = 0xC0; // ROM byte 0x80 (128).
+
+    rom[15] = 0x08; // Store into
+    rom[16] = 0x00;
+    rom[17] = 0x00;
+    rom[18] = 0x60;
+    rom[19] = 0x1C; // relocated syscall interrupt handler
+    rom[20] = 0x00; // r0.
+
+    rom[21] = 0x0A; // Copy literal
+    rom[22] = 0x24; // into imr
+    rom[23] = 0x00;
+    rom[24] = 0x80; // syscall only.
+
+    rom[25] = 0x6B; // SYSCALL.
+
+    rom[26] = 0x01; // Pause (fail condition).
+
+    // Interrupt handler.
+    rom[128] = 0x0B; // Copy register
+    rom[129] = 0x07; // into r7
+    rom[130] = 0x30; // from ivbr.
+
+    rom[131] = 0x00; // HALT.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[7], 0x00006000);
+}
+
+#[test]
+#[timeout(100)]
+fn test_interrupt_priority_order() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x22; // into kspr
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x50;
+    rom[5] = 0x00; // address 0x00005000.
+
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x10; // into r0b
+    rom[8] = 0xC0; // ROM byte 0x80 (128).
+
+    rom[9] = 0x08; // Store into
+    rom[10] = 0x00;
+    rom[11] = 0x00;
+    rom[12] = 0x00;
+    rom[13] = 0x1C; // syscall interrupt handler
+    rom[14] = 0x00; // r0.
+
+    rom[15] = 0x0A; // Copy literal
+    rom[16] = 0x10; // into r0b
+    rom[17] = 0xE0; // ROM byte 0xA0 (160).
+
+    rom[18] = 0x08; // Store into
+    rom[19] = 0x00;
+    rom[20] = 0x00;
+    rom[21] = 0x00;
+    rom[22] = 0x28; // IPI interrupt handler
+    rom[23] = 0x00; // r0.
+
+    // Both of these will be latched, as all interrupts are disabled.
+    rom[24] = 0x6B; // SYSCALL.
+
+    rom[25] = 0x6E; // Send an IPI
+    rom[26] = 0x00;
+    rom[27] = 0x00;
+    rom[28] = 0x00;
+    rom[29] = 0x00; // to this core.
+
+    rom[30] = 0x0A; // Copy literal
+    rom[31] = 0x31; // into ipr
+    rom[32] = 0x00;
+    rom[33] = 0x10;
+    rom[34] = 0x00;
+    rom[35] = 0x00; // IPI at priority level 1.
+
+    rom[36] = 0x0A; // Copy literal
+    rom[37] = 0x24; // into imr
+    rom[38] = 0x04;
+    rom[39] = 0x80; // IPI and syscall.
+
+    rom[40] = 0x01; // Pause (fail condition).
+
+    // Syscall handler.
+    rom[128] = 0x21; // Add literal
+    rom[129] = 0x17; // to r7b
+    rom[130] = 0x01; // 1.
+
+    rom[131] = 0x0B; // Copy register
+    rom[132] = 0x15; // into r5b
+    rom[133] = 0x17; // from r7b.
+
+    rom[134] = 0x00; // HALT.
+
+    // IPI handler.
+    rom[160] = 0x21; // Add literal
+    rom[161] = 0x17; // to r7b
+    rom[162] = 0x01; // 1.
+
+    rom[163] = 0x0B; // Copy register
+    rom[164] = 0x16; // into r6b
+    rom[165] = 0x17; // from r7b.
+
+    rom[166] = 0x05; // IRETURN.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    // The IPI has the higher priority, so should be serviced first despite
+    // its higher number.
+    assert_eq!(internal!(cpu).r[6], 1);
+    assert_eq!(internal!(cpu).r[5], 2);
+}
+
+#[test]
+#[timeout(100)]
+fn test_interrupt_preemption() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x22; // into kspr
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x50;
+    rom[5] = 0x00; // address 0x00005000.
+
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x10; // into r0b
+    rom[8] = 0xC0; // ROM byte 0x80 (128).
+
+    rom[9] = 0x08; // Store into
+    rom[10] = 0x00;
+    rom[11] = 0x00;
+    rom[12] = 0x00;
+    rom[13] = 0x1C; // syscall interrupt handler
+    rom[14] = 0x00; // r0.
+
+    rom[15] = 0x0A; // Copy literal
+    rom[16] = 0x10; // into r0b
+    rom[17] = 0xE0; // ROM byte 0xA0 (160).
+
+    rom[18] = 0x08; // Store into
+    rom[19] = 0x00;
+    rom[20] = 0x00;
+    rom[21] = 0x00;
+    rom[22] = 0x28; // IPI interrupt handler
+    rom[23] = 0x00; // r0.
+
+    rom[24] = 0x0A; // Copy literal
+    rom[25] = 0x31; // into ipr
+    rom[26] = 0x00;
+    rom[27] = 0x10;
+    rom[28] = 0x00;
+    rom[29] = 0x00; // IPI at priority level 1.
+
+    rom[30] = 0x0A; // Copy literal
+    rom[31] = 0x24; // into imr
+    rom[32] = 0x04;
+    rom[33] = 0x80; // IPI and syscall.
+
+    rom[34] = 0x6B; // SYSCALL.
+
+    rom[35] = 0x01; // Pause (fail condition).
+
+    // Syscall handler.
+    rom[128] = 0x0A; // Copy literal
+    rom[129] = 0x17; // into r7b
+    rom[130] = 0x01; // 1.
+
+    rom[131] = 0x6E; // Send an IPI
+    rom[132] = 0x00;
+    rom[133] = 0x00;
+    rom[134] = 0x00;
+    rom[135] = 0x00; // to this core.
+
+    rom[136] = 0x0A; // Copy literal
+    rom[137] = 0x17; // into r7b
+    rom[138] = 0x02; // 2.
+
+    rom[139] = 0x0B; // Copy register
+    rom[140] = 0x0D; // into r5h
+    rom[141] = 0x24; // from imr.
+
+    rom[142] = 0x00; // HALT.
+
+    // IPI handler.
+    rom[160] = 0x0B; // Copy register
+    rom[161] = 0x16; // into r6b
+    rom[162] = 0x17; // from r7b.
+
+    rom[163] = 0x05; // IRETURN.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    // The IPI should have pre-empted the syscall handler.
+    assert_eq!(internal!(cpu).r[6], 1);
+    assert_eq!(internal!(cpu).r[7], 2);
+    // Only the IPI should have been enabled within the syscall handler.
+    assert_eq!(internal!(cpu).r[5], 0x0400);
+}
+
+#[test]
+#[timeout(100)]
+fn test_interrupt_pending() {
+    let mut rom = [0; ROM_SIZE];
+    // All interrupts are disabled, so raised interrupts stay latched.
+    rom[0] = 0xB4; // Raise
+    rom[1] = 0x00;
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x06; // the timer interrupt.
+
+    rom[5] = 0x0A; // Copy literal
+    rom[6] = 0x10; // into r0b
+    rom[7] = 0x03; // the keyboard interrupt.
+
+    rom[8] = 0xB5; // Raise
+    rom[9] = 0x00; // the interrupt in r0.
+
+    rom[10] = 0x0B; // Copy register
+    rom[11] = 0x09; // into r1h
+    rom[12] = 0x32; // from ipnd.
+
+    rom[13] = 0x0A; // Copy literal
+    rom[14] = 0x32; // into ipnd
+    rom[15] = 0x00;
+    rom[16] = 0x40; // clearing the timer interrupt.
+
+    rom[17] = 0x0B; // Copy register
+    rom[18] = 0x0A; // into r2h
+    rom[19] = 0x32; // from ipnd.
+
+    rom[20] = 0x00; // HALT.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[1], 0x0048);
+    assert_eq!(internal!(cpu).r[2], 0x0008);
+}
+
+#[test]
+#[timeout(100)]
+fn test_raise_bad_interrupt() {
+    let mut rom = [0; ROM_SIZE];
+    // Try to raise a nonexistent interrupt. Should raise an illegal
+    // operation interrupt instead.
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x22; // into kspr
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x50;
+    rom[5] = 0x00; // address 0x00005000.
+
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x24; // into imr
+    rom[8] = 0x00;
+    rom[9] = 0x01; // illegal operation interrupt only.
+
+    rom[10] = 0xB4; // Raise
+    rom[11] = 0x00;
+    rom[12] = 0x00;
+    rom[13] = 0x00;
+    rom[14] = 0x10; // interrupt 16.
+
+    rom[15] = 0x01; // Pause. We should never hit this.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    // Address 0 is HALT, so we should have halted in the handler.
+    assert_eq!(internal!(cpu).program_counter, 0x0001);
+}
+
+#[test]
+#[timeout(100)]
+fn test_double_fault() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x22; // into kspr
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x01;
+    rom[5] = 0x00; // address 0x00000100, which is in read-only ROM.
+
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x34; // into espr
+    rom[8] = 0x00;
+    rom[9] = 0x00;
+    rom[10] = 0x50;
+    rom[11] = 0x00; // address 0x00005000.
+
+    rom[12] = 0x0A; // Copy literal
+    rom[13] = 0x10; // into r0b
+    rom[14] = 0xC0; // ROM byte 0x80 (128).
+
+    rom[15] = 0x08; // Store into
+    rom[16] = 0x00;
+    rom[17] = 0x00;
+    rom[18] = 0x00;
+    rom[19] = 0x30; // double fault interrupt handler
+    rom[20] = 0x00; // r0.
+
+    rom[21] = 0x0A; // Copy literal
+    rom[22] = 0x24; // into imr
+    rom[23] = 0x00;
+    rom[24] = 0x80; // syscall only.
+
+    rom[25] = 0x6B; // SYSCALL, which can't push to the kernel stack.
+
+    rom[26] = 0x01; // Pause (fail condition).
+
+    // Double fault handler.
+    rom[128] = 0x0B; // Copy register
+    rom[129] = 0x07; // into r7
+    rom[130] = 0x22; // from kspr.
+
+    rom[131] = 0x06; // Load literal address
+    rom[132] = 0x06; // into r6
+    rom[133] = 0x00;
+    rom[134] = 0x00;
+    rom[135] = 0x4F;
+    rom[136] = 0xF8; // the failed interrupt number.
+
+    rom[137] = 0x06; // Load literal address
+    rom[138] = 0x05; // into r5
+    rom[139] = 0x00;
+    rom[140] = 0x00;
+    rom[141] = 0x4F;
+    rom[142] = 0xFC; // the old kspr.
+
+    rom[143] = 0x00; // HALT.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(cpu.fatal_error(0), None);
+    // The emergency stack holds the old kspr, the interrupt number, and the
+    // usual flags, program counter and IMR.
+    assert_eq!(internal!(cpu).r[7], 0x00004FF0);
+    assert_eq!(internal!(cpu).r[6], INTERRUPT_SYSCALL);
+    assert_eq!(internal!(cpu).r[5], 0x00000100);
+}
+
+#[test]
+#[timeout(100)]
+fn test_double_fault_discards_illegal_operation() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x22; // into kspr
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x01;
+    rom[5] = 0x00; // address 0x00000100, which is in read-only ROM.
+
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x34; // into espr
+    rom[8] = 0x00;
+    rom[9] = 0x00;
+    rom[10] = 0x50;
+    rom[11] = 0x00; // address 0x00005000.
+
+    rom[12] = 0x0A; // Copy literal
+    rom[13] = 0x10; // into r0b
+    rom[14] = 0xC0; // ROM byte 0x80 (128).
+
+    rom[15] = 0x08; // Store into
+    rom[16] = 0x00;
+    rom[17] = 0x00;
+    rom[18] = 0x00;
+    rom[19] = 0x30; // double fault interrupt handler
+    rom[20] = 0x00; // r0.
+
+    rom[21] = 0x0A; // Copy literal
+    rom[22] = 0x11; // into r1b
+    rom[23] = 0xE0; // ROM byte 0xA0 (160).
+
+    rom[24] = 0x08; // Store into
+    rom[25] = 0x00;
+    rom[26] = 0x00;
+    rom[27] = 0x00;
+    rom[28] = 0x00; // illegal operation interrupt handler
+    rom[29] = 0x01; // r1.
+
+    rom[30] = 0x0A; // Copy literal
+    rom[31] = 0x24; // into imr
+    rom[32] = 0x00;
+    rom[33] = 0x80; // syscall only.
+
+    rom[34] = 0x6B; // SYSCALL, which can't push to the kernel stack.
+
+    rom[35] = 0x01; // Pause (fail condition).
+
+    // Double fault handler.
+    rom[128] = 0x0A; // Copy literal
+    rom[129] = 0x24; // into imr
+    rom[130] = 0x00;
+    rom[131] = 0x01; // illegal operation only.
+
+    rom[132] = 0x0A; // Copy literal
+    rom[133] = 0x03; // into r3
+    rom[134] = 0x00;
+    rom[135] = 0x00;
+    rom[136] = 0x60;
+    rom[137] = 0x0D; // 0x600D.
+
+    rom[138] = 0x00; // HALT.
+
+    // Illegal operation handler.
+    rom[160] = 0x0A; // Copy literal
+    rom[161] = 0x03; // into r3
+    rom[162] = 0x00;
+    rom[163] = 0x00;
+    rom[164] = 0x0B;
+    rom[165] = 0xAD; // 0xBAD (fail condition).
+
+    rom[166] = 0x00; // HALT.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(cpu.fatal_error(0), None);
+    // The failed push must not leave an illegal operation interrupt latched.
+    assert_eq!(internal!(cpu).r[3], 0x600D);
+}
+
+#[test]
+#[timeout(100)]
+fn test_double_fault_halt() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x22; // into kspr
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x01;
+    rom[5] = 0x00; // address 0x00000100, which is in read-only ROM.
+
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x34; // into espr
+    rom[8] = 0x00;
+    rom[9] = 0x00;
+    rom[10] = 0x01;
+    rom[11] = 0x00; // the same address.
+
+    rom[12] = 0x0A; // Copy literal
+    rom[13] = 0x24; // into imr
+    rom[14] = 0x00;
+    rom[15] = 0x80; // syscall only.
+
+    rom[16] = 0x6B; // SYSCALL, which can't push to either stack.
+
+    rom[17] = 0x01; // Pause (fail condition).
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 2);
+    let reason = cpu.fatal_error(0).unwrap();
+    assert!(reason.contains("double fault"));
+    assert!(reason.contains("interrupt 7"));
+    // The UI is told straight away.
+    assert_eq!(
+        ui_commands[0],
+        UICommand::CPUFatalError {
+            core: 0,
+            reason: reason.to_string()
+        }
+    );
+}
+
+#[test]
+#[timeout(100)]
+fn test_bad_reg_ref() {
+    let mut rom = [0; ROM_SIZE];
+    // Try a copy with unmatched sizes. Should raise an interrupt.
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x22; // into kspr
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x50;
+    rom[5] = 0x00; // address 0x00005000.
+
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x24; // into imr
+    rom[8] = 0x00;
+    rom[9] = 0x01; // illegal operation interrupt only.
+
+    rom[10] = 0x0B; // Copy register
+    rom[11] = 0x01; // into r1
+    rom[12] = 0x0B; // from r3h.
+
+    rom[13] = 0x01; // Pause. We should never hit this.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[1], 0x00000000);
+}
+
+#[test]
+#[timeout(100)]
+fn test_trace() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x22; // into kspr
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0xA0;
+    rom[5] = 0x00; // address 0x0000A000.
+
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x23; // into pdpr
+    rom[8] = 0x00;
+    rom[9] = 0x00;
+    rom[10] = 0x00;
+    rom[11] = 0xC0; // ROM byte 0x80 (128).
+
+    // Write the page table entry to main memory.
+    rom[12] = 0x0A; // Copy literal
+    rom[13] = 0x00; // into r0
+    rom[14] = 0x00;
+    rom[15] = 0x00;
+    rom[16] = 0x40;
+    rom[17] = 0x1F; // Valid, Present, RWX entry at 0x00004000.
+
+    rom[18] = 0x08; // Store into
+    rom[19] = 0x00;
+    rom[20] = 0x00;
+    rom[21] = 0xB0;
+    rom[22] = 0x00; // address 0x0000B000
+    rom[23] = 0x00; // r0.
+
+    // Write the user mode instructions to RAM: add 1 to r1b twice, then
+    // execute the privileged HALT, which is illegal.
+    rom[24] = 0x0A; // Copy literal
+    rom[25] = 0x00; // into r0
+    rom[26] = 0x21; // ADD literal
+    rom[27] = 0x11; // r1b
+    rom[28] = 0x01; // 1
+    rom[29] = 0x21; // ADD literal.
+
+    rom[30] = 0x08; // Store into
+    rom[31] = 0x00;
+    rom[32] = 0x00;
+    rom[33] = 0x40;
+    rom[34] = 0x00; // address 0x00004000
+    rom[35] = 0x00; // r0.
+
+    rom[36] = 0x0A; // Copy literal
+    rom[37] = 0x08; // into r0h
+    rom[38] = 0x11; // r1b
+    rom[39] = 0x01; // 1.
+
+    rom[40] = 0x08; // Store into
+    rom[41] = 0x00;
+    rom[42] = 0x00;
+    rom[43] = 0x40;
+    rom[44] = 0x04; // address 0x00004004
+    rom[45] = 0x08; // r0h.
+
+    // Install the debug handler.
+    rom[46] = 0x0A; // Copy literal
+    rom[47] = 0x00; // into r0
+    rom[48] = 0x00;
+    rom[49] = 0x00;
+    rom[50] = 0x00;
+    rom[51] = 0xA4; // ROM byte 100.
+
+    rom[52] = 0x08; // Store into
+    rom[53] = 0x00;
+    rom[54] = 0x00;
+    rom[55] = 0x00;
+    rom[56] = 0x34; // debug interrupt handler address
+    rom[57] = 0x00; // r0.
+
+    // Enter user mode with the trace flag set via IRETURN.
+    rom[58] = 0x0A; // Copy literal
+    rom[59] = 0x08; // into r0h
+    rom[60] = 0x00;
+    rom[61] = 0x10; // trace flag, user mode.
+
+    rom[62] = 0x0E; // Push
+    rom[63] = 0x08; // r0h.
+
+    rom[64] = 0x0A; // Copy literal
+    rom[65] = 0x00; // into r0
+    rom[66] = 0x00;
+    rom[67] = 0x00;
+    rom[68] = 0x00;
+    rom[69] = 0x00; // virtual address 0x0.
+
+    rom[70] = 0x0E; // Push
+    rom[71] = 0x00; // r0.
+
+    rom[72] = 0x0A; // Copy literal
+    rom[73] = 0x08; // into r0h
+    rom[74] = 0x20;
+    rom[75] = 0x01; // illegal operation and debug interrupts.
+
+    rom[76] = 0x0E; // Push
+    rom[77] = 0x08; // r0h.
+
+    rom[78] = 0x05; // IRETURN.
+
+    // Debug handler: count the steps.
+    rom[100] = 0x21; // Add literal
+    rom[101] = 0x12; // into r2b
+    rom[102] = 0x01; // 1.
+
+    rom[103] = 0x05; // IRETURN.
+
+    // Page directory entry.
+    rom[128] = 0x00;
+    rom[129] = 0x00;
+    rom[130] = 0xB0;
+    rom[131] = 0x01; // Valid entry at 0x0000B000.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[1], 2);
+    // Only the two successful instructions trapped, not the illegal one.
+    assert_eq!(internal!(cpu).r[2], 2);
+    // Entering the illegal operation handler cleared the trace flag.
+    assert!(!internal!(cpu).trace);
+    assert_eq!(internal!(cpu).program_counter, 1);
+}
+
+#[test]
+#[timeout(100)]
+fn test_breakpoint_execute() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x22; // into kspr
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0xA0;
+    rom[5] = 0x00; // address 0x0000A000.
+
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x23; // into pdpr
+    rom[8] = 0x00;
+    rom[9] = 0x00;
+    rom[10] = 0x00;
+    rom[11] = 0xC0; // ROM byte 0x80 (128).
+
+    // Write the page table entry to main memory.
+    rom[12] = 0x0A; // Copy literal
+    rom[13] = 0x00; // into r0
+    rom[14] = 0x00;
+    rom[15] = 0x00;
+    rom[16] = 0x40;
+    rom[17] = 0x1F; // Valid, Present, RWX entry at 0x00004000.
+
+    rom[18] = 0x08; // Store into
+    rom[19] = 0x00;
+    rom[20] = 0x00;
+    rom[21] = 0xB0;
+    rom[22] = 0x00; // address 0x0000B000
+    rom[23] = 0x00; // r0.
+
+    // Write the user mode instructions to RAM: add 1 to r1b twice.
+    rom[24] = 0x0A; // Copy literal
+    rom[25] = 0x00; // into r0
+    rom[26] = 0x21; // ADD literal
+    rom[27] = 0x11; // r1b
+    rom[28] = 0x01; // 1
+    rom[29] = 0x21; // ADD literal.
+
+    rom[30] = 0x08; // Store into
+    rom[31] = 0x00;
+    rom[32] = 0x00;
+    rom[33] = 0x40;
+    rom[34] = 0x00; // address 0x00004000
+    rom[35] = 0x00; // r0.
+
+    rom[36] = 0x0A; // Copy literal
+    rom[37] = 0x08; // into r0h
+    rom[38] = 0x11; // r1b
+    rom[39] = 0x01; // 1.
+
+    rom[40] = 0x08; // Store into
+    rom[41] = 0x00;
+    rom[42] = 0x00;
+    rom[43] = 0x40;
+    rom[44] = 0x04; // address 0x00004004
+    rom[45] = 0x08; // r0h.
+
+    // Install the debug handler.
+    rom[46] = 0x0A; // Copy literal
+    rom[47] = 0x00; // into r0
+    rom[48] = 0x00;
+    rom[49] = 0x00;
+    rom[50] = 0x00;
+    rom[51] = 0xA4; // ROM byte 100.
+
+    rom[52] = 0x08; // Store into
+    rom[53] = 0x00;
+    rom[54] = 0x00;
+    rom[55] = 0x00;
+    rom[56] = 0x34; // debug interrupt handler address
+    rom[57] = 0x00; // r0.
+
+    // Break on executing the second ADD.
+    rom[58] = 0x0A; // Copy literal
+    rom[59] = 0x35; // into da0
+    rom[60] = 0x00;
+    rom[61] = 0x00;
+    rom[62] = 0x00;
+    rom[63] = 0x03; // virtual address 0x3.
+
+    rom[64] = 0x0A; // Copy literal
+    rom[65] = 0x39; // into dcr
+    rom[66] = 0x00;
+    rom[67] = 0x00;
+    rom[68] = 0x00;
+    rom[69] = 0x01; // execute, length 1.
+
+    // Enter user mode via IRETURN.
+    rom[70] = 0x0A; // Copy literal
+    rom[71] = 0x08; // into r0h
+    rom[72] = 0x00;
+    rom[73] = 0x00; // user mode.
+
+    rom[74] = 0x0E; // Push
+    rom[75] = 0x08; // r0h.
+
+    rom[76] = 0x0E; // Push
+    rom[77] = 0x00; // r0 (virtual address 0x0).
+
+    rom[78] = 0x0A; // Copy literal
+    rom[79] = 0x08; // into r0h
+    rom[80] = 0x20;
+    rom[81] = 0x01; // illegal operation and debug interrupts.
+
+    rom[82] = 0x0E; // Push
+    rom[83] = 0x08; // r0h.
+
+    rom[84] = 0x05; // IRETURN.
+
+    // Debug handler: read the DSR and halt.
+    rom[100] = 0x0B; // Copy ref
+    rom[101] = 0x0B; // into r3h
+    rom[102] = 0x3A; // from dsr.
+
+    rom[103] = 0x00; // HALT.
+
+    // Page directory entry.
+    rom[128] = 0x00;
+    rom[129] = 0x00;
+    rom[130] = 0xB0;
+    rom[131] = 0x01; // Valid entry at 0x0000B000.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    // The second ADD never executed.
+    assert_eq!(internal!(cpu).r[1], 1);
+    assert_eq!(internal!(cpu).r[3], 0x0001);
+    assert_eq!(internal!(cpu).program_counter, 0xA8);
+    // The saved program counter points at the second ADD.
+    assert_eq!(
+        internal!(cpu)
+            .mmu
+            .lock()
+            .unwrap()
+            .load_physical_32(0x00009FFA),
+        Ok(0x00000003)
+    );
+}
+
+#[test]
+#[timeout(100)]
+fn test_watchpoint() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x22; // into kspr
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0xA0;
+    rom[5] = 0x00; // address 0x0000A000.
+
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x23; // into pdpr
+    rom[8] = 0x00;
+    rom[9] = 0x00;
+    rom[10] = 0x00;
+    rom[11] = 0xC0; // ROM byte 0x80 (128).
+
+    // Write the page table entry to main memory.
+    rom[12] = 0x0A; // Copy literal
+    rom[13] = 0x00; // into r0
+    rom[14] = 0x00;
+    rom[15] = 0x00;
+    rom[16] = 0x40;
+    rom[17] = 0x1F; // Valid, Present, RWX entry at 0x00004000.
+
+    rom[18] = 0x08; // Store into
+    rom[19] = 0x00;
+    rom[20] = 0x00;
+    rom[21] = 0xB0;
+    rom[22] = 0x00; // address 0x0000B000
+    rom[23] = 0x00; // r0.
+
+    // Write the user mode instructions to RAM: load r2b from 0x102, then
+    // store r1 to 0x100.
+    rom[24] = 0x0A; // Copy literal
+    rom[25] = 0x00; // into r0
+    rom[26] = 0x06; // LOAD literal
+    rom[27] = 0x12; // r2b
+    rom[28] = 0x00;
+    rom[29] = 0x00;
+
+    rom[30] = 0x08; // Store into
+    rom[31] = 0x00;
+    rom[32] = 0x00;
+    rom[33] = 0x40;
+    rom[34] = 0x00; // address 0x00004000
+    rom[35] = 0x00; // r0.
+
+    rom[36] = 0x0A; // Copy literal
+    rom[37] = 0x00; // into r0
+    rom[38] = 0x01;
+    rom[39] = 0x02; // address 0x00000102
+    rom[40] = 0x08; // STORE literal
+    rom[41] = 0x00;
+
+    rom[42] = 0x08; // Store into
+    rom[43] = 0x00;
+    rom[44] = 0x00;
+    rom[45] = 0x40;
+    rom[46] = 0x04; // address 0x00004004
+    rom[47] = 0x00; // r0.
+
+    rom[48] = 0x0A; // Copy literal
+    rom[49] = 0x00; // into r0
+    rom[50] = 0x00;
+    rom[51] = 0x01;
+    rom[52] = 0x00; // address 0x00000100
+    rom[53] = 0x01; // r1.
+
+    rom[54] = 0x08; // Store into
+    rom[55] = 0x00;
+    rom[56] = 0x00;
+    rom[57] = 0x40;
+    rom[58] = 0x08; // address 0x00004008
+    rom[59] = 0x00; // r0.
+
+    // Give the user process something to load.
+    rom[60] = 0x0A; // Copy literal
+    rom[61] = 0x10; // into r0b
+    rom[62] = 0x55; // some random number.
+
+    rom[63] = 0x08; // Store into
+    rom[64] = 0x00;
+    rom[65] = 0x00;
+    rom[66] = 0x41;
+    rom[67] = 0x02; // address 0x00004102
+    rom[68] = 0x10; // r0b.
+
+    rom[69] = 0x0A; // Copy literal
+    rom[70] = 0x01; // into r1
+    rom[71] = 0xDE;
+    rom[72] = 0xAD;
+    rom[73] = 0xBE;
+    rom[74] = 0xEF; // some random number.
+
+    // Install the debug handler.
+    rom[75] = 0x0A; // Copy literal
+    rom[76] = 0x00; // into r0
+    rom[77] = 0x00;
+    rom[78] = 0x00;
+    rom[79] = 0x00;
+    rom[80] = 0xB8; // ROM byte 120.
+
+    rom[81] = 0x08; // Store into
+    rom[82] = 0x00;
+    rom[83] = 0x00;
+    rom[84] = 0x00;
+    rom[85] = 0x34; // debug interrupt handler address
+    rom[86] = 0x00; // r0.
+
+    // Watch for reads of 0x103, which the store covers but doesn't read.
+    rom[87] = 0x0A; // Copy literal
+    rom[88] = 0x35; // into da0
+    rom[89] = 0x00;
+    rom[90] = 0x00;
+    rom[91] = 0x01;
+    rom[92] = 0x03; // virtual address 0x103.
+
+    // Watch for writes to 0x102-0x105, which the load reads but doesn't write.
+    rom[93] = 0x0A; // Copy literal
+    rom[94] = 0x37; // into da2
+    rom[95] = 0x00;
+    rom[96] = 0x00;
+    rom[97] = 0x01;
+    rom[98] = 0x02; // virtual address 0x102.
+
+    rom[99] = 0x0A; // Copy literal
+    rom[100] = 0x39; // into dcr
+    rom[101] = 0x00;
+    rom[102] = 0x14; // breakpoint 2: write, length 4
+    rom[103] = 0x00;
+    rom[104] = 0x02; // breakpoint 0: read, length 1.
+
+    // Enter user mode via IRETURN.
+    rom[105] = 0x0A; // Copy literal
+    rom[106] = 0x08; // into r0h
+    rom[107] = 0x00;
+    rom[108] = 0x00; // user mode.
+
+    rom[109] = 0x0E; // Push
+    rom[110] = 0x08; // r0h.
+
+    rom[111] = 0x0E; // Push
+    rom[112] = 0x00; // r0 (virtual address 0x0).
+
+    rom[113] = 0x0A; // Copy literal
+    rom[114] = 0x08; // into r0h
+    rom[115] = 0x20;
+    rom[116] = 0x01; // illegal operation and debug interrupts.
+
+    rom[117] = 0x0E; // Push
+    rom[118] = 0x08; // r0h.
+
+    rom[119] = 0x05; // IRETURN.
+
+    // Debug handler: read the DSR and halt.
+    rom[120] = 0x0B; // Copy ref
+    rom[121] = 0x0B; // into r3h
+    rom[122] = 0x3A; // from dsr.
+
+    rom[123] = 0x00; // HALT.
+
+    // Page directory entry.
+    rom[128] = 0x00;
+    rom[129] = 0x00;
+    rom[130] = 0xB0;
+    rom[131] = 0x01; // Valid entry at 0x0000B000.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[2], 0x55);
+    assert_eq!(internal!(cpu).r[3], 0x0004);
+    // The store never happened.
+    assert_eq!(
+        internal!(cpu)
+            .mmu
+            .lock()
+            .unwrap()
+            .load_physical_32(0x00004100),
+        Ok(0x00005500)
+    );
+    // The saved program counter points at the store.
+    assert_eq!(
+        internal!(cpu)
+            .mmu
+            .lock()
+            .unwrap()
+            .load_physical_32(0x00009FFA),
+        Ok(0x00000006)
+    );
+}
+
+#[test]
+#[timeout(100)]
+fn test_pfsr() {
+    let mut rom = [0; ROM_SIZE];
+    // Set the page directory pointer.
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x23; // into pdpr
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x00;
+    rom[5] = 0xC0; // ROM byte 0x80 (128).
+
+    // Write the page table entry.
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x00; // into r0
+    rom[8] = 0x00;
+    rom[9] = 0x00;
+    rom[10] = 0x50;
+    rom[11] = 0x13; // Valid, Present, Execute entry at 0x00005000.
+
+    rom[12] = 0x08; // Store into
+    rom[13] = 0x00;
+    rom[14] = 0x00;
+    rom[15] = 0xB0;
+    rom[16] = 0x00; // address 0x0000B000
+    rom[17] = 0x00; // r0.
+
+    // Write the user mode instructions to RAM.
+
+    // (Load an execute-only address, which is not allowed).
+    rom[18] = 0x0A; // Copy literal
+    rom[19] = 0x08; // into r0h
+    rom[20] = 0x06; // LOAD instruction
+    rom[21] = 0x17; // into r7b.
+
+    rom[22] = 0x08; // Store into
+    rom[23] = 0x00;
+    rom[24] = 0x00;
+    rom[25] = 0x50;
+    rom[26] = 0x00; // address 0x00005000
+    rom[27] = 0x08; // r0h.
+
+    rom[28] = 0x0A; // Copy literal
+    rom[29] = 0x00; // into r0
+    rom[30] = 0x00;
+    rom[31] = 0x00;
+    rom[32] = 0x00;
+    rom[33] = 0x04; // virtual address 0x00000004.
+
+    rom[34] = 0x08; // Store into
+    rom[35] = 0x00;
+    rom[36] = 0x00;
+    rom[37] = 0x50;
+    rom[38] = 0x02; // address 0x00005002
+    rom[39] = 0x00; // r0.
+
+    // (Pause, which should never be hit).
+    rom[40] = 0x0A; // Copy literal
+    rom[41] = 0x10; // into r0b
+    rom[42] = 0x01; // PAUSE instruction.
+
+    rom[43] = 0x08; // Store into
+    rom[44] = 0x00;
+    rom[45] = 0x00;
+    rom[46] = 0x50;
+    rom[47] = 0x06; // address 0x00005006.
+    rom[48] = 0x10; // r0b.
+
+    // Set the kernel stack pointer.
+    rom[49] = 0x0A; // Copy literal
+    rom[50] = 0x22; // into kspr
+    rom[51] = 0x00;
+    rom[52] = 0x00;
+    rom[53] = 0xA0;
+    rom[54] = 0x00; // address 0x0000A000.
+
+    // Enable page fault interrupts.
+    rom[55] = 0x0A; // Copy literal
+    rom[56] = 0x24; // into imr
+    rom[57] = 0x00;
+    rom[58] = 0x04; // page fault interrupt only.
+
+    // Set page fault interrupt handler
+    rom[59] = 0x0A; // Copy literal
+    rom[60] = 0x00; // into r0
+    rom[61] = 0x00;
+    rom[62] = 0x00;
+    rom[63] = 0x01;
+    rom[64] = 0x40; // ROM byte 0x100 (256).
+
+    rom[65] = 0x08; // Store into
+    rom[66] = 0x00;
+    rom[67] = 0x00;
+    rom[68] = 0x00;
+    rom[69] = 0x08; // page fault handler
+    rom[70] = 0x00; // r0.
+
+    // Push the user mode address to the stack.
+    rom[71] = 0x0A; // Copy literal
+    rom[72] = 0x00; // into r0
+    rom[73] = 0x00;
+    rom[74] = 0x00;
+    rom[75] = 0x00;
+    rom[76] = 0x00; // virtual address 0x0.
+
+    rom[77] = 0x0E; // Push to the stack
+    rom[78] = 0x00; // r0.
+
+    // Enter user mode!
+    rom[79] = 0x04;
+
+    // Page directory entry.
+    rom[128] = 0x00;
+    rom[129] = 0x00;
+    rom[130] = 0xB0;
+    rom[131] = 0x01; // Valid entry at 0x0000B000.
+
+    // Page fault handler.
+    rom[256] = 0x0B; // Copy between registers
+    rom[257] = 0x05; // into r5
+    rom[258] = 0x25; // from pfsr.
+
+    rom[259] = 0x00; // Halt.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    // Assert the user mode process stored in its stack correctly.
+    assert_eq!(
+        internal!(cpu).r[5],
+        internal!(cpu)
+            .mmu
+            .lock()
+            .unwrap()
+            .page_fault_status_register()
+    );
+    assert_eq!(internal!(cpu).r[5], crate::mmu::PAGE_FAULT_ILLEGAL_ACCESS);
+}
+
+#[test]
+#[timeout(100)]
+fn test_page_fault_return_address() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x22; // into kspr
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0xA0;
+    rom[5] = 0x00; // address 0x0000A000.
+
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x23; // into pdpr
+    rom[8] = 0x00;
+    rom[9] = 0x00;
+    rom[10] = 0x00;
+    rom[11] = 0xC0; // ROM byte 0x80 (128).
+
+    // Write the page table entry to main memory.
+    rom[12] = 0x0A; // Copy literal
+    rom[13] = 0x00; // into r0
+    rom[14] = 0x00;
+    rom[15] = 0x00;
+    rom[16] = 0x40;
+    rom[17] = 0x1F; // Valid, Present, RWX entry at 0x00004000.
+
+    rom[18] = 0x08; // Store into
+    rom[19] = 0x00;
+    rom[20] = 0x00;
+    rom[21] = 0xB0;
+    rom[22] = 0x00; // address 0x0000B000
+    rom[23] = 0x00; // r0.
+
+    // Write the user mode instructions to RAM: add 1 to r1b, then load
+    // from the unmapped address 0x1000.
+    rom[24] = 0x0A; // Copy literal
+    rom[25] = 0x00; // into r0
+    rom[26] = 0x21; // ADD literal
+    rom[27] = 0x11; // r1b
+    rom[28] = 0x01; // 1
+    rom[29] = 0x06; // LOAD literal.
+
+    rom[30] = 0x08; // Store into
+    rom[31] = 0x00;
+    rom[32] = 0x00;
+    rom[33] = 0x40;
+    rom[34] = 0x00; // address 0x00004000
+    rom[35] = 0x00; // r0.
+
+    rom[36] = 0x0A; // Copy literal
+    rom[37] = 0x00; // into r0
+    rom[38] = 0x12; // r2b
+    rom[39] = 0x00;
+    rom[40] = 0x00;
+    rom[41] = 0x10;
+
+    rom[42] = 0x08; // Store into
+    rom[43] = 0x00;
+    rom[44] = 0x00;
+    rom[45] = 0x40;
+    rom[46] = 0x04; // address 0x00004004
+    rom[47] = 0x00; // r0.
+
+    // Install the page fault handler.
+    rom[48] = 0x0A; // Copy literal
+    rom[49] = 0x00; // into r0
+    rom[50] = 0x00;
+    rom[51] = 0x00;
+    rom[52] = 0x00;
+    rom[53] = 0xA4; // ROM byte 100.
+
+    rom[54] = 0x08; // Store into
+    rom[55] = 0x00;
+    rom[56] = 0x00;
+    rom[57] = 0x00;
+    rom[58] = 0x08; // page fault handler address
+    rom[59] = 0x00; // r0.
+
+    rom[60] = 0x0A; // Copy literal
+    rom[61] = 0x24; // into imr
+    rom[62] = 0x00;
+    rom[63] = 0x04; // page fault interrupt only.
+
+    // Enter user mode.
+    rom[64] = 0x0A; // Copy literal
+    rom[65] = 0x00; // into r0
+    rom[66] = 0x00;
+    rom[67] = 0x00;
+    rom[68] = 0x00;
+    rom[69] = 0x00; // virtual address 0x0.
+
+    rom[70] = 0x0E; // Push
+    rom[71] = 0x00; // r0.
+
+    rom[72] = 0x04; // USERMODE.
+
+    // Page fault handler.
+    rom[100] = 0x00; // HALT.
+
+    // Page directory entry.
+    rom[128] = 0x00;
+    rom[129] = 0x00;
+    rom[130] = 0xB0;
+    rom[131] = 0x01; // Valid entry at 0x0000B000.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[1], 1);
+    // The saved program counter points at the faulting LOAD, not somewhere
+    // before it.
+    assert_eq!(
+        internal!(cpu)
+            .mmu
+            .lock()
+            .unwrap()
+            .load_physical_32(0x00009FFA),
+        Ok(0x00000003)
+    );
+}
+
+#[test]
+#[timeout(200)]
+fn test_timer_literal_interval() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x22; // into kspr
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x50;
+    rom[5] = 0x00; // address 0x00005000.
+
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x00; // into r0
+    rom[8] = 0x00;
+    rom[9] = 0x00;
+    rom[10] = 0x40;
+    rom[11] = 0x00; // address 0x00004000.
+
+    rom[12] = 0x08; // Store into
+    rom[13] = 0x00;
+    rom[14] = 0x00;
+    rom[15] = 0x00;
+    rom[16] = 0x18; // timer interrupt handler
+    rom[17] = 0x00; // r0.
+
+    // Address 0x4000 is HALT, so we should halt on interrupt.
+
+    rom[18] = 0x0A; // Copy literal
+    rom[19] = 0x24; // into imr
+    rom[20] = 0x00;
+    rom[21] = 0x40; // timer interrupt only.
+
+    rom[22] = 0x02; // Set timer to
+    rom[23] = 0x00;
+    rom[24] = 0x00;
+    rom[25] = 0x00;
+    rom[26] = 0x64; // 100 milliseconds.
+
+    rom[27] = 0x01; // Pause.
+
+    let (_cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    // Simply by halting we confirm that the test was successful.
+}
+
+#[test]
+#[timeout(200)]
+fn test_timer_reg_interval() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x22; // into kspr
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x50;
+    rom[5] = 0x00; // address 0x00005000.
+
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x00; // into r0
+    rom[8] = 0x00;
+    rom[9] = 0x00;
+    rom[10] = 0x40;
+    rom[11] = 0x00; // address 0x00004000.
+
+    rom[12] = 0x08; // Store into
+    rom[13] = 0x00;
+    rom[14] = 0x00;
+    rom[15] = 0x00;
+    rom[16] = 0x18; // timer interrupt handler
+    rom[17] = 0x00; // r0.
+
+    // Address 0x4000 is HALT, so we should halt on interrupt.
+
+    rom[18] = 0x0A; // Copy literal
+    rom[19] = 0x24; // into imr
+    rom[20] = 0x00;
+    rom[21] = 0x40; // timer interrupt only.
+
+    rom[22] = 0x0A; // Copy literal
+    rom[23] = 0x00; // into r0
+    rom[24] = 0x00;
+    rom[25] = 0x00;
+    rom[26] = 0x00;
+    rom[27] = 0x64; // 100 milliseconds.
+
+    rom[28] = 0x03; // Set timer to
+    rom[29] = 0x00; // interval in r0.
+
+    rom[30] = 0x01; // Pause.
+
+    let (_cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    // Simply by halting we confirm that the test was successful.
+}
+
+#[test]
+#[timeout(100)]
+fn test_interval_timer() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x22; // into kspr
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x50;
+    rom[5] = 0x00; // address 0x00005000.
+
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x00; // into r0
+    rom[8] = 0x00;
+    rom[9] = 0x00;
+    rom[10] = 0x40;
+    rom[11] = 0x00; // address 0x00004000.
+
+    rom[12] = 0x08; // Store into
+    rom[13] = 0x00;
+    rom[14] = 0x00;
+    rom[15] = 0x00;
+    rom[16] = 0x24; // interval timer interrupt handler
+    rom[17] = 0x00; // r0.
+
+    // Address 0x4000 is HALT, so we should halt on interrupt.
+
+    rom[18] = 0x0A; // Copy literal
+    rom[19] = 0x24; // into imr
+    rom[20] = 0x02;
+    rom[21] = 0x00; // interval timer interrupt only.
+
+    rom[22] = 0x0A; // Copy literal
+    rom[23] = 0x00; // into r0
+    rom[24] = 0x00;
+    rom[25] = 0x00;
+    rom[26] = 0x27;
+    rom[27] = 0x10; // 10000 microseconds.
+
+    rom[28] = 0x08; // Store into
+    rom[29] = 0x00;
+    rom[30] = 0x00;
+    rom[31] = 0x19;
+    rom[32] = 0xF8; // channel 0 reload
+    rom[33] = 0x00; // r0.
+
+    rom[34] = 0x0A; // Copy literal
+    rom[35] = 0x10; // into r0b
+    rom[36] = 0x01; // enable, one-shot.
+
+    rom[37] = 0x08; // Store into
+    rom[38] = 0x00;
+    rom[39] = 0x00;
+    rom[40] = 0x1A;
+    rom[41] = 0x08; // channel 0 control
+    rom[42] = 0x10; // r0b.
+
+    rom[43] = 0x01; // Pause.
+    rom[44] = 0x01; // Pause (should never happen, acts as a fail condition).
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    // The handler should have been reached.
+    assert_eq!(internal!(cpu).program_counter, 0x4001);
+}
+
+#[test]
+#[timeout(100)]
+fn test_blockcopy() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x00; // into r0
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x40;
+    rom[5] = 0x00; // address 0x00004000.
+
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x01; // into r1
+    rom[8] = 0x00;
+    rom[9] = 0x00;
+    rom[10] = 0x00;
+    rom[11] = 0x80; // ROM byte 0x40 (64).
+
+    rom[12] = 0x13; // Blockcopy literal length, ref source and dest
+    rom[13] = 0x00;
+    rom[14] = 0x00;
+    rom[15] = 0x00;
+    rom[16] = 0x40; // 64 bytes
     rom[17] = 0x00; // into address in r0
     rom[18] = 0x01; // from address in r1.
 
-    // DATA
-    rom[64] = 0x11;
-    rom[65] = 0x22;
-    rom[66] = 0x33;
-    rom[67] = 0x44;
-    rom[68] = 0x55;
-    rom[69] = 0x66;
-    rom[70] = 0x77;
-    rom[71] = 0x88;
-    rom[72] = 0x99;
-    rom[73] = 0xAA;
-    rom[74] = 0xBB;
-    rom[75] = 0xCC;
-    rom[76] = 0xDD;
-    rom[77] = 0xEE;
-    rom[78] = 0xFF;
-    // Gap of zeroes
-    rom[113] = 0x11;
-    rom[114] = 0x22;
-    rom[115] = 0x33;
-    rom[116] = 0x44;
-    rom[117] = 0x55;
-    rom[118] = 0x66;
-    rom[119] = 0x77;
-    rom[120] = 0x88;
-    rom[121] = 0x99;
-    rom[122] = 0xAA;
-    rom[123] = 0xBB;
-    rom[124] = 0xCC;
-    rom[125] = 0xDD;
-    rom[126] = 0xEE;
-    rom[127] = 0xFF;
-    // This last byte should NOT be copied.
-    rom[128] = 0xFF;
+    // DATA
+    rom[64] = 0x11;
+    rom[65] = 0x22;
+    rom[66] = 0x33;
+    rom[67] = 0x44;
+    rom[68] = 0x55;
+    rom[69] = 0x66;
+    rom[70] = 0x77;
+    rom[71] = 0x88;
+    rom[72] = 0x99;
+    rom[73] = 0xAA;
+    rom[74] = 0xBB;
+    rom[75] = 0xCC;
+    rom[76] = 0xDD;
+    rom[77] = 0xEE;
+    rom[78] = 0xFF;
+    // Gap of zeroes
+    rom[113] = 0x11;
+    rom[114] = 0x22;
+    rom[115] = 0x33;
+    rom[116] = 0x44;
+    rom[117] = 0x55;
+    rom[118] = 0x66;
+    rom[119] = 0x77;
+    rom[120] = 0x88;
+    rom[121] = 0x99;
+    rom[122] = 0xAA;
+    rom[123] = 0xBB;
+    rom[124] = 0xCC;
+    rom[125] = 0xDD;
+    rom[126] = 0xEE;
+    rom[127] = 0xFF;
+    // This last byte should NOT be copied.
+    rom[128] = 0xFF;
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x4000),
+        Ok(0x11)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x4001),
+        Ok(0x22)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x4002),
+        Ok(0x33)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x4003),
+        Ok(0x44)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x4004),
+        Ok(0x55)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x4005),
+        Ok(0x66)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x4006),
+        Ok(0x77)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x4007),
+        Ok(0x88)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x4008),
+        Ok(0x99)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x4009),
+        Ok(0xAA)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x400A),
+        Ok(0xBB)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x400B),
+        Ok(0xCC)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x400C),
+        Ok(0xDD)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x400D),
+        Ok(0xEE)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x400E),
+        Ok(0xFF)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x400F),
+        Ok(0x00)
+    );
+
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x4030),
+        Ok(0x00)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x4031),
+        Ok(0x11)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x4032),
+        Ok(0x22)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x4033),
+        Ok(0x33)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x4034),
+        Ok(0x44)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x4035),
+        Ok(0x55)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x4036),
+        Ok(0x66)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x4037),
+        Ok(0x77)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x4038),
+        Ok(0x88)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x4039),
+        Ok(0x99)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x403A),
+        Ok(0xAA)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x403B),
+        Ok(0xBB)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x403C),
+        Ok(0xCC)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x403D),
+        Ok(0xDD)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x403E),
+        Ok(0xEE)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x403F),
+        Ok(0xFF)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x4040),
+        Ok(0x00)
+    );
+}
+
+#[test]
+#[timeout(100)]
+fn test_blockset() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x00; // into r0
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x00;
+    rom[5] = 0x20; // 32 bytes.
+
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x11; // into r1b
+    rom[8] = 0x42; // some value.
+
+    rom[9] = 0x1D; // Blockset ref length, literal dest, ref value
+    rom[10] = 0x00; // length in r0
+    rom[11] = 0x00;
+    rom[12] = 0x00;
+    rom[13] = 0x80;
+    rom[14] = 0x00; // destination address 0x00008000
+    rom[15] = 0x11; // value in r1b.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x8000),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x8001),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x8002),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x8003),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x8004),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x8005),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x8006),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x8007),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x8008),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x8009),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x800A),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x800B),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x800C),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x800D),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x800E),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x800F),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x8010),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x8011),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x8012),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x8013),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x8014),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x8015),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x8016),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x8017),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x8018),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x8019),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x801A),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x801B),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x801C),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x801D),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x801E),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x801F),
+        Ok(0x42)
+    );
+    assert_eq!(
+        internal!(cpu).mmu.lock().unwrap().load_physical_8(0x8020),
+        Ok(0x00)
+    );
+}
+
+#[test]
+#[timeout(100)]
+fn test_negate() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x00; // into r0
+    rom[2] = 0x12;
+    rom[3] = 0x34;
+    rom[4] = 0x56;
+    rom[5] = 0x78; // random number.
+
+    rom[6] = 0x20; // Negate
+    rom[7] = 0x00; // r0.
+
+    rom[8] = 0x0A; // Copy literal
+    rom[9] = 0x14; // into r4b
+    rom[10] = 0x10; // random number.
+
+    rom[11] = 0x20; // Negate
+    rom[12] = 0x14; // r4b.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[0], -0x12345678_i32 as u32);
+    assert_eq!(internal!(cpu).r[4], (-0x10_i32 as u8) as u32);
+}
+
+#[test]
+#[timeout(100)]
+fn test_add() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x10; // into r0b
+    rom[2] = 0x05; // some number.
+
+    rom[3] = 0x0A; // Copy literal
+    rom[4] = 0x11; // into r1b
+    rom[5] = 0x06; // some number.
+
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x12; // into r2b
+    rom[8] = 0xFF; // max number.
+
+    rom[9] = 0x22; // Add register
+    rom[10] = 0x10; // into r0b
+    rom[11] = 0x11; // r1b.
+
+    rom[12] = 0x22; // Add register
+    rom[13] = 0x11; // into r1b
+    rom[14] = 0x11; // r1b.
+
+    rom[15] = 0x21; // Add literal
+    rom[16] = 0x12; // into r2b
+    rom[17] = 0x01; // 1.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[0], 0x0B);
+    assert_eq!(internal!(cpu).r[1], 0x0C);
+    assert_eq!(internal!(cpu).r[2], 0x00);
+}
+
+#[test]
+#[timeout(100)]
+fn test_flags() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x10; // into r0b
+    rom[2] = 0xFF; // max number.
+
+    rom[3] = 0x21; // Add literal
+    rom[4] = 0x10; // into r0b
+    rom[5] = 0x01; // 1.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[0], 0x00);
+    assert_eq!(internal!(cpu).flags, FLAG_ZERO | FLAG_CARRY);
+
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x10; // into r0b
+    rom[2] = 0x7F; // max signed number.
+
+    rom[3] = 0x21; // Add literal
+    rom[4] = 0x10; // into r0b
+    rom[5] = 0x01; // 1.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[0], 0x80);
+    assert_eq!(internal!(cpu).flags, FLAG_NEGATIVE | FLAG_OVERFLOW);
+
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x10; // into r0b
+    rom[2] = 0xFF; // -1.
+
+    rom[3] = 0x22; // Add register
+    rom[4] = 0x10; // into r0b
+    rom[5] = 0x10; // r0b.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[0], 0xFE);
+    assert_eq!(internal!(cpu).flags, FLAG_NEGATIVE | FLAG_CARRY);
+
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x10; // into r0b
+    rom[2] = 0x01; // 1.
+
+    rom[3] = 0x22; // Add register
+    rom[4] = 0x10; // into r0b
+    rom[5] = 0x10; // r0b.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[0], 0x02);
+    assert_eq!(internal!(cpu).flags, 0);
+}
+
+#[test]
+#[timeout(100)]
+fn test_float_add() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x10; // into r0b
+    rom[2] = 0x42; // some number.
+
+    rom[3] = 0x6C; // Convert register
+    rom[4] = 0x18; // into f0
+    rom[5] = 0x00; // from r0.
+
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x10; // into r0b
+    rom[8] = 0x56; // some number.
+
+    rom[9] = 0x6C; // Convert register
+    rom[10] = 0x19; // into f1
+    rom[11] = 0x00; // from r0.
+
+    rom[12] = 0x22; // Add register
+    rom[13] = 0x18; // into f0
+    rom[14] = 0x19; // from f1.
+
+    rom[15] = 0x6C; // Convert register
+    rom[16] = 0x01; // into r1
+    rom[17] = 0x18; // from f0.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_about_eq!(internal!(cpu).f[0], (0x42 + 0x56) as f32);
+    assert_eq!(internal!(cpu).r[1], 0x42 + 0x56);
+    assert_eq!(internal!(cpu).flags, 0);
+}
+
+#[test]
+#[timeout(100)]
+fn test_float_convert() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x00; // into r0
+    rom[2] = 0xFF;
+    rom[3] = 0xFF;
+    rom[4] = 0xFF;
+    rom[5] = 0xFF; // -1.
+
+    rom[6] = 0x6C; // Signed convert register
+    rom[7] = 0x18; // into f0
+    rom[8] = 0x00; // from r0.
+
+    rom[9] = 0x6D; // Unsigned convert register
+    rom[10] = 0x19; // into f1
+    rom[11] = 0x00; // from r0.
+
+    rom[12] = 0x6D; // Unsigned convert register
+    rom[13] = 0x00; // into r0
+    rom[14] = 0x18; // from f0.
+
+    rom[15] = 0x6C; // Signed convert register
+    rom[16] = 0x01; // into r1
+    rom[17] = 0x18; // from f0.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_about_eq!(internal!(cpu).f[0], -1.0);
+    assert_about_eq!(internal!(cpu).f[1], u32::MAX as f32);
+    assert_eq!(internal!(cpu).r[0], 0);
+    assert_eq!(internal!(cpu).r[1], u32::MAX);
+}
+
+#[test]
+#[timeout(100)]
+fn test_addcarry() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x10; // into r0b
+    rom[2] = 0xFF; // max number.
+
+    rom[3] = 0x21; // Add literal
+    rom[4] = 0x10; // into r0b
+    rom[5] = 0x01; // 1.
+
+    rom[6] = 0x23; // Add literal with carry
+    rom[7] = 0x10; // into r0b
+    rom[8] = 0x01; // 1.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[0], 0x02);
+}
+
+#[test]
+#[timeout(100)]
+fn test_sub() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x10; // into r0b
+    rom[2] = 0x05; // some number.
+
+    rom[3] = 0x6C; // Convert
+    rom[4] = 0x18; // into f0
+    rom[5] = 0x00; // r0.
+
+    rom[6] = 0x25; // Sub literal
+    rom[7] = 0x10; // into r0b
+    rom[8] = 0x07; // 7.
+
+    rom[9] = 0x26; // Sub register
+    rom[10] = 0x18; // into f0
+    rom[11] = 0x18; // f0.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[0], 0xFE);
+    assert_about_eq!(internal!(cpu).f[0], 0.0);
+}
+
+#[test]
+#[timeout(100)]
+fn test_subborrow() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x21; // Add literal
+    rom[1] = 0x11; // into r1b
+    rom[2] = 0x01; // 1.
+
+    rom[3] = 0x25; // Subtract literal
+    rom[4] = 0x10; // into r0b
+    rom[5] = 0x05; // 5.
+
+    rom[6] = 0x28; // Subtract register with borrow
+    rom[7] = 0x10; // into r0b
+    rom[8] = 0x11; // r1b.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[0], 0xF9);
+}
+
+#[test]
+#[timeout(100)]
+fn test_mult() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x10; // into r0b
+    rom[2] = 0x03; // 3.
+
+    rom[3] = 0x0A; // Copy literal
+    rom[4] = 0x11; // into r1b
+    rom[5] = 0x05; // 5.
+
+    rom[6] = 0x2A; // Multiply register
+    rom[7] = 0x10; // into r0b
+    rom[8] = 0x11; // r1b.
+
+    rom[9] = 0x29; // Multiply literal
+    rom[10] = 0x09; // into r1h
+    rom[11] = 0x04;
+    rom[12] = 0x00; // 1024.
+
+    rom[13] = 0x6C; // Convert
+    rom[14] = 0x18; // into f0
+    rom[15] = 0x01; // from r1.
+
+    rom[16] = 0x2A; // Multiply register
+    rom[17] = 0x18; // into f0
+    rom[18] = 0x18; // f0.
 
     let (cpu, ui_commands) = run_default(rom);
     assert_eq!(ui_commands.len(), 1);
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x4000), Ok(0x11));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x4001), Ok(0x22));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x4002), Ok(0x33));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x4003), Ok(0x44));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x4004), Ok(0x55));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x4005), Ok(0x66));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x4006), Ok(0x77));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x4007), Ok(0x88));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x4008), Ok(0x99));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x4009), Ok(0xAA));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x400A), Ok(0xBB));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x400B), Ok(0xCC));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x400C), Ok(0xDD));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x400D), Ok(0xEE));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x400E), Ok(0xFF));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x400F), Ok(0x00));
-
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x4030), Ok(0x00));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x4031), Ok(0x11));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x4032), Ok(0x22));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x4033), Ok(0x33));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x4034), Ok(0x44));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x4035), Ok(0x55));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x4036), Ok(0x66));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x4037), Ok(0x77));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x4038), Ok(0x88));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x4039), Ok(0x99));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x403A), Ok(0xAA));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x403B), Ok(0xBB));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x403C), Ok(0xCC));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x403D), Ok(0xDD));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x403E), Ok(0xEE));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x403F), Ok(0xFF));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x4040), Ok(0x00));
+    assert_eq!(internal!(cpu).r[0], 0x0F);
+    assert_eq!(internal!(cpu).r[1], 0x1400);
+    assert_about_eq!(internal!(cpu).f[0], 26214400.0);
 }
 
 #[test]
 #[timeout(100)]
-fn test_blockset() {
+fn test_sdiv() {
     let mut rom = [0; ROM_SIZE];
     rom[0] = 0x0A; // Copy literal
-    rom[1] = 0x00; // into r0
-    rom[2] = 0x00;
-    rom[3] = 0x00;
-    rom[4] = 0x00;
-    rom[5] = 0x20; // 32 bytes.
+    rom[1] = 0x10; // into r0b
+    rom[2] = 0x0F; // 15.
 
-    rom[6] = 0x0A; // Copy literal
-    rom[7] = 0x11; // into r1b
-    rom[8] = 0x42; // some value.
+    rom[3] = 0x0A; // Copy literal
+    rom[4] = 0x11; // into r1b
+    rom[5] = 0x08; // 8.
 
-    rom[9] = 0x1D; // Blockset ref length, literal dest, ref value
-    rom[10] = 0x00; // length in r0
-    rom[11] = 0x00;
-    rom[12] = 0x00;
-    rom[13] = 0x80;
-    rom[14] = 0x00; // destination address 0x00008000
-    rom[15] = 0x11; // value in r1b.
+    rom[6] = 0x2C; // Divide register
+    rom[7] = 0x10; // into r0b
+    rom[8] = 0x11; // by r1b.
+
+    rom[9] = 0x2B; // Divide literal
+    rom[10] = 0x11; // into r1b
+    rom[11] = 0x04; // 4.
+
+    rom[12] = 0x0A; // Copy literal
+    rom[13] = 0x12; // into r2b.
+    rom[14] = 0xE2; // -30.
+
+    rom[15] = 0x2B; // Divide literal
+    rom[16] = 0x12; // into r2b
+    rom[17] = 0x05; // 5.
+
+    rom[18] = 0x0A; // Copy literal
+    rom[19] = 0x13; // into r3b
+    rom[20] = 0xE2; // -30.
+
+    rom[21] = 0x2B; // Divide literal
+    rom[22] = 0x13; // into r3b
+    rom[23] = 0xFB; // -5.
+
+    rom[24] = 0x0A; // Copy literal
+    rom[25] = 0x14; // into r4b
+    rom[26] = 0xFF; // 255.
+
+    rom[27] = 0x6C; // Convert
+    rom[28] = 0x18; // into f0
+    rom[29] = 0x04; // r4.
+
+    rom[30] = 0x0A; // Copy literal
+    rom[31] = 0x14; // into r4b
+    rom[32] = 0x64; // 100.
+
+    rom[33] = 0x6C; // Convert
+    rom[34] = 0x19; // into f1
+    rom[35] = 0x04; // r4.
+
+    rom[36] = 0x2C; // Divide register
+    rom[37] = 0x18; // into f0
+    rom[38] = 0x19; // by f1.
 
     let (cpu, ui_commands) = run_default(rom);
     assert_eq!(ui_commands.len(), 1);
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x8000), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x8001), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x8002), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x8003), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x8004), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x8005), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x8006), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x8007), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x8008), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x8009), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x800A), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x800B), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x800C), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x800D), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x800E), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x800F), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x8010), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x8011), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x8012), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x8013), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x8014), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x8015), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x8016), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x8017), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x8018), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x8019), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x801A), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x801B), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x801C), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x801D), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x801E), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x801F), Ok(0x42));
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x8020), Ok(0x00));
+    assert_eq!(internal!(cpu).r[0], 0x01);
+    assert_eq!(internal!(cpu).r[1], 0x02);
+    assert_eq!(internal!(cpu).r[2], 0xFA);
+    assert_eq!(internal!(cpu).r[3], 0x06);
+    assert_about_eq!(internal!(cpu).f[0], 2.55);
+    assert_about_eq!(internal!(cpu).f[1], 100.0);
 }
 
 #[test]
 #[timeout(100)]
-fn test_negate() {
+fn test_div_by_zero() {
     let mut rom = [0; ROM_SIZE];
     rom[0] = 0x0A; // Copy literal
-    rom[1] = 0x00; // into r0
-    rom[2] = 0x12;
-    rom[3] = 0x34;
-    rom[4] = 0x56;
-    rom[5] = 0x78; // random number.
+    rom[1] = 0x10; // into r0b
+    rom[2] = 0xC0; // ROM address 128.
 
-    rom[6] = 0x20; // Negate
-    rom[7] = 0x00; // r0.
+    rom[3] = 0x08; // Store literal address
+    rom[4] = 0x00;
+    rom[5] = 0x00;
+    rom[6] = 0x00;
+    rom[7] = 0x04; // div by zero interrupt handler
+    rom[8] = 0x00; // r0.
 
-    rom[8] = 0x0A; // Copy literal
-    rom[9] = 0x14; // into r4b
-    rom[10] = 0x10; // random number.
+    rom[9] = 0x0A; // Copy literal
+    rom[10] = 0x22; // into kspr
+    rom[11] = 0x00;
+    rom[12] = 0x00;
+    rom[13] = 0x50;
+    rom[14] = 0x00; // address 0x00005000.
 
-    rom[11] = 0x20; // Negate
-    rom[12] = 0x14; // r4b.
+    rom[15] = 0x0A; // Copy literal
+    rom[16] = 0x24; // into imr
+    rom[17] = 0x00;
+    rom[18] = 0x02; // div by zero interrupt only.
+
+    rom[19] = 0x2B; // Divide literal
+    rom[20] = 0x10; // into r0b
+    rom[21] = 0x00; // 0.
+
+    rom[22] = 0x01; // Pause (fail condition).
+
+    // Interrupt handler.
+    rom[128] = 0x21; // Add literal
+    rom[129] = 0x17; // into r7b
+    rom[130] = 0x01; // 1.
+
+    rom[131] = 0x00; // HALT.
 
     let (cpu, ui_commands) = run_default(rom);
     assert_eq!(ui_commands.len(), 1);
-    assert_eq!(internal!(cpu).r[0], -0x12345678_i32 as u32);
-    assert_eq!(internal!(cpu).r[4], (-0x10_i32 as u8) as u32);
+    assert_eq!(internal!(cpu).r[0], 0xC0);
+    assert_eq!(internal!(cpu).r[7], 0x01);
 }
 
 #[test]
 #[timeout(100)]
-fn test_add() {
+fn test_udiv() {
     let mut rom = [0; ROM_SIZE];
+    // These use the same values as test_sdiv, but interpret them as unsigned instead.
     rom[0] = 0x0A; // Copy literal
     rom[1] = 0x10; // into r0b
-    rom[2] = 0x05; // some number.
+    rom[2] = 0x0F; // 15.
 
     rom[3] = 0x0A; // Copy literal
     rom[4] = 0x11; // into r1b
-    rom[5] = 0x06; // some number.
+    rom[5] = 0x08; // 8.
 
-    rom[6] = 0x0A; // Copy literal
-    rom[7] = 0x12; // into r2b
-    rom[8] = 0xFF; // max number.
+    rom[6] = 0x2E; // Divide register
+    rom[7] = 0x10; // into r0b
+    rom[8] = 0x11; // r1b.
 
-    rom[9] = 0x22; // Add register
-    rom[10] = 0x10; // into r0b
-    rom[11] = 0x11; // r1b.
+    rom[9] = 0x2D; // Divide literal
+    rom[10] = 0x11; // into r1b
+    rom[11] = 0x04; // 4.
 
-    rom[12] = 0x22; // Add register
-    rom[13] = 0x11; // into r1b
-    rom[14] = 0x11; // r1b.
+    rom[12] = 0x0A; // Copy literal
+    rom[13] = 0x12; // into r2b.
+    rom[14] = 0xE2; // 226.
 
-    rom[15] = 0x21; // Add literal
+    rom[15] = 0x2D; // Divide literal
     rom[16] = 0x12; // into r2b
-    rom[17] = 0x01; // 1.
+    rom[17] = 0x05; // 5.
+
+    rom[18] = 0x0A; // Copy literal
+    rom[19] = 0x13; // into r3b
+    rom[20] = 0xE2; // 226.
+
+    rom[21] = 0x2D; // Divide literal
+    rom[22] = 0x13; // into r3b
+    rom[23] = 0xFB; // 251.
 
     let (cpu, ui_commands) = run_default(rom);
     assert_eq!(ui_commands.len(), 1);
-    assert_eq!(internal!(cpu).r[0], 0x0B);
-    assert_eq!(internal!(cpu).r[1], 0x0C);
-    assert_eq!(internal!(cpu).r[2], 0x00);
+    assert_eq!(internal!(cpu).r[0], 0x01);
+    assert_eq!(internal!(cpu).r[1], 0x02);
+    assert_eq!(internal!(cpu).r[2], 0x2D);
+    assert_eq!(internal!(cpu).r[3], 0x00);
 }
 
 #[test]
 #[timeout(100)]
-fn test_flags() {
+fn test_srem() {
     let mut rom = [0; ROM_SIZE];
     rom[0] = 0x0A; // Copy literal
     rom[1] = 0x10; // into r0b
-    rom[2] = 0xFF; // max number.
+    rom[2] = 0x0F; // 15.
 
-    rom[3] = 0x21; // Add literal
-    rom[4] = 0x10; // into r0b
-    rom[5] = 0x01; // 1.
+    rom[3] = 0x0A; // Copy literal
+    rom[4] = 0x11; // into r1b
+    rom[5] = 0x08; // 8.
 
-    let (cpu, ui_commands) = run_default(rom);
-    assert_eq!(ui_commands.len(), 1);
-    assert_eq!(internal!(cpu).r[0], 0x00);
-    assert_eq!(internal!(cpu).flags, FLAG_ZERO | FLAG_CARRY);
+    rom[6] = 0x30; // Remainder register
+    rom[7] = 0x10; // into r0b
+    rom[8] = 0x11; // by r1b.
 
-    let mut rom = [0; ROM_SIZE];
-    rom[0] = 0x0A; // Copy literal
-    rom[1] = 0x10; // into r0b
-    rom[2] = 0x7F; // max signed number.
+    rom[9] = 0x2F; // Remainder literal
+    rom[10] = 0x11; // into r1b
+    rom[11] = 0x04; // 4.
 
-    rom[3] = 0x21; // Add literal
-    rom[4] = 0x10; // into r0b
-    rom[5] = 0x01; // 1.
+    rom[12] = 0x0A; // Copy literal
+    rom[13] = 0x12; // into r2b.
+    rom[14] = 0xE2; // -30.
 
-    let (cpu, ui_commands) = run_default(rom);
-    assert_eq!(ui_commands.len(), 1);
-    assert_eq!(internal!(cpu).r[0], 0x80);
-    assert_eq!(internal!(cpu).flags, FLAG_NEGATIVE | FLAG_OVERFLOW);
+    rom[15] = 0x2F; // Remainder literal
+    rom[16] = 0x12; // into r2b
+    rom[17] = 0x04; // 4.
 
-    let mut rom = [0; ROM_SIZE];
-    rom[0] = 0x0A; // Copy literal
-    rom[1] = 0x10; // into r0b
-    rom[2] = 0xFF; // -1.
+    rom[18] = 0x0A; // Copy literal
+    rom[19] = 0x13; // into r3b
+    rom[20] = 0xE2; // -30.
 
-    rom[3] = 0x22; // Add register
-    rom[4] = 0x10; // into r0b
-    rom[5] = 0x10; // r0b.
+    rom[21] = 0x2F; // Remainder literal
+    rom[22] = 0x13; // into r3b
+    rom[23] = 0xFC; // -4.
 
-    let (cpu, ui_commands) = run_default(rom);
-    assert_eq!(ui_commands.len(), 1);
-    assert_eq!(internal!(cpu).r[0], 0xFE);
-    assert_eq!(internal!(cpu).flags, FLAG_NEGATIVE | FLAG_CARRY);
+    rom[24] = 0x0A; // Copy literal
+    rom[25] = 0x14; // into r4b
+    rom[26] = 0xFF; // 255.
 
-    let mut rom = [0; ROM_SIZE];
-    rom[0] = 0x0A; // Copy literal
-    rom[1] = 0x10; // into r0b
-    rom[2] = 0x01; // 1.
+    rom[27] = 0x6C; // Convert
+    rom[28] = 0x18; // into f0
+    rom[29] = 0x04; // r4.
 
-    rom[3] = 0x22; // Add register
-    rom[4] = 0x10; // into r0b
-    rom[5] = 0x10; // r0b.
+    rom[30] = 0x0A; // Copy literal
+    rom[31] = 0x14; // into r4b
+    rom[32] = 0x64; // 100.
+
+    rom[33] = 0x6C; // Convert
+    rom[34] = 0x19; // into f1
+    rom[35] = 0x04; // r4.
+
+    rom[36] = 0x30; // Remainder register
+    rom[37] = 0x18; // into f0
+    rom[38] = 0x19; // by f1.
 
     let (cpu, ui_commands) = run_default(rom);
     assert_eq!(ui_commands.len(), 1);
-    assert_eq!(internal!(cpu).r[0], 0x02);
-    assert_eq!(internal!(cpu).flags, 0);
+    assert_eq!(internal!(cpu).r[0], 0x07);
+    assert_eq!(internal!(cpu).r[1], 0x00);
+    assert_eq!(internal!(cpu).r[2], 0xFE);
+    assert_eq!(internal!(cpu).r[3], 0xFE);
+    assert_about_eq!(internal!(cpu).f[0], 55.0);
+    assert_about_eq!(internal!(cpu).f[1], 100.0);
 }
 
 #[test]
 #[timeout(100)]
-fn test_float_add() {
+fn test_urem() {
     let mut rom = [0; ROM_SIZE];
+    // These use the same values as test_srem, but interpret them as unsigned instead.
     rom[0] = 0x0A; // Copy literal
     rom[1] = 0x10; // into r0b
-    rom[2] = 0x42; // some number.
+    rom[2] = 0x0F; // 15.
 
-    rom[3] = 0x6C; // Convert register
-    rom[4] = 0x18; // into f0
-    rom[5] = 0x00; // from r0.
+    rom[3] = 0x0A; // Copy literal
+    rom[4] = 0x11; // into r1b
+    rom[5] = 0x08; // 8.
 
-    rom[6] = 0x0A; // Copy literal
+    rom[6] = 0x32; // Remainder register
     rom[7] = 0x10; // into r0b
-    rom[8] = 0x56; // some number.
+    rom[8] = 0x11; // r1b.
 
-    rom[9] = 0x6C; // Convert register
-    rom[10] = 0x19; // into f1
-    rom[11] = 0x00; // from r0.
+    rom[9] = 0x31; // Remainder literal
+    rom[10] = 0x11; // into r1b
+    rom[11] = 0x04; // 4.
 
-    rom[12] = 0x22; // Add register
-    rom[13] = 0x18; // into f0
-    rom[14] = 0x19; // from f1.
+    rom[12] = 0x0A; // Copy literal
+    rom[13] = 0x12; // into r2b.
+    rom[14] = 0xE2; // 226.
 
-    rom[15] = 0x6C; // Convert register
-    rom[16] = 0x01; // into r1
-    rom[17] = 0x18; // from f0.
+    rom[15] = 0x31; // Remainder literal
+    rom[16] = 0x12; // into r2b
+    rom[17] = 0x04; // 4.
+
+    rom[18] = 0x0A; // Copy literal
+    rom[19] = 0x13; // into r3b
+    rom[20] = 0xE2; // 226.
+
+    rom[21] = 0x31; // Remainder literal
+    rom[22] = 0x13; // into r3b
+    rom[23] = 0xFC; // 252.
 
     let (cpu, ui_commands) = run_default(rom);
     assert_eq!(ui_commands.len(), 1);
-    assert_about_eq!(internal!(cpu).f[0], (0x42 + 0x56) as f32);
-    assert_eq!(internal!(cpu).r[1], 0x42 + 0x56);
-    assert_eq!(internal!(cpu).flags, 0);
+    assert_eq!(internal!(cpu).r[0], 0x07);
+    assert_eq!(internal!(cpu).r[1], 0x00);
+    assert_eq!(internal!(cpu).r[2], 0x02);
+    assert_eq!(internal!(cpu).r[3], 0xE2);
 }
 
 #[test]
 #[timeout(100)]
-fn test_float_convert() {
+fn test_mult_wide() {
     let mut rom = [0; ROM_SIZE];
     rom[0] = 0x0A; // Copy literal
     rom[1] = 0x00; // into r0
-    rom[2] = 0xFF;
-    rom[3] = 0xFF;
-    rom[4] = 0xFF;
-    rom[5] = 0xFF; // -1.
-
-    rom[6] = 0x6C; // Signed convert register
-    rom[7] = 0x18; // into f0
-    rom[8] = 0x00; // from r0.
-
-    rom[9] = 0x6D; // Unsigned convert register
-    rom[10] = 0x19; // into f1
-    rom[11] = 0x00; // from r0.
+    rom[2] = 0x80;
+    rom[3] = 0x00;
+    rom[4] = 0x00;
+    rom[5] = 0x00; // 0x80000000.
 
-    rom[12] = 0x6D; // Unsigned convert register
-    rom[13] = 0x00; // into r0
-    rom[14] = 0x18; // from f0.
+    rom[6] = 0xA3; // Unsigned widening multiply literal
+    rom[7] = 0x01; // high r1
+    rom[8] = 0x00; // low r0
+    rom[9] = 0x00;
+    rom[10] = 0x00;
+    rom[11] = 0x00;
+    rom[12] = 0x04; // by 4.
+
+    rom[13] = 0x0A; // Copy literal
+    rom[14] = 0x02; // into r2
+    rom[15] = 0xFF;
+    rom[16] = 0xFF;
+    rom[17] = 0xFF;
+    rom[18] = 0xFD; // -3.
+
+    rom[19] = 0x0A; // Copy literal
+    rom[20] = 0x04; // into r4
+    rom[21] = 0x00;
+    rom[22] = 0x00;
+    rom[23] = 0x00;
+    rom[24] = 0x05; // 5.
 
-    rom[15] = 0x6C; // Signed convert register
-    rom[16] = 0x01; // into r1
-    rom[17] = 0x18; // from f0.
+    rom[25] = 0xA6; // Signed widening multiply ref
+    rom[26] = 0x03; // high r3
+    rom[27] = 0x02; // low r2
+    rom[28] = 0x04; // by r4.
 
     let (cpu, ui_commands) = run_default(rom);
     assert_eq!(ui_commands.len(), 1);
-    assert_about_eq!(internal!(cpu).f[0], -1.0);
-    assert_about_eq!(internal!(cpu).f[1], u32::MAX as f32);
-    assert_eq!(internal!(cpu).r[0], 0);
-    assert_eq!(internal!(cpu).r[1], u32::MAX);
+    assert_eq!(internal!(cpu).r[0], 0x00000000);
+    assert_eq!(internal!(cpu).r[1], 0x00000002);
+    assert_eq!(internal!(cpu).r[2], 0xFFFFFFF1);
+    assert_eq!(internal!(cpu).r[3], 0xFFFFFFFF);
+    assert_eq!(internal!(cpu).flags, FLAG_NEGATIVE);
 }
 
 #[test]
 #[timeout(100)]
-fn test_addcarry() {
+fn test_div_wide() {
     let mut rom = [0; ROM_SIZE];
     rom[0] = 0x0A; // Copy literal
-    rom[1] = 0x10; // into r0b
-    rom[2] = 0xFF; // max number.
-
-    rom[3] = 0x21; // Add literal
-    rom[4] = 0x10; // into r0b
+    rom[1] = 0x00; // into r0
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x00;
     rom[5] = 0x01; // 1.
 
-    rom[6] = 0x23; // Add literal with carry
-    rom[7] = 0x10; // into r0b
-    rom[8] = 0x01; // 1.
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x01; // into r1
+    rom[8] = 0x00;
+    rom[9] = 0x00;
+    rom[10] = 0x00;
+    rom[11] = 0x02; // 2.
+
+    rom[12] = 0xA7; // Unsigned wide divide literal
+    rom[13] = 0x01; // high r1
+    rom[14] = 0x00; // low r0
+    rom[15] = 0x00;
+    rom[16] = 0x00;
+    rom[17] = 0x00;
+    rom[18] = 0x03; // by 3.
+
+    rom[19] = 0x0A; // Copy literal
+    rom[20] = 0x02; // into r2
+    rom[21] = 0xFF;
+    rom[22] = 0xFF;
+    rom[23] = 0xFF;
+    rom[24] = 0xF9; // -7.
+
+    rom[25] = 0x0A; // Copy literal
+    rom[26] = 0x03; // into r3
+    rom[27] = 0xFF;
+    rom[28] = 0xFF;
+    rom[29] = 0xFF;
+    rom[30] = 0xFF; // sign extension of r2.
+
+    rom[31] = 0x0A; // Copy literal
+    rom[32] = 0x04; // into r4
+    rom[33] = 0x00;
+    rom[34] = 0x00;
+    rom[35] = 0x00;
+    rom[36] = 0x02; // 2.
+
+    rom[37] = 0xAA; // Signed wide divide ref
+    rom[38] = 0x03; // high r3
+    rom[39] = 0x02; // low r2
+    rom[40] = 0x04; // by r4.
 
     let (cpu, ui_commands) = run_default(rom);
     assert_eq!(ui_commands.len(), 1);
-    assert_eq!(internal!(cpu).r[0], 0x02);
+    assert_eq!(internal!(cpu).r[0], 0xAAAAAAAB);
+    assert_eq!(internal!(cpu).r[1], 0x00000000);
+    assert_eq!(internal!(cpu).r[2], 0xFFFFFFFD);
+    assert_eq!(internal!(cpu).r[3], 0xFFFFFFFF);
+    assert_eq!(internal!(cpu).flags, FLAG_NEGATIVE);
 }
 
 #[test]
 #[timeout(100)]
-fn test_sub() {
+fn test_wide_bad_registers() {
     let mut rom = [0; ROM_SIZE];
+    // Try a widening multiply into a half register. Should raise an interrupt.
     rom[0] = 0x0A; // Copy literal
-    rom[1] = 0x10; // into r0b
-    rom[2] = 0x05; // some number.
+    rom[1] = 0x22; // into kspr
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x50;
+    rom[5] = 0x00; // address 0x00005000.
 
-    rom[3] = 0x6C; // Convert
-    rom[4] = 0x18; // into f0
-    rom[5] = 0x00; // r0.
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x24; // into imr
+    rom[8] = 0x00;
+    rom[9] = 0x01; // illegal operation interrupt only.
 
-    rom[6] = 0x25; // Sub literal
-    rom[7] = 0x10; // into r0b
-    rom[8] = 0x07; // 7.
+    rom[10] = 0x0A; // Copy literal
+    rom[11] = 0x10; // into r0b
+    rom[12] = 0x03; // 3.
 
-    rom[9] = 0x26; // Sub register
-    rom[10] = 0x18; // into f0
-    rom[11] = 0x18; // f0.
+    rom[13] = 0xA4; // Unsigned widening multiply ref
+    rom[14] = 0x09; // high r1h
+    rom[15] = 0x00; // low r0
+    rom[16] = 0x00; // by r0.
+
+    rom[17] = 0x01; // Pause. We should never hit this.
 
     let (cpu, ui_commands) = run_default(rom);
     assert_eq!(ui_commands.len(), 1);
-    assert_eq!(internal!(cpu).r[0], 0xFE);
-    assert_about_eq!(internal!(cpu).f[0], 0.0);
+    assert_eq!(internal!(cpu).r[0], 0x00000003);
 }
 
 #[test]
 #[timeout(100)]
-fn test_subborrow() {
+fn test_float_math() {
     let mut rom = [0; ROM_SIZE];
-    rom[0] = 0x21; // Add literal
-    rom[1] = 0x11; // into r1b
-    rom[2] = 0x01; // 1.
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x18; // into f0
+    rom[2] = 0x40;
+    rom[3] = 0x00;
+    rom[4] = 0x00;
+    rom[5] = 0x00; // 2.0.
 
-    rom[3] = 0x25; // Subtract literal
-    rom[4] = 0x10; // into r0b
-    rom[5] = 0x05; // 5.
+    rom[6] = 0xAB; // Square root
+    rom[7] = 0x18; // of f0.
 
-    rom[6] = 0x28; // Subtract register with borrow
-    rom[7] = 0x10; // into r0b
-    rom[8] = 0x11; // r1b.
+    rom[8] = 0x0A; // Copy literal
+    rom[9] = 0x19; // into f1
+    rom[10] = 0xC0;
+    rom[11] = 0x20;
+    rom[12] = 0x00;
+    rom[13] = 0x00; // -2.5.
 
-    let (cpu, ui_commands) = run_default(rom);
-    assert_eq!(ui_commands.len(), 1);
-    assert_eq!(internal!(cpu).r[0], 0xF9);
-}
+    rom[14] = 0xAC; // Absolute value
+    rom[15] = 0x19; // of f1.
 
-#[test]
-#[timeout(100)]
-fn test_mult() {
-    let mut rom = [0; ROM_SIZE];
-    rom[0] = 0x0A; // Copy literal
-    rom[1] = 0x10; // into r0b
-    rom[2] = 0x03; // 3.
+    rom[16] = 0x0A; // Copy literal
+    rom[17] = 0x1A; // into f2
+    rom[18] = 0x40;
+    rom[19] = 0x20;
+    rom[20] = 0x00;
+    rom[21] = 0x00; // 2.5.
 
-    rom[3] = 0x0A; // Copy literal
-    rom[4] = 0x11; // into r1b
-    rom[5] = 0x05; // 5.
+    rom[22] = 0xB3; // Round
+    rom[23] = 0x1A; // f2.
 
-    rom[6] = 0x2A; // Multiply register
-    rom[7] = 0x10; // into r0b
-    rom[8] = 0x11; // r1b.
+    rom[24] = 0x0A; // Copy literal
+    rom[25] = 0x1B; // into f3
+    rom[26] = 0xC0;
+    rom[27] = 0x20;
+    rom[28] = 0x00;
+    rom[29] = 0x00; // -2.5.
+
+    rom[30] = 0xB1; // Floor
+    rom[31] = 0x1B; // f3.
+
+    rom[32] = 0x0A; // Copy literal
+    rom[33] = 0x1C; // into f4
+    rom[34] = 0xC0;
+    rom[35] = 0x20;
+    rom[36] = 0x00;
+    rom[37] = 0x00; // -2.5.
 
-    rom[9] = 0x29; // Multiply literal
-    rom[10] = 0x09; // into r1h
-    rom[11] = 0x04;
-    rom[12] = 0x00; // 1024.
+    rom[38] = 0xB2; // Ceiling
+    rom[39] = 0x1C; // f4.
 
-    rom[13] = 0x6C; // Convert
-    rom[14] = 0x18; // into f0
-    rom[15] = 0x01; // from r1.
+    rom[40] = 0xAD; // Minimum literal
+    rom[41] = 0x19; // of f1
+    rom[42] = 0x3F;
+    rom[43] = 0x80;
+    rom[44] = 0x00;
+    rom[45] = 0x00; // and 1.0.
 
-    rom[16] = 0x2A; // Multiply register
-    rom[17] = 0x18; // into f0
-    rom[18] = 0x18; // f0.
+    rom[46] = 0xB0; // Maximum ref
+    rom[47] = 0x1B; // of f3
+    rom[48] = 0x1C; // and f4.
 
     let (cpu, ui_commands) = run_default(rom);
     assert_eq!(ui_commands.len(), 1);
-    assert_eq!(internal!(cpu).r[0], 0x0F);
-    assert_eq!(internal!(cpu).r[1], 0x1400);
-    assert_about_eq!(internal!(cpu).f[0], 26214400.0);
+    assert_eq!(internal!(cpu).f[0], 2.0f32.sqrt());
+    assert_eq!(internal!(cpu).f[1], 1.0);
+    assert_eq!(internal!(cpu).f[2], 2.0);
+    assert_eq!(internal!(cpu).f[3], -2.0);
+    assert_eq!(internal!(cpu).f[4], -2.0);
+    assert_eq!(internal!(cpu).flags, FLAG_NEGATIVE);
+    assert_eq!(internal!(cpu).fpsr, fpu::FP_INEXACT);
 }
 
 #[test]
 #[timeout(100)]
-fn test_sdiv() {
+fn test_float_exceptions_masked() {
     let mut rom = [0; ROM_SIZE];
     rom[0] = 0x0A; // Copy literal
-    rom[1] = 0x10; // into r0b
-    rom[2] = 0x0F; // 15.
-
-    rom[3] = 0x0A; // Copy literal
-    rom[4] = 0x11; // into r1b
-    rom[5] = 0x08; // 8.
-
-    rom[6] = 0x2C; // Divide register
-    rom[7] = 0x10; // into r0b
-    rom[8] = 0x11; // by r1b.
-
-    rom[9] = 0x2B; // Divide literal
-    rom[10] = 0x11; // into r1b
-    rom[11] = 0x04; // 4.
-
-    rom[12] = 0x0A; // Copy literal
-    rom[13] = 0x12; // into r2b.
-    rom[14] = 0xE2; // -30.
-
-    rom[15] = 0x2B; // Divide literal
-    rom[16] = 0x12; // into r2b
-    rom[17] = 0x05; // 5.
-
-    rom[18] = 0x0A; // Copy literal
-    rom[19] = 0x13; // into r3b
-    rom[20] = 0xE2; // -30.
-
-    rom[21] = 0x2B; // Divide literal
-    rom[22] = 0x13; // into r3b
-    rom[23] = 0xFB; // -5.
-
-    rom[24] = 0x0A; // Copy literal
-    rom[25] = 0x14; // into r4b
-    rom[26] = 0xFF; // 255.
-
-    rom[27] = 0x6C; // Convert
-    rom[28] = 0x18; // into f0
-    rom[29] = 0x04; // r4.
-
-    rom[30] = 0x0A; // Copy literal
-    rom[31] = 0x14; // into r4b
-    rom[32] = 0x64; // 100.
+    rom[1] = 0x18; // into f0
+    rom[2] = 0x3F;
+    rom[3] = 0x80;
+    rom[4] = 0x00;
+    rom[5] = 0x00; // 1.0.
 
-    rom[33] = 0x6C; // Convert
-    rom[34] = 0x19; // into f1
-    rom[35] = 0x04; // r4.
+    rom[6] = 0x2B; // Divide literal
+    rom[7] = 0x18; // f0
+    rom[8] = 0x00;
+    rom[9] = 0x00;
+    rom[10] = 0x00;
+    rom[11] = 0x00; // by 0.0.
 
-    rom[36] = 0x2C; // Divide register
-    rom[37] = 0x18; // into f0
-    rom[38] = 0x19; // by f1.
+    rom[12] = 0x2C; // Divide ref
+    rom[13] = 0x19; // f1 (zero)
+    rom[14] = 0x19; // by f1.
 
     let (cpu, ui_commands) = run_default(rom);
     assert_eq!(ui_commands.len(), 1);
-    assert_eq!(internal!(cpu).r[0], 0x01);
-    assert_eq!(internal!(cpu).r[1], 0x02);
-    assert_eq!(internal!(cpu).r[2], 0xFA);
-    assert_eq!(internal!(cpu).r[3], 0x06);
-    assert_about_eq!(internal!(cpu).f[0], 2.55);
-    assert_about_eq!(internal!(cpu).f[1], 100.0);
+    assert_eq!(internal!(cpu).f[0], f32::INFINITY);
+    assert!(internal!(cpu).f[1].is_nan());
+    assert_eq!(internal!(cpu).fpsr, fpu::FP_DIV_BY_0 | fpu::FP_INVALID);
 }
 
 #[test]
 #[timeout(100)]
-fn test_div_by_zero() {
+fn test_float_exception_interrupt() {
     let mut rom = [0; ROM_SIZE];
     rom[0] = 0x0A; // Copy literal
     rom[1] = 0x10; // into r0b
@@ -1433,7 +4022,7 @@ fn test_div_by_zero() {
     rom[4] = 0x00;
     rom[5] = 0x00;
     rom[6] = 0x00;
-    rom[7] = 0x04; // div by zero interrupt handler
+    rom[7] = 0x2C; // floating point interrupt handler
     rom[8] = 0x00; // r0.
 
     rom[9] = 0x0A; // Copy literal
@@ -1445,14 +4034,29 @@ fn test_div_by_zero() {
 
     rom[15] = 0x0A; // Copy literal
     rom[16] = 0x24; // into imr
-    rom[17] = 0x00;
-    rom[18] = 0x02; // div by zero interrupt only.
-
-    rom[19] = 0x2B; // Divide literal
-    rom[20] = 0x10; // into r0b
-    rom[21] = 0x00; // 0.
+    rom[17] = 0x08;
+    rom[18] = 0x00; // floating point interrupt only.
+
+    rom[19] = 0x0A; // Copy literal
+    rom[20] = 0x27; // into fpsr
+    rom[21] = 0x04;
+    rom[22] = 0x00; // enable overflow only.
+
+    rom[23] = 0x0A; // Copy literal
+    rom[24] = 0x18; // into f0
+    rom[25] = 0x7F;
+    rom[26] = 0x61;
+    rom[27] = 0xB1;
+    rom[28] = 0xE6; // 3e38.
+
+    rom[29] = 0x29; // Multiply literal
+    rom[30] = 0x18; // f0
+    rom[31] = 0x40;
+    rom[32] = 0x00;
+    rom[33] = 0x00;
+    rom[34] = 0x00; // by 2.0.
 
-    rom[22] = 0x01; // Pause (fail condition).
+    rom[35] = 0x01; // Pause (fail condition).
 
     // Interrupt handler.
     rom[128] = 0x21; // Add literal
@@ -1463,164 +4067,138 @@ fn test_div_by_zero() {
 
     let (cpu, ui_commands) = run_default(rom);
     assert_eq!(ui_commands.len(), 1);
-    assert_eq!(internal!(cpu).r[0], 0xC0);
+    assert_eq!(internal!(cpu).f[0], 3e38);
     assert_eq!(internal!(cpu).r[7], 0x01);
+    assert_eq!(
+        internal!(cpu).fpsr,
+        0x0400 | fpu::FP_OVERFLOW | fpu::FP_INEXACT
+    );
 }
 
 #[test]
 #[timeout(100)]
-fn test_udiv() {
-    let mut rom = [0; ROM_SIZE];
-    // These use the same values as test_sdiv, but interpret them as unsigned instead.
-    rom[0] = 0x0A; // Copy literal
-    rom[1] = 0x10; // into r0b
-    rom[2] = 0x0F; // 15.
-
-    rom[3] = 0x0A; // Copy literal
-    rom[4] = 0x11; // into r1b
-    rom[5] = 0x08; // 8.
-
-    rom[6] = 0x2E; // Divide register
-    rom[7] = 0x10; // into r0b
-    rom[8] = 0x11; // r1b.
-
-    rom[9] = 0x2D; // Divide literal
-    rom[10] = 0x11; // into r1b
-    rom[11] = 0x04; // 4.
-
-    rom[12] = 0x0A; // Copy literal
-    rom[13] = 0x12; // into r2b.
-    rom[14] = 0xE2; // 226.
-
-    rom[15] = 0x2D; // Divide literal
-    rom[16] = 0x12; // into r2b
-    rom[17] = 0x05; // 5.
-
-    rom[18] = 0x0A; // Copy literal
-    rom[19] = 0x13; // into r3b
-    rom[20] = 0xE2; // 226.
-
-    rom[21] = 0x2D; // Divide literal
-    rom[22] = 0x13; // into r3b
-    rom[23] = 0xFB; // 251.
-
-    let (cpu, ui_commands) = run_default(rom);
-    assert_eq!(ui_commands.len(), 1);
-    assert_eq!(internal!(cpu).r[0], 0x01);
-    assert_eq!(internal!(cpu).r[1], 0x02);
-    assert_eq!(internal!(cpu).r[2], 0x2D);
-    assert_eq!(internal!(cpu).r[3], 0x00);
-}
-
-#[test]
-#[timeout(100)]
-fn test_srem() {
+fn test_double() {
     let mut rom = [0; ROM_SIZE];
     rom[0] = 0x0A; // Copy literal
-    rom[1] = 0x10; // into r0b
-    rom[2] = 0x0F; // 15.
-
-    rom[3] = 0x0A; // Copy literal
-    rom[4] = 0x11; // into r1b
-    rom[5] = 0x08; // 8.
-
-    rom[6] = 0x30; // Remainder register
-    rom[7] = 0x10; // into r0b
-    rom[8] = 0x11; // by r1b.
-
-    rom[9] = 0x2F; // Remainder literal
-    rom[10] = 0x11; // into r1b
-    rom[11] = 0x04; // 4.
-
-    rom[12] = 0x0A; // Copy literal
-    rom[13] = 0x12; // into r2b.
-    rom[14] = 0xE2; // -30.
+    rom[1] = 0x28; // into d0
+    rom[2] = 0x3F;
+    rom[3] = 0xF0;
+    rom[4] = 0x00;
+    rom[5] = 0x00;
+    rom[6] = 0x00;
+    rom[7] = 0x00;
+    rom[8] = 0x00;
+    rom[9] = 0x00; // 1.0.
 
-    rom[15] = 0x2F; // Remainder literal
-    rom[16] = 0x12; // into r2b
-    rom[17] = 0x04; // 4.
+    rom[10] = 0x2B; // Divide literal
+    rom[11] = 0x28; // d0
+    rom[12] = 0x40;
+    rom[13] = 0x08;
+    rom[14] = 0x00;
+    rom[15] = 0x00;
+    rom[16] = 0x00;
+    rom[17] = 0x00;
+    rom[18] = 0x00;
+    rom[19] = 0x00; // by 3.0.
 
-    rom[18] = 0x0A; // Copy literal
-    rom[19] = 0x13; // into r3b
-    rom[20] = 0xE2; // -30.
+    rom[20] = 0x0A; // Copy literal
+    rom[21] = 0x00; // into r0
+    rom[22] = 0x01;
+    rom[23] = 0x00;
+    rom[24] = 0x00;
+    rom[25] = 0x01; // 2^24 + 1, which is too precise for a float.
 
-    rom[21] = 0x2F; // Remainder literal
-    rom[22] = 0x13; // into r3b
-    rom[23] = 0xFC; // -4.
+    rom[26] = 0x6C; // Signed convert
+    rom[27] = 0x29; // into d1
+    rom[28] = 0x00; // from r0.
 
-    rom[24] = 0x0A; // Copy literal
-    rom[25] = 0x14; // into r4b
-    rom[26] = 0xFF; // 255.
+    rom[29] = 0x0A; // Copy literal
+    rom[30] = 0x2A; // into d2
+    rom[31] = 0x40;
+    rom[32] = 0x00;
+    rom[33] = 0x00;
+    rom[34] = 0x00;
+    rom[35] = 0x00;
+    rom[36] = 0x00;
+    rom[37] = 0x00;
+    rom[38] = 0x00; // 2.0.
 
-    rom[27] = 0x6C; // Convert
-    rom[28] = 0x18; // into f0
-    rom[29] = 0x04; // r4.
+    rom[39] = 0xAB; // Square root
+    rom[40] = 0x2A; // of d2.
 
-    rom[30] = 0x0A; // Copy literal
-    rom[31] = 0x14; // into r4b
-    rom[32] = 0x64; // 100.
+    rom[41] = 0x6D; // Unsigned convert
+    rom[42] = 0x18; // into f0
+    rom[43] = 0x28; // from d0.
 
-    rom[33] = 0x6C; // Convert
-    rom[34] = 0x19; // into f1
-    rom[35] = 0x04; // r4.
+    rom[44] = 0x08; // Store literal address
+    rom[45] = 0x00;
+    rom[46] = 0x00;
+    rom[47] = 0x40;
+    rom[48] = 0x00; // 0x4000
+    rom[49] = 0x28; // d0.
 
-    rom[36] = 0x30; // Remainder register
-    rom[37] = 0x18; // into f0
-    rom[38] = 0x19; // by f1.
+    rom[50] = 0x06; // Load literal address
+    rom[51] = 0x2B; // into d3
+    rom[52] = 0x00;
+    rom[53] = 0x00;
+    rom[54] = 0x40;
+    rom[55] = 0x00; // 0x4000.
 
     let (cpu, ui_commands) = run_default(rom);
     assert_eq!(ui_commands.len(), 1);
-    assert_eq!(internal!(cpu).r[0], 0x07);
-    assert_eq!(internal!(cpu).r[1], 0x00);
-    assert_eq!(internal!(cpu).r[2], 0xFE);
-    assert_eq!(internal!(cpu).r[3], 0xFE);
-    assert_about_eq!(internal!(cpu).f[0], 55.0);
-    assert_about_eq!(internal!(cpu).f[1], 100.0);
+    let third = 1.0f64 / 3.0;
+    assert_eq!(internal!(cpu).d[0], third);
+    assert_eq!(internal!(cpu).d[1], 16777217.0);
+    assert_eq!(internal!(cpu).d[2], 2.0f64.sqrt());
+    assert_eq!(internal!(cpu).d[3], third);
+    assert_eq!(internal!(cpu).f[0], third as f32);
+    let mmu = internal!(cpu).mmu.lock().unwrap();
+    assert_eq!(
+        mmu.load_physical_32(0x4000).unwrap(),
+        (third.to_bits() >> 32) as u32
+    );
+    assert_eq!(
+        mmu.load_physical_32(0x4004).unwrap(),
+        third.to_bits() as u32
+    );
+    drop(mmu);
+    assert_eq!(internal!(cpu).flags, 0);
+    assert_eq!(internal!(cpu).fpsr, fpu::FP_INEXACT);
 }
 
 #[test]
 #[timeout(100)]
-fn test_urem() {
+fn test_double_bad_types() {
     let mut rom = [0; ROM_SIZE];
-    // These use the same values as test_srem, but interpret them as unsigned instead.
+    // Try to add a float to a double. Should raise an interrupt.
     rom[0] = 0x0A; // Copy literal
-    rom[1] = 0x10; // into r0b
-    rom[2] = 0x0F; // 15.
-
-    rom[3] = 0x0A; // Copy literal
-    rom[4] = 0x11; // into r1b
-    rom[5] = 0x08; // 8.
-
-    rom[6] = 0x32; // Remainder register
-    rom[7] = 0x10; // into r0b
-    rom[8] = 0x11; // r1b.
-
-    rom[9] = 0x31; // Remainder literal
-    rom[10] = 0x11; // into r1b
-    rom[11] = 0x04; // 4.
+    rom[1] = 0x22; // into kspr
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x50;
+    rom[5] = 0x00; // address 0x00005000.
 
-    rom[12] = 0x0A; // Copy literal
-    rom[13] = 0x12; // into r2b.
-    rom[14] = 0xE2; // 226.
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x24; // into imr
+    rom[8] = 0x00;
+    rom[9] = 0x01; // illegal operation interrupt only.
 
-    rom[15] = 0x31; // Remainder literal
-    rom[16] = 0x12; // into r2b
-    rom[17] = 0x04; // 4.
+    rom[10] = 0x0A; // Copy literal
+    rom[11] = 0x18; // into f0
+    rom[12] = 0x3F;
+    rom[13] = 0x80;
+    rom[14] = 0x00;
+    rom[15] = 0x00; // 1.0.
 
-    rom[18] = 0x0A; // Copy literal
-    rom[19] = 0x13; // into r3b
-    rom[20] = 0xE2; // 226.
+    rom[16] = 0x22; // Add ref
+    rom[17] = 0x28; // into d0
+    rom[18] = 0x18; // f0.
 
-    rom[21] = 0x31; // Remainder literal
-    rom[22] = 0x13; // into r3b
-    rom[23] = 0xFC; // 252.
+    rom[19] = 0x01; // Pause. We should never hit this.
 
     let (cpu, ui_commands) = run_default(rom);
     assert_eq!(ui_commands.len(), 1);
-    assert_eq!(internal!(cpu).r[0], 0x07);
-    assert_eq!(internal!(cpu).r[1], 0x00);
-    assert_eq!(internal!(cpu).r[2], 0x02);
-    assert_eq!(internal!(cpu).r[3], 0xE2);
+    assert_eq!(internal!(cpu).d[0], 0.0);
 }
 
 #[test]
@@ -1991,62 +4569,200 @@ fn test_rrot() {
     rom[19] = 0x13; // into r3b
     rom[20] = 0x01; // by 1.
 
-    rom[21] = 0x42; // Right rotate literal
-    rom[22] = 0x08; // into r0h
-    rom[23] = 0x10; // by 16.
+    rom[21] = 0x42; // Right rotate literal
+    rom[22] = 0x08; // into r0h
+    rom[23] = 0x10; // by 16.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[0], 0x40);
+    assert_eq!(internal!(cpu).r[1], 0x08);
+    assert_eq!(internal!(cpu).r[2], 0xFF);
+    assert_eq!(internal!(cpu).r[3], 0x00);
+    assert_eq!(internal!(cpu).flags, 0);
+}
+
+#[test]
+#[timeout(100)]
+fn test_rotcarry() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x10; // into r0b
+    rom[2] = 0x80; // 128.
+
+    rom[3] = 0x0A; // Copy literal
+    rom[4] = 0x11; // into r1b
+    rom[5] = 0x01; // 1.
+
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x12; // into r2b
+    rom[8] = 0xFF; // 255.
+
+    rom[9] = 0x45; // Left rotate carry register
+    rom[10] = 0x10; // into r0b
+    rom[11] = 0x11; // by r1b.
+
+    rom[12] = 0x44; // Left rotate carry literal
+    rom[13] = 0x11; // into r1b
+    rom[14] = 0x05; // by 5.
+
+    rom[15] = 0x46; // Right rotate carry literal
+    rom[16] = 0x12; // into r2b
+    rom[17] = 0x01; // by 1.
+
+    rom[18] = 0x0A; // Copy literal
+    rom[19] = 0x13; // into r3b
+    rom[20] = 0x01; // 1.
+
+    rom[21] = 0x47; // Right rotate carry register
+    rom[22] = 0x13; // into r3b
+    rom[23] = 0x13; // by r3b.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[0], 0x00);
+    assert_eq!(internal!(cpu).r[1], 0x30);
+    assert_eq!(internal!(cpu).r[2], 0x7F);
+    assert_eq!(internal!(cpu).r[3], 0x80);
+    assert_eq!(internal!(cpu).flags, FLAG_NEGATIVE | FLAG_CARRY);
+}
+
+#[test]
+#[timeout(100)]
+fn test_bit_count() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x00; // into r0
+    rom[2] = 0x00;
+    rom[3] = 0xF0;
+    rom[4] = 0x0F;
+    rom[5] = 0x00; // 0x00F00F00.
+
+    rom[6] = 0x99; // Population count
+    rom[7] = 0x00; // of r0.
+
+    rom[8] = 0x0A; // Copy literal
+    rom[9] = 0x01; // into r1
+    rom[10] = 0x00;
+    rom[11] = 0xF0;
+    rom[12] = 0x0F;
+    rom[13] = 0x00; // 0x00F00F00.
+
+    rom[14] = 0x9A; // Count leading zeros
+    rom[15] = 0x01; // of r1.
+
+    rom[16] = 0x0A; // Copy literal
+    rom[17] = 0x0A; // into r2h
+    rom[18] = 0x00;
+    rom[19] = 0x10; // 0x0010.
+
+    rom[20] = 0x9B; // Count trailing zeros
+    rom[21] = 0x0A; // of r2h.
+
+    rom[22] = 0x9A; // Count leading zeros
+    rom[23] = 0x13; // of r3b (zero).
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[0], 8);
+    assert_eq!(internal!(cpu).r[1], 8);
+    assert_eq!(internal!(cpu).r[2], 4);
+    assert_eq!(internal!(cpu).r[3], 8);
+    assert_eq!(internal!(cpu).flags, FLAG_CARRY);
+}
+
+#[test]
+#[timeout(100)]
+fn test_bit_test_set_clear() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x00; // into r0
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x00;
+    rom[5] = 0xF0; // 0x000000F0.
+
+    rom[6] = 0x9E; // Set bit literal
+    rom[7] = 0x00; // of r0
+    rom[8] = 0x00; // bit 0.
+
+    rom[9] = 0x85; // Set if equal (the bit was clear)
+    rom[10] = 0x12; // r2b.
+
+    rom[11] = 0xA0; // Clear bit literal
+    rom[12] = 0x00; // of r0
+    rom[13] = 0x24; // bit 36 (wraps to 4).
+
+    rom[14] = 0x0A; // Copy literal
+    rom[15] = 0x11; // into r1b
+    rom[16] = 0x07; // 7.
+
+    rom[17] = 0x9D; // Test bit ref
+    rom[18] = 0x00; // of r0
+    rom[19] = 0x11; // bit r1b.
 
     let (cpu, ui_commands) = run_default(rom);
     assert_eq!(ui_commands.len(), 1);
-    assert_eq!(internal!(cpu).r[0], 0x40);
-    assert_eq!(internal!(cpu).r[1], 0x08);
-    assert_eq!(internal!(cpu).r[2], 0xFF);
-    assert_eq!(internal!(cpu).r[3], 0x00);
-    assert_eq!(internal!(cpu).flags, 0);
+    assert_eq!(internal!(cpu).r[0], 0xE1);
+    assert_eq!(internal!(cpu).r[2], 1);
+    assert_eq!(internal!(cpu).flags, FLAG_CARRY);
 }
 
 #[test]
 #[timeout(100)]
-fn test_rotcarry() {
+fn test_bit_bad_types() {
     let mut rom = [0; ROM_SIZE];
+    // Try to set a bit indexed by a half register. Should raise an interrupt.
     rom[0] = 0x0A; // Copy literal
-    rom[1] = 0x10; // into r0b
-    rom[2] = 0x80; // 128.
-
-    rom[3] = 0x0A; // Copy literal
-    rom[4] = 0x11; // into r1b
-    rom[5] = 0x01; // 1.
+    rom[1] = 0x22; // into kspr
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x50;
+    rom[5] = 0x00; // address 0x00005000.
 
     rom[6] = 0x0A; // Copy literal
-    rom[7] = 0x12; // into r2b
-    rom[8] = 0xFF; // 255.
+    rom[7] = 0x24; // into imr
+    rom[8] = 0x00;
+    rom[9] = 0x01; // illegal operation interrupt only.
 
-    rom[9] = 0x45; // Left rotate carry register
-    rom[10] = 0x10; // into r0b
-    rom[11] = 0x11; // by r1b.
+    rom[10] = 0x9F; // Set bit ref
+    rom[11] = 0x00; // of r0
+    rom[12] = 0x09; // bit r1h.
 
-    rom[12] = 0x44; // Left rotate carry literal
-    rom[13] = 0x11; // into r1b
-    rom[14] = 0x05; // by 5.
+    rom[13] = 0x01; // Pause. We should never hit this.
 
-    rom[15] = 0x46; // Right rotate carry literal
-    rom[16] = 0x12; // into r2b
-    rom[17] = 0x01; // by 1.
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[0], 0x00000000);
+}
 
-    rom[18] = 0x0A; // Copy literal
-    rom[19] = 0x13; // into r3b
-    rom[20] = 0x01; // 1.
+#[test]
+#[timeout(100)]
+fn test_byteswap() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x00; // into r0
+    rom[2] = 0x12;
+    rom[3] = 0x34;
+    rom[4] = 0x56;
+    rom[5] = 0x78; // 0x12345678.
 
-    rom[21] = 0x47; // Right rotate carry register
-    rom[22] = 0x13; // into r3b
-    rom[23] = 0x13; // by r3b.
+    rom[6] = 0xA2; // Byte swap
+    rom[7] = 0x00; // r0.
+
+    rom[8] = 0x0A; // Copy literal
+    rom[9] = 0x09; // into r1h
+    rom[10] = 0x00;
+    rom[11] = 0x80; // 0x0080.
+
+    rom[12] = 0xA2; // Byte swap
+    rom[13] = 0x09; // r1h.
 
     let (cpu, ui_commands) = run_default(rom);
     assert_eq!(ui_commands.len(), 1);
-    assert_eq!(internal!(cpu).r[0], 0x00);
-    assert_eq!(internal!(cpu).r[1], 0x30);
-    assert_eq!(internal!(cpu).r[2], 0x7F);
-    assert_eq!(internal!(cpu).r[3], 0x80);
-    assert_eq!(internal!(cpu).flags, FLAG_NEGATIVE | FLAG_CARRY);
+    assert_eq!(internal!(cpu).r[0], 0x78563412);
+    assert_eq!(internal!(cpu).r[1], 0x8000);
+    assert_eq!(internal!(cpu).flags, FLAG_NEGATIVE);
 }
 
 #[test]
@@ -2091,6 +4807,101 @@ fn test_jump() {
     assert_eq!(internal!(cpu).r[7], 0x42);
 }
 
+#[test]
+#[timeout(100)]
+fn test_jump_relative() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x78; // Jump relative
+    rom[1] = 0x00;
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x3B; // forward 0x3B bytes (ROM byte 64).
+
+    rom[5] = 0x01; // Pause (fail condition).
+
+    rom[10] = 0x00; // HALT.
+
+    rom[64] = 0x0A; // Copy literal
+    rom[65] = 0x17; // into r7b
+    rom[66] = 0x42; // some number.
+
+    rom[67] = 0x78; // Jump relative
+    rom[68] = 0xFF;
+    rom[69] = 0xFF;
+    rom[70] = 0xFF;
+    rom[71] = 0xC2; // back 0x3E bytes (ROM byte 10).
+
+    rom[72] = 0x01; // Pause (fail condition).
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[7], 0x42);
+    assert_eq!(internal!(cpu).program_counter, 0x4B);
+}
+
+#[test]
+#[timeout(100)]
+fn test_cond_jump_relative() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x10; // into r0b
+    rom[2] = 0x0A; // 10.
+
+    rom[3] = 0x4A; // Compare literal
+    rom[4] = 0x10; // r0b
+    rom[5] = 0x0A; // with 10.
+
+    rom[6] = 0x7A; // Jump relative if not equal
+    rom[7] = 0x00;
+    rom[8] = 0x00;
+    rom[9] = 0x00;
+    rom[10] = 0xF0; // forward 0xF0 bytes (ROM byte 251).
+
+    rom[11] = 0x79; // Jump relative if equal
+    rom[12] = 0x00;
+    rom[13] = 0x00;
+    rom[14] = 0x00;
+    rom[15] = 0x30; // forward 0x30 bytes (ROM byte 64).
+
+    rom[16] = 0x01; // Pause (fail condition).
+
+    rom[64] = 0x0A; // Copy literal
+    rom[65] = 0x17; // into r7b
+    rom[66] = 0x99; // some number.
+
+    rom[67] = 0x00; // HALT.
+
+    rom[251] = 0x01; // Pause (fail condition).
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[7], 0x99);
+}
+
+#[test]
+#[timeout(100)]
+fn test_loadpc() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x84; // Load PC-relative address
+    rom[1] = 0x00; // into r0
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x00;
+    rom[5] = 0x00; // offset 0.
+
+    rom[6] = 0x84; // Load PC-relative address
+    rom[7] = 0x01; // into r1
+    rom[8] = 0xFF;
+    rom[9] = 0xFF;
+    rom[10] = 0xFF;
+    rom[11] = 0xFA; // offset -6.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[0], 0x46);
+    assert_eq!(internal!(cpu).r[1], 0x46);
+}
+
 #[test]
 #[timeout(100)]
 fn test_compare() {
@@ -2727,6 +5538,174 @@ fn test_ujlessereq() {
     assert_eq!(internal!(cpu).r[7], 0x99);
 }
 
+#[test]
+#[timeout(100)]
+fn test_set() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x01; // into r1
+    rom[2] = 0xFF;
+    rom[3] = 0xFF;
+    rom[4] = 0xFF;
+    rom[5] = 0xFF; // some number.
+
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x03; // into r3
+    rom[8] = 0x12;
+    rom[9] = 0x34;
+    rom[10] = 0x56;
+    rom[11] = 0x78; // some number.
+
+    rom[12] = 0x0A; // Copy literal
+    rom[13] = 0x10; // into r0b
+    rom[14] = 0xFF; // -1 or 255.
+
+    rom[15] = 0x4A; // Compare literal
+    rom[16] = 0x10; // r0b
+    rom[17] = 0x01; // with 1.
+
+    rom[18] = 0x85; // Set if equal
+    rom[19] = 0x01; // r1.
+
+    rom[20] = 0x86; // Set if not equal
+    rom[21] = 0x02; // r2.
+
+    rom[22] = 0x87; // Set if signed greater
+    rom[23] = 0x0B; // r3h.
+
+    rom[24] = 0x89; // Set if unsigned greater
+    rom[25] = 0x14; // r4b.
+
+    rom[26] = 0x8B; // Set if signed lesser
+    rom[27] = 0x05; // r5.
+
+    rom[28] = 0x8D; // Set if unsigned lesser
+    rom[29] = 0x16; // r6b.
+
+    rom[30] = 0x4A; // Compare literal
+    rom[31] = 0x10; // r0b
+    rom[32] = 0xFF; // with itself.
+
+    rom[33] = 0x88; // Set if signed greater or equal
+    rom[34] = 0x17; // r7b.
+
+    rom[35] = 0x8A; // Set if unsigned greater or equal
+    rom[36] = 0x0F; // r7h.
+
+    rom[37] = 0x8C; // Set if signed lesser or equal
+    rom[38] = 0x00; // r0.
+
+    rom[39] = 0x8E; // Set if unsigned lesser or equal
+    rom[40] = 0x11; // r1b.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[0], 1);
+    assert_eq!(internal!(cpu).r[1], 1);
+    assert_eq!(internal!(cpu).r[2], 1);
+    assert_eq!(internal!(cpu).r[3], 0x12340000);
+    assert_eq!(internal!(cpu).r[4], 1);
+    assert_eq!(internal!(cpu).r[5], 1);
+    assert_eq!(internal!(cpu).r[6], 0);
+    assert_eq!(internal!(cpu).r[7], 1);
+}
+
+#[test]
+#[timeout(100)]
+fn test_cmov() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x00; // into r0
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x00;
+    rom[5] = 0x05; // 5.
+
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x01; // into r1
+    rom[8] = 0x00;
+    rom[9] = 0x00;
+    rom[10] = 0x00;
+    rom[11] = 0x07; // 7.
+
+    rom[12] = 0x0A; // Copy literal
+    rom[13] = 0x18; // into f0
+    rom[14] = 0x3F;
+    rom[15] = 0x80;
+    rom[16] = 0x00;
+    rom[17] = 0x00; // 1.0.
+
+    rom[18] = 0x0A; // Copy literal
+    rom[19] = 0x19; // into f1
+    rom[20] = 0x40;
+    rom[21] = 0x00;
+    rom[22] = 0x00;
+    rom[23] = 0x00; // 2.0.
+
+    rom[24] = 0x4A; // Compare literal
+    rom[25] = 0x00; // r0
+    rom[26] = 0x00;
+    rom[27] = 0x00;
+    rom[28] = 0x00;
+    rom[29] = 0x05; // with 5.
+
+    rom[30] = 0x90; // Move if not equal
+    rom[31] = 0x02; // into r2
+    rom[32] = 0x01; // from r1.
+
+    rom[33] = 0x8F; // Move if equal
+    rom[34] = 0x03; // into r3
+    rom[35] = 0x01; // from r1.
+
+    rom[36] = 0x8F; // Move if equal
+    rom[37] = 0x18; // into f0
+    rom[38] = 0x19; // from f1.
+
+    rom[39] = 0x91; // Move if signed greater
+    rom[40] = 0x0D; // into r5h
+    rom[41] = 0x08; // from r0h.
+
+    rom[42] = 0x94; // Move if unsigned greater or equal
+    rom[43] = 0x0C; // into r4h
+    rom[44] = 0x08; // from r0h.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[2], 0);
+    assert_eq!(internal!(cpu).r[3], 7);
+    assert_eq!(internal!(cpu).r[4], 5);
+    assert_eq!(internal!(cpu).r[5], 0);
+    assert_eq!(internal!(cpu).f[0], 2.0);
+}
+
+#[test]
+#[timeout(100)]
+fn test_cmov_bad_types() {
+    let mut rom = [0; ROM_SIZE];
+    // Try a conditional move with unmatched sizes. Should raise an interrupt.
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x22; // into kspr
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x50;
+    rom[5] = 0x00; // address 0x00005000.
+
+    rom[6] = 0x0A; // Copy literal
+    rom[7] = 0x24; // into imr
+    rom[8] = 0x00;
+    rom[9] = 0x01; // illegal operation interrupt only.
+
+    rom[10] = 0x8F; // Move if equal
+    rom[11] = 0x01; // into r1
+    rom[12] = 0x0B; // from r3h.
+
+    rom[13] = 0x01; // Pause. We should never hit this.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[1], 0x00000000);
+}
+
 #[test]
 #[timeout(100)]
 fn test_call_return() {
@@ -2786,7 +5765,54 @@ fn test_call_return() {
     assert_eq!(internal!(cpu).r[7], 0x56);
     assert_eq!(internal!(cpu).flags, FLAG_NEGATIVE | FLAG_CARRY);
     // The return address of the last subroutine should still be on the stack.
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x00004FFF), Ok(0x56));
+    assert_eq!(
+        internal!(cpu)
+            .mmu
+            .lock()
+            .unwrap()
+            .load_physical_8(0x00004FFF),
+        Ok(0x56)
+    );
+}
+
+#[test]
+#[timeout(100)]
+fn test_call_relative() {
+    let mut rom = [0; ROM_SIZE];
+    rom[0] = 0x0A; // Copy literal
+    rom[1] = 0x22; // into KSPR
+    rom[2] = 0x00;
+    rom[3] = 0x00;
+    rom[4] = 0x50;
+    rom[5] = 0x00; // address 0x00005000.
+
+    rom[6] = 0x83; // Call relative
+    rom[7] = 0x00;
+    rom[8] = 0x00;
+    rom[9] = 0x00;
+    rom[10] = 0x35; // forward 0x35 bytes (ROM byte 64).
+
+    rom[11] = 0x00; // HALT.
+
+    // Subroutine
+    rom[64] = 0x0A; // Copy literal
+    rom[65] = 0x17; // into r7b
+    rom[66] = 0x56; // some number.
+
+    rom[67] = 0x6A; // RETURN.
+
+    let (cpu, ui_commands) = run_default(rom);
+    assert_eq!(ui_commands.len(), 1);
+    assert_eq!(internal!(cpu).r[7], 0x56);
+    // The return address should still be on the stack.
+    assert_eq!(
+        internal!(cpu)
+            .mmu
+            .lock()
+            .unwrap()
+            .load_physical_32(0x00004FFC),
+        Ok(0x4B)
+    );
 }
 
 #[test]
@@ -2838,5 +5864,12 @@ fn test_call_modify_return() {
     assert_eq!(ui_commands.len(), 1);
     assert_eq!(internal!(cpu).r[7], 0x33);
     // The return address of the last subroutine should still be on the stack.
-    assert_eq!(internal!(cpu).mmu.load_physical_8(0x00004FFF), Ok(0xC0));
+    assert_eq!(
+        internal!(cpu)
+            .mmu
+            .lock()
+            .unwrap()
+            .load_physical_8(0x00004FFF),
+        Ok(0xC0)
+    );
 }
@@ -378,7 +378,7 @@ fn worker_iteration(
                 })
                 .and_then(|mut file| {
                     // Read into the buffer.
-                    file.read_exact(&mut *sd.buffer).ok()
+                    file.read_exact(&mut sd.buffer).ok()
                 });
             (result, sustained)
         }
@@ -396,7 +396,7 @@ fn worker_iteration(
                 })
                 .and_then(|mut file| {
                     // Write from the buffer.
-                    file.write_all(&*sd.buffer).ok()
+                    file.write_all(&sd.buffer).ok()
                 });
             (result, sustained)
         }
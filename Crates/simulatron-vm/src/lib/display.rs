@@ -1,70 +1,353 @@
-use std::sync::mpsc::Sender;
+use std::sync::{mpsc::Sender, Arc, RwLock};
 
 use crate::ui::UICommand;
 
+// Display dimensions.
+const TEXT_ROWS: u32 = 25;
+const TEXT_COLS: u32 = 80;
+const PIXEL_ROWS: u32 = TEXT_ROWS * 2;
+const PIXEL_COLS: u32 = TEXT_COLS;
+const TEXT_PAGES: u32 = 4;
+
+// Sizes of the display memory blocks.
+const TEXT_BLOCK_SIZE: u32 = TEXT_ROWS * TEXT_COLS;
+const FRAMEBUFFER_SIZE: u32 = PIXEL_ROWS * PIXEL_COLS;
+
+// Display control register offsets.
+const CONTROL_MODE: u32 = 0;
+const CONTROL_CURSOR_ROW: u32 = 1;
+const CONTROL_CURSOR_COL: u32 = 2;
+const CONTROL_CURSOR_VISIBLE: u32 = 3;
+const CONTROL_CURSOR_STYLE: u32 = 4;
+const CONTROL_VISIBLE_PAGE: u32 = 5;
+const CONTROL_MEMORY_PAGE: u32 = 6;
+const CONTROL_SCROLL: u32 = 7;
+
+// Display mode register values.
+const MODE_TEXT: u8 = 0;
+const MODE_GRAPHICS: u8 = 1;
+
+// Cursor register values.
+const CURSOR_VISIBLE: u8 = 1;
+const CURSOR_STYLE_MASK: u8 = 0b11;
+
+// Initial colours: white on black.
+const DEFAULT_FG: u8 = 0b00111111;
+const DEFAULT_BG: u8 = 0b00000000;
+
+/// The possible display modes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DisplayMode {
+    Text,
+    Graphics,
+}
+
+/// The possible text cursor styles.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CursorStyle {
+    Inverted,
+    Underscore,
+    Bar,
+    Block,
+}
+
+impl CursorStyle {
+    fn from_register(value: u8) -> Self {
+        match value & CURSOR_STYLE_MASK {
+            0 => CursorStyle::Inverted,
+            1 => CursorStyle::Underscore,
+            2 => CursorStyle::Bar,
+            3 => CursorStyle::Block,
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// The state of the hardware text cursor.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Cursor {
+    pub row: u16,
+    pub col: u16,
+    pub style: CursorStyle,
+}
+
+/// The contents of display memory. This is owned by the display controller,
+/// and shared with the UI which renders from it.
+pub struct DisplayMemory {
+    chars: Vec<u8>,
+    fg: Vec<u8>,
+    bg: Vec<u8>,
+    framebuffer: Vec<u8>,
+    mode: DisplayMode,
+    cursor_row: u8,
+    cursor_col: u8,
+    cursor_visible: u8,
+    cursor_style: u8,
+    visible_page: u8,
+    memory_page: u8,
+    scroll: u8,
+}
+
+/// A handle to display memory that can be shared across threads.
+pub type SharedDisplayMemory = Arc<RwLock<DisplayMemory>>;
+
+impl DisplayMemory {
+    fn new() -> Self {
+        DisplayMemory {
+            chars: vec![0; (TEXT_BLOCK_SIZE * TEXT_PAGES) as usize],
+            fg: vec![DEFAULT_FG; (TEXT_BLOCK_SIZE * TEXT_PAGES) as usize],
+            bg: vec![DEFAULT_BG; (TEXT_BLOCK_SIZE * TEXT_PAGES) as usize],
+            framebuffer: vec![DEFAULT_BG; FRAMEBUFFER_SIZE as usize],
+            mode: DisplayMode::Text,
+            cursor_row: 0,
+            cursor_col: 0,
+            cursor_visible: 0,
+            cursor_style: 0,
+            visible_page: 0,
+            memory_page: 0,
+            scroll: 0,
+        }
+    }
+
+    /// The current display mode.
+    pub fn mode(&self) -> DisplayMode {
+        self.mode
+    }
+
+    /// The text cursor, if it should currently be shown. This requires it
+    /// to be enabled, in text mode, and positioned within the display.
+    pub fn cursor(&self) -> Option<Cursor> {
+        let row = u16::from(self.cursor_row);
+        let col = u16::from(self.cursor_col);
+        if self.cursor_visible & CURSOR_VISIBLE != 0
+            && self.mode == DisplayMode::Text
+            && u32::from(row) < TEXT_ROWS
+            && u32::from(col) < TEXT_COLS
+        {
+            Some(Cursor {
+                row,
+                col,
+                style: CursorStyle::from_register(self.cursor_style),
+            })
+        } else {
+            None
+        }
+    }
+
+    /// The raw character, foreground and background bytes of the text cell
+    /// shown at the given screen position, accounting for the visible page
+    /// and scroll offset.
+    pub fn text_cell(&self, row: u16, col: u16) -> (u8, u8, u8) {
+        let buffer_row = (u32::from(row) + self.scroll_offset()) % TEXT_ROWS;
+        let cell_num = buffer_row * TEXT_COLS + u32::from(col);
+        let index = page_index(self.visible_page, cell_num);
+        (self.chars[index], self.fg[index], self.bg[index])
+    }
+
+    /// The scroll offset in rows, wrapped to the height of the display.
+    fn scroll_offset(&self) -> u32 {
+        u32::from(self.scroll) % TEXT_ROWS
+    }
+
+    /// Convert a cell number within a page to the screen position it is
+    /// currently shown at, if any.
+    fn screen_position(&self, page: u8, cell_num: u32) -> Option<(u16, u16)> {
+        if u32::from(page) % TEXT_PAGES != u32::from(self.visible_page) % TEXT_PAGES {
+            return None;
+        }
+        let buffer_row = cell_num / TEXT_COLS;
+        let row = (buffer_row + TEXT_ROWS - self.scroll_offset()) % TEXT_ROWS;
+        let col = cell_num % TEXT_COLS;
+        Some((row as u16, col as u16))
+    }
+
+    /// The raw colour byte of the given pixel.
+    pub fn pixel(&self, row: u16, col: u16) -> u8 {
+        self.framebuffer[usize::from(row) * PIXEL_COLS as usize + usize::from(col)]
+    }
+}
+
 /// A display controller.
 pub struct DisplayController {
     ui_channel: Sender<UICommand>,
+    memory: SharedDisplayMemory,
+    mode_register: u8,
 }
 
 impl DisplayController {
     /// Create a new display controller with the given UI command channel.
     pub fn new(ui_channel: Sender<UICommand>) -> Self {
-        DisplayController { ui_channel }
-    }
-
-    /// Handle a memory-mapped command signal by sending a command to the UI.
-    pub fn store(&self, address: u32, value: u8) {
-        if address < 2000 {
-            // Character value.
-            let row = (address / 80) as u16;
-            let col = (address % 80) as u16;
-            if let Some(character) = u8_to_printable_char(value) {
-                self.ui_channel
-                    .send(UICommand::SetChar {
-                        row,
-                        col,
-                        character,
-                    })
-                    .unwrap();
-            }
-        } else if address < 4000 {
-            // Foreground color.
-            let cell_num = address - 2000;
-            let row = (cell_num / 80) as u16;
-            let col = (cell_num % 80) as u16;
-            let (r, g, b) = rgb(value);
-            self.ui_channel
-                .send(UICommand::SetFg { row, col, r, g, b })
-                .unwrap();
-        } else if address < 6000 {
-            // Background color.
-            let cell_num = address - 4000;
-            let row = (cell_num / 80) as u16;
-            let col = (cell_num % 80) as u16;
-            let (r, g, b) = rgb(value);
-            self.ui_channel
-                .send(UICommand::SetBg { row, col, r, g, b })
-                .unwrap();
+        DisplayController {
+            ui_channel,
+            memory: Arc::new(RwLock::new(DisplayMemory::new())),
+            mode_register: MODE_TEXT,
+        }
+    }
+
+    /// Get a handle to the display memory, for rendering.
+    pub fn memory(&self) -> SharedDisplayMemory {
+        Arc::clone(&self.memory)
+    }
+
+    /// The mode currently selected by the mode register.
+    fn mode(&self) -> DisplayMode {
+        if self.mode_register & MODE_GRAPHICS != 0 {
+            DisplayMode::Graphics
         } else {
-            unreachable!()
+            DisplayMode::Text
+        }
+    }
+
+    /// Handle a write to display memory, notifying the UI of any cell that
+    /// needs redrawing.
+    pub fn store(&mut self, address: u32, value: u8) {
+        let mut memory = self.memory.write().unwrap();
+        match memory.mode {
+            DisplayMode::Text => {
+                let cell_num = address % TEXT_BLOCK_SIZE;
+                let index = page_index(memory.memory_page, cell_num);
+                if address < TEXT_BLOCK_SIZE {
+                    memory.chars[index] = value;
+                } else if address < TEXT_BLOCK_SIZE * 2 {
+                    memory.fg[index] = value;
+                } else if address < TEXT_BLOCK_SIZE * 3 {
+                    memory.bg[index] = value;
+                } else {
+                    unreachable!()
+                }
+                // Only redraw if the page being written to is on screen.
+                if let Some((row, col)) = memory.screen_position(memory.memory_page, cell_num) {
+                    self.ui_channel
+                        .send(UICommand::RedrawCell { row, col })
+                        .unwrap();
+                }
+            }
+            DisplayMode::Graphics => {
+                // Anything past the end of the framebuffer is unused in
+                // graphics mode.
+                if address < FRAMEBUFFER_SIZE {
+                    memory.framebuffer[address as usize] = value;
+                    // Each cell holds two vertically-stacked pixels.
+                    let row = (address / PIXEL_COLS / 2) as u16;
+                    let col = (address % PIXEL_COLS) as u16;
+                    self.ui_channel
+                        .send(UICommand::RedrawCell { row, col })
+                        .unwrap();
+                }
+            }
         }
     }
-}
 
-/// Try and convert the given byte in the Simulatron character set to a
-/// printable Unicode character.
-fn u8_to_printable_char(byte: u8) -> Option<char> {
-    match byte {
-        31 => Some('£'),
-        32..=126 => Some(char::from(byte)),
-        127 => Some('¬'),
-        _ => None,
+    /// Handle a read from display memory.
+    pub fn load(&self, address: u32) -> u8 {
+        let memory = self.memory.read().unwrap();
+        match memory.mode {
+            DisplayMode::Text => {
+                let index = page_index(memory.memory_page, address % TEXT_BLOCK_SIZE);
+                if address < TEXT_BLOCK_SIZE {
+                    memory.chars[index]
+                } else if address < TEXT_BLOCK_SIZE * 2 {
+                    memory.fg[index]
+                } else if address < TEXT_BLOCK_SIZE * 3 {
+                    memory.bg[index]
+                } else {
+                    unreachable!()
+                }
+            }
+            DisplayMode::Graphics => {
+                if address < FRAMEBUFFER_SIZE {
+                    memory.framebuffer[address as usize]
+                } else {
+                    0
+                }
+            }
+        }
     }
+
+    /// Handle a write to a display control register.
+    pub fn store_control(&mut self, address: u32, value: u8) {
+        match address {
+            CONTROL_MODE => {
+                self.mode_register = value;
+                let new_mode = self.mode();
+                let mut memory = self.memory.write().unwrap();
+                if new_mode != memory.mode {
+                    memory.mode = new_mode;
+                    self.ui_channel.send(UICommand::RedrawAll).unwrap();
+                }
+            }
+            CONTROL_CURSOR_ROW..=CONTROL_CURSOR_STYLE => {
+                let mut memory = self.memory.write().unwrap();
+                let old_cursor = memory.cursor();
+                match address {
+                    CONTROL_CURSOR_ROW => memory.cursor_row = value,
+                    CONTROL_CURSOR_COL => memory.cursor_col = value,
+                    CONTROL_CURSOR_VISIBLE => memory.cursor_visible = value,
+                    CONTROL_CURSOR_STYLE => memory.cursor_style = value,
+                    _ => unreachable!(),
+                }
+                let new_cursor = memory.cursor();
+                // Redraw the cells the cursor has left and entered.
+                if new_cursor != old_cursor {
+                    let old_cell = old_cursor.map(|c| (c.row, c.col));
+                    let new_cell = new_cursor.map(|c| (c.row, c.col));
+                    if let Some((row, col)) = old_cell {
+                        self.ui_channel
+                            .send(UICommand::RedrawCell { row, col })
+                            .unwrap();
+                    }
+                    if let Some((row, col)) = new_cell.filter(|&cell| Some(cell) != old_cell) {
+                        self.ui_channel
+                            .send(UICommand::RedrawCell { row, col })
+                            .unwrap();
+                    }
+                }
+            }
+            CONTROL_VISIBLE_PAGE | CONTROL_SCROLL => {
+                let mut memory = self.memory.write().unwrap();
+                if address == CONTROL_VISIBLE_PAGE {
+                    memory.visible_page = value;
+                } else {
+                    memory.scroll = value;
+                }
+                // Graphics mode is unaffected by text pages and scrolling.
+                if memory.mode == DisplayMode::Text {
+                    self.ui_channel.send(UICommand::RedrawAll).unwrap();
+                }
+            }
+            CONTROL_MEMORY_PAGE => {
+                // Only changes where reads and writes go, not what's shown.
+                self.memory.write().unwrap().memory_page = value;
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Handle a read from a display control register.
+    pub fn load_control(&self, address: u32) -> u8 {
+        let memory = self.memory.read().unwrap();
+        match address {
+            CONTROL_MODE => self.mode_register,
+            CONTROL_CURSOR_ROW => memory.cursor_row,
+            CONTROL_CURSOR_COL => memory.cursor_col,
+            CONTROL_CURSOR_VISIBLE => memory.cursor_visible,
+            CONTROL_CURSOR_STYLE => memory.cursor_style,
+            CONTROL_VISIBLE_PAGE => memory.visible_page,
+            CONTROL_MEMORY_PAGE => memory.memory_page,
+            CONTROL_SCROLL => memory.scroll,
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Find the index into the text buffers of the given cell number within the
+/// given page.
+fn page_index(page: u8, cell_num: u32) -> usize {
+    ((u32::from(page) % TEXT_PAGES) * TEXT_BLOCK_SIZE + cell_num) as usize
 }
 
 /// Convert a raw color byte to its RGB components.
-fn rgb(raw_byte: u8) -> (u8, u8, u8) {
+pub fn rgb(raw_byte: u8) -> (u8, u8, u8) {
     let r = (raw_byte & 0b00110000) >> 4;
     let g = (raw_byte & 0b00001100) >> 2;
     let b = raw_byte & 0b00000011;
@@ -0,0 +1,365 @@
+use log::{debug, info};
+use std::sync::{
+    mpsc::{self, RecvTimeoutError, Sender},
+    Arc, Mutex,
+};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::cpu::INTERRUPT_INTERVAL_TIMER;
+
+pub const NUM_CHANNELS: usize = 4;
+
+// Register addresses.
+const ADDRESS_STATUS: u32 = 0; // Expired channels; cleared on read.
+const ADDRESS_COUNTER: u32 = 1; // Free-running microsecond counter (8 bytes).
+const ADDRESS_COUNTDOWN: u32 = ADDRESS_COUNTER + 8; // Remaining microseconds (4 bytes per channel).
+const ADDRESS_RELOAD: u32 = ADDRESS_COUNTDOWN + 4 * NUM_CHANNELS as u32; // Interval in microseconds (4 bytes per channel).
+const ADDRESS_CONTROL: u32 = ADDRESS_RELOAD + 4 * NUM_CHANNELS as u32; // Control (1 byte per channel).
+const ADDRESS_END: u32 = ADDRESS_CONTROL + NUM_CHANNELS as u32;
+
+// Control register bits.
+const CONTROL_ENABLE: u8 = 0x01;
+const CONTROL_PERIODIC: u8 = 0x02;
+
+/// A single timer channel.
+#[derive(Default)]
+struct Channel {
+    control: u8,
+    reload: u32,
+    deadline: Option<Instant>,
+    latched_countdown: u32,
+}
+
+impl Channel {
+    /// Restart the countdown from the reload value, or disarm the channel if
+    /// it is disabled or has no interval.
+    fn restart(&mut self, now: Instant) {
+        self.deadline = if self.control & CONTROL_ENABLE != 0 && self.reload != 0 {
+            Some(now + Duration::from_micros(self.reload.into()))
+        } else {
+            None
+        };
+    }
+
+    /// The number of microseconds until this channel expires.
+    fn countdown(&self, now: Instant) -> u32 {
+        match self.deadline {
+            Some(deadline) => deadline.saturating_duration_since(now).as_micros() as u32,
+            None => 0,
+        }
+    }
+}
+
+/// Data shared between the controller and CPU threads.
+struct SharedData {
+    epoch: Instant,
+    status: u8,
+    latched_counter: u64,
+    channels: [Channel; NUM_CHANNELS],
+}
+
+impl SharedData {
+    /// Handle any channels that have expired, returning whether there were any.
+    fn expire(&mut self, now: Instant) -> bool {
+        let mut expired = false;
+        for (i, channel) in self.channels.iter_mut().enumerate() {
+            match channel.deadline {
+                Some(deadline) if deadline <= now => {
+                    debug!("Interval timer channel {} expired.", i);
+                    self.status |= 1 << i;
+                    expired = true;
+                    if channel.control & CONTROL_PERIODIC != 0 && channel.reload != 0 {
+                        // Keep to the original schedule, unless we've fallen
+                        // more than a whole period behind.
+                        let next = deadline + Duration::from_micros(channel.reload.into());
+                        channel.deadline = Some(if next > now {
+                            next
+                        } else {
+                            now + Duration::from_micros(channel.reload.into())
+                        });
+                    } else {
+                        channel.control &= !CONTROL_ENABLE;
+                        channel.deadline = None;
+                    }
+                }
+                _ => {}
+            }
+        }
+        expired
+    }
+
+    /// The earliest deadline of any channel.
+    fn next_deadline(&self) -> Option<Instant> {
+        self.channels.iter().filter_map(|c| c.deadline).min()
+    }
+}
+
+/// Commands that can be sent to the timer thread.
+enum TimerMessage {
+    Reconfigure,
+    JoinThread,
+}
+
+/// A programmable interval timer, with several independent countdown
+/// channels and a free-running counter.
+pub struct IntervalTimerController {
+    interrupt_tx: Option<Sender<u32>>,
+    command_tx: Option<Sender<TimerMessage>>,
+    thread_handle: Option<thread::JoinHandle<Sender<u32>>>,
+    shared_data: Arc<Mutex<SharedData>>,
+}
+
+impl IntervalTimerController {
+    /// Construct a new interval timer with the given interrupt channel.
+    pub fn new(interrupt_tx: Sender<u32>) -> Self {
+        IntervalTimerController {
+            interrupt_tx: Some(interrupt_tx),
+            command_tx: None,
+            thread_handle: None,
+            shared_data: Arc::new(Mutex::new(SharedData {
+                epoch: Instant::now(),
+                status: 0,
+                latched_counter: 0,
+                channels: Default::default(),
+            })),
+        }
+    }
+
+    /// Start the timer thread. Panics if already running.
+    pub fn start(&mut self) {
+        let interrupt_tx = self
+            .interrupt_tx
+            .take()
+            .expect("IntervalTimerController was already running.");
+        let (command_tx, command_rx) = mpsc::channel();
+        info!("Interval timer starting.");
+
+        let shared_data = Arc::clone(&self.shared_data);
+        let thread_handle = thread::spawn(move || loop {
+            // Wait for a command, or until the next channel expires. If the
+            // controller was dropped while running, exit as if joined.
+            let next_deadline = shared_data.lock().unwrap().next_deadline();
+            let message = match next_deadline {
+                Some(deadline) => {
+                    let timeout = deadline.saturating_duration_since(Instant::now());
+                    match command_rx.recv_timeout(timeout) {
+                        Ok(message) => Some(message),
+                        Err(RecvTimeoutError::Timeout) => None,
+                        Err(RecvTimeoutError::Disconnected) => Some(TimerMessage::JoinThread),
+                    }
+                }
+                None => Some(command_rx.recv().unwrap_or(TimerMessage::JoinThread)),
+            };
+            if let Some(TimerMessage::JoinThread) = message {
+                return interrupt_tx;
+            }
+
+            // Raise an interrupt if anything expired.
+            let expired = shared_data.lock().unwrap().expire(Instant::now());
+            if expired {
+                interrupt_tx.send(INTERRUPT_INTERVAL_TIMER).unwrap();
+            }
+        });
+        self.thread_handle = Some(thread_handle);
+        self.command_tx = Some(command_tx);
+    }
+
+    /// Stop the timer thread. Panics if not running.
+    pub fn stop(&mut self) {
+        let command_tx = self
+            .command_tx
+            .take()
+            .expect("IntervalTimerController was already stopped.");
+        command_tx.send(TimerMessage::JoinThread).unwrap();
+        let interrupt_tx = self
+            .thread_handle
+            .take()
+            .unwrap()
+            .join()
+            .expect("Interval timer thread terminated with error.");
+        self.interrupt_tx = Some(interrupt_tx);
+        info!("Interval timer stopping.");
+    }
+
+    /// Handle a memory-mapped register read. Reading the status register
+    /// clears it. Reading the first byte of the counter or a countdown
+    /// latches its value, so that the remaining bytes are consistent.
+    pub fn load(&self, address: u32) -> u8 {
+        let mut sd = self.shared_data.lock().unwrap();
+        let now = Instant::now();
+        match address {
+            ADDRESS_STATUS => {
+                let status = sd.status;
+                sd.status = 0;
+                status
+            }
+            ADDRESS_COUNTER..ADDRESS_COUNTDOWN => {
+                let index = (address - ADDRESS_COUNTER) as usize;
+                if index == 0 {
+                    sd.latched_counter = now.duration_since(sd.epoch).as_micros() as u64;
+                }
+                sd.latched_counter.to_be_bytes()[index]
+            }
+            ADDRESS_COUNTDOWN..ADDRESS_RELOAD => {
+                let channel = &mut sd.channels[((address - ADDRESS_COUNTDOWN) / 4) as usize];
+                let index = ((address - ADDRESS_COUNTDOWN) % 4) as usize;
+                if index == 0 {
+                    channel.latched_countdown = channel.countdown(now);
+                }
+                channel.latched_countdown.to_be_bytes()[index]
+            }
+            ADDRESS_RELOAD..ADDRESS_CONTROL => {
+                let channel = &sd.channels[((address - ADDRESS_RELOAD) / 4) as usize];
+                let index = ((address - ADDRESS_RELOAD) % 4) as usize;
+                channel.reload.to_be_bytes()[index]
+            }
+            ADDRESS_CONTROL..ADDRESS_END => {
+                sd.channels[(address - ADDRESS_CONTROL) as usize].control
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Handle a memory-mapped register write. Writing a control register
+    /// restarts that channel's countdown from its reload value.
+    pub fn store(&mut self, address: u32, value: u8) {
+        let mut sd = self.shared_data.lock().unwrap();
+        match address {
+            ADDRESS_RELOAD..ADDRESS_CONTROL => {
+                let channel = &mut sd.channels[((address - ADDRESS_RELOAD) / 4) as usize];
+                let index = ((address - ADDRESS_RELOAD) % 4) as usize;
+                let mut bytes = channel.reload.to_be_bytes();
+                bytes[index] = value;
+                channel.reload = u32::from_be_bytes(bytes);
+            }
+            ADDRESS_CONTROL..ADDRESS_END => {
+                let channel = &mut sd.channels[(address - ADDRESS_CONTROL) as usize];
+                channel.control = value & (CONTROL_ENABLE | CONTROL_PERIODIC);
+                channel.restart(Instant::now());
+                drop(sd);
+                // Wake the timer thread so it picks up the new deadline.
+                if let Some(command_tx) = self.command_tx.as_ref() {
+                    command_tx.send(TimerMessage::Reconfigure).unwrap();
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ntest::timeout;
+    use std::sync::mpsc::{self, Receiver};
+
+    use crate::init_test_logging;
+
+    const CHANNEL_0_COUNTDOWN: u32 = ADDRESS_COUNTDOWN;
+    const CHANNEL_1_RELOAD: u32 = ADDRESS_RELOAD + 4;
+    const CHANNEL_1_COUNTDOWN: u32 = ADDRESS_COUNTDOWN + 4;
+    const CHANNEL_1_CONTROL: u32 = ADDRESS_CONTROL + 1;
+
+    fn setup() -> (IntervalTimerController, Receiver<u32>) {
+        init_test_logging();
+        let (interrupt_tx, interrupt_rx) = mpsc::channel();
+        let mut timer = IntervalTimerController::new(interrupt_tx);
+        timer.start();
+        (timer, interrupt_rx)
+    }
+
+    fn load_32(timer: &IntervalTimerController, address: u32) -> u32 {
+        u32::from_be_bytes([0, 1, 2, 3].map(|i| timer.load(address + i)))
+    }
+
+    fn store_32(timer: &mut IntervalTimerController, address: u32, value: u32) {
+        for (i, byte) in value.to_be_bytes().into_iter().enumerate() {
+            timer.store(address + i as u32, byte);
+        }
+    }
+
+    #[test]
+    #[timeout(1000)]
+    fn test_one_shot() {
+        let (mut timer, interrupt_rx) = setup();
+
+        store_32(&mut timer, CHANNEL_1_RELOAD, 50_000);
+        assert_eq!(load_32(&timer, CHANNEL_1_RELOAD), 50_000);
+        // Nothing counts down until enabled.
+        assert_eq!(load_32(&timer, CHANNEL_1_COUNTDOWN), 0);
+
+        timer.store(CHANNEL_1_CONTROL, CONTROL_ENABLE);
+        let countdown = load_32(&timer, CHANNEL_1_COUNTDOWN);
+        assert!(countdown > 0 && countdown <= 50_000);
+        assert_eq!(load_32(&timer, CHANNEL_0_COUNTDOWN), 0);
+
+        // The channel should expire exactly once, then disable itself.
+        assert_eq!(interrupt_rx.recv(), Ok(INTERRUPT_INTERVAL_TIMER));
+        assert_eq!(timer.load(ADDRESS_STATUS), 0b0010);
+        assert_eq!(timer.load(ADDRESS_STATUS), 0);
+        assert_eq!(timer.load(CHANNEL_1_CONTROL), 0);
+        assert_eq!(load_32(&timer, CHANNEL_1_COUNTDOWN), 0);
+        assert_eq!(
+            interrupt_rx.recv_timeout(Duration::from_millis(100)),
+            Err(RecvTimeoutError::Timeout)
+        );
+
+        timer.stop();
+    }
+
+    #[test]
+    #[timeout(1000)]
+    fn test_periodic() {
+        let (mut timer, interrupt_rx) = setup();
+
+        store_32(&mut timer, CHANNEL_1_RELOAD, 10_000);
+        timer.store(CHANNEL_1_CONTROL, CONTROL_ENABLE | CONTROL_PERIODIC);
+        let start = Instant::now();
+        for _ in 0..5 {
+            assert_eq!(interrupt_rx.recv(), Ok(INTERRUPT_INTERVAL_TIMER));
+            assert_eq!(timer.load(ADDRESS_STATUS), 0b0010);
+        }
+        assert!(start.elapsed() >= Duration::from_millis(50));
+        assert_eq!(
+            timer.load(CHANNEL_1_CONTROL),
+            CONTROL_ENABLE | CONTROL_PERIODIC
+        );
+
+        // Disabling the channel should stop it.
+        timer.store(CHANNEL_1_CONTROL, 0);
+        let _ = timer.load(ADDRESS_STATUS);
+        while interrupt_rx.try_recv().is_ok() {}
+        assert_eq!(
+            interrupt_rx.recv_timeout(Duration::from_millis(50)),
+            Err(RecvTimeoutError::Timeout)
+        );
+        assert_eq!(timer.load(ADDRESS_STATUS), 0);
+
+        timer.stop();
+    }
+
+    #[test]
+    #[timeout(1000)]
+    fn test_counter() {
+        let (mut timer, _interrupt_rx) = setup();
+
+        let read_counter = |timer: &IntervalTimerController| {
+            u64::from_be_bytes([0, 1, 2, 3, 4, 5, 6, 7].map(|i| timer.load(ADDRESS_COUNTER + i)))
+        };
+        let first = read_counter(&timer);
+        thread::sleep(Duration::from_millis(10));
+        let second = read_counter(&timer);
+        assert!(second >= first + 10_000);
+
+        // The counter is latched when its first byte is read.
+        let _ = timer.load(ADDRESS_COUNTER);
+        thread::sleep(Duration::from_millis(1));
+        let latched = timer.load(ADDRESS_COUNTER + 7);
+        thread::sleep(Duration::from_millis(1));
+        assert_eq!(timer.load(ADDRESS_COUNTER + 7), latched);
+
+        timer.stop();
+    }
+}
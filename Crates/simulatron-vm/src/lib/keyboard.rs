@@ -1,4 +1,5 @@
-use log::info;
+use log::{info, warn};
+use std::collections::VecDeque;
 use std::sync::{
     mpsc::{Receiver, Sender},
     Arc, Mutex,
@@ -7,9 +8,23 @@ use std::thread;
 
 use crate::cpu::INTERRUPT_KEYBOARD;
 
+// Modifier bits in the metadata buffer.
+pub const MODIFIER_CTRL: u8 = 0b000001;
+pub const MODIFIER_ALT: u8 = 0b000010;
+pub const MODIFIER_SHIFT: u8 = 0b000100;
+pub const MODIFIER_SUPER: u8 = 0b001000;
+pub const MODIFIER_HYPER: u8 = 0b010000;
+pub const MODIFIER_META: u8 = 0b100000;
+
+// The maximum number of keys that can be waiting to be read.
+const FIFO_CAPACITY: usize = 16;
+
+// Status register bits.
+const STATUS_OVERFLOW: u8 = 0b10000000;
+
 /// Commands that can be sent to the keyboard controller.
 enum InternalKeyMessage {
-    Key { key: u8, ctrl: bool, alt: bool },
+    Key { key: u8, modifiers: u8 },
     JoinThread, // This is not exposed by KeyMessage; only this module can use it.
 }
 
@@ -18,8 +33,8 @@ pub struct KeyMessage(InternalKeyMessage);
 
 #[allow(non_snake_case)] // We're breaking method naming conventions to simulate the enum names.
 impl KeyMessage {
-    pub fn Key(key: u8, ctrl: bool, alt: bool) -> Self {
-        KeyMessage(InternalKeyMessage::Key { key, ctrl, alt })
+    pub fn Key(key: u8, modifiers: u8) -> Self {
+        KeyMessage(InternalKeyMessage::Key { key, modifiers })
     }
 
     fn JoinThread() -> Self {
@@ -33,8 +48,9 @@ impl KeyMessage {
 
 /// Data shared between the controller and CPU threads.
 struct SharedData {
-    key_buffer: u8,
+    fifo: VecDeque<(u8, u8)>,
     metadata_buffer: u8,
+    overflowed: bool,
 }
 
 /// A keyboard controller.
@@ -60,8 +76,9 @@ impl KeyboardController {
             interrupt_tx: Some(interrupt_tx),
             thread_handle: None,
             shared_data: Arc::new(Mutex::new(SharedData {
-                key_buffer: 0,
+                fifo: VecDeque::with_capacity(FIFO_CAPACITY),
                 metadata_buffer: 0,
+                overflowed: false,
             })),
         }
     }
@@ -82,13 +99,16 @@ impl KeyboardController {
             // Receive the next key.
             let key_message = keyboard_rx.recv().expect("Failed to receive key from UI.");
             match *key_message.internal() {
-                InternalKeyMessage::Key { key, ctrl, alt } => {
-                    // Record it in the buffer and send an interrupt.
+                InternalKeyMessage::Key { key, modifiers } => {
+                    // Queue it and send an interrupt, unless the queue is full.
                     let mut sd = shared_data.lock().unwrap();
-                    sd.key_buffer = key;
-                    sd.metadata_buffer =
-                        (if ctrl { 0b1 } else { 0 }) | (if alt { 0b10 } else { 0 });
-                    interrupt_channel.send(INTERRUPT_KEYBOARD).unwrap();
+                    if sd.fifo.len() < FIFO_CAPACITY {
+                        sd.fifo.push_back((key, modifiers));
+                        interrupt_channel.send(INTERRUPT_KEYBOARD).unwrap();
+                    } else {
+                        warn!("Keyboard buffer full; dropping key {}.", key);
+                        sd.overflowed = true;
+                    }
                 }
                 InternalKeyMessage::JoinThread => {
                     return (keyboard_rx, interrupt_channel);
@@ -117,12 +137,87 @@ impl KeyboardController {
         info!("Keyboard Controller stopping.");
     }
 
-    /// Handle a memory-mapped status request.
+    /// Handle a memory-mapped status request. Reading the key buffer pops
+    /// the oldest key, and makes its modifiers available in the metadata
+    /// buffer. Reading the status clears the overflow flag. These side effects
+    /// happen on every read, even if the instruction performing it then fails
+    /// and is retried.
     pub fn load(&self, address: u32) -> u8 {
+        let mut sd = self.shared_data.lock().unwrap();
         match address {
-            0 => self.shared_data.lock().unwrap().key_buffer,
-            1 => self.shared_data.lock().unwrap().metadata_buffer,
+            0 => match sd.fifo.pop_front() {
+                Some((key, modifiers)) => {
+                    sd.metadata_buffer = modifiers;
+                    key
+                }
+                None => {
+                    sd.metadata_buffer = 0;
+                    0
+                }
+            },
+            1 => sd.metadata_buffer,
+            2 => {
+                let status = sd.fifo.len() as u8 | if sd.overflowed { STATUS_OVERFLOW } else { 0 };
+                sd.overflowed = false;
+                status
+            }
             _ => unreachable!(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ntest::timeout;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    use crate::init_test_logging;
+
+    #[test]
+    #[timeout(1000)]
+    fn test_fifo() {
+        init_test_logging();
+
+        let (interrupt_tx, interrupt_rx) = mpsc::channel();
+        let (keyboard_tx, keyboard_rx) = mpsc::channel();
+        let keyboard_tx_manual = keyboard_tx.clone();
+        let mut keyboard = KeyboardController::new(keyboard_tx, keyboard_rx, interrupt_tx);
+        keyboard.start();
+
+        // Fill the buffer, waiting for an interrupt for each key.
+        for i in 0..FIFO_CAPACITY as u8 {
+            keyboard_tx_manual
+                .send(KeyMessage::Key(b'a' + i, i % 4))
+                .unwrap();
+            assert_eq!(interrupt_rx.recv(), Ok(INTERRUPT_KEYBOARD));
+        }
+        assert_eq!(keyboard.load(2), FIFO_CAPACITY as u8);
+
+        // Another key should be dropped without an interrupt.
+        keyboard_tx_manual
+            .send(KeyMessage::Key(b'!', MODIFIER_SHIFT))
+            .unwrap();
+        interrupt_rx
+            .recv_timeout(Duration::from_millis(50))
+            .unwrap_err();
+        assert_eq!(keyboard.load(2), FIFO_CAPACITY as u8 | STATUS_OVERFLOW);
+        // Reading the status should have cleared the overflow flag.
+        assert_eq!(keyboard.load(2), FIFO_CAPACITY as u8);
+
+        // Keys should come out in order, along with their modifiers.
+        for i in 0..FIFO_CAPACITY as u8 {
+            assert_eq!(keyboard.load(0), b'a' + i);
+            assert_eq!(keyboard.load(1), i % 4);
+            assert_eq!(keyboard.load(2), FIFO_CAPACITY as u8 - i - 1);
+        }
+
+        // Reading from an empty buffer should give nothing.
+        assert_eq!(keyboard.load(0), 0);
+        assert_eq!(keyboard.load(1), 0);
+
+        keyboard.stop();
+    }
+}
@@ -1,21 +1,37 @@
 mod cpu;
 mod disk;
 mod display;
+mod interval_timer;
 mod keyboard;
 mod mmu;
+mod mouse;
 mod ram;
+mod sound;
 mod ui;
 
+use std::fs::File;
 use std::sync::mpsc;
 
+pub use crate::cpu::MAX_CORES;
 use crate::disk::RealDiskController;
 pub use crate::mmu::ROM_SIZE;
 
-/// Run the simulatron with the given ROM and disk directories.
-pub fn run(rom: [u8; ROM_SIZE], disk_a_path: &str, disk_b_path: &str) {
+/// Run the simulatron with the given ROM, disk directories, and number of
+/// CPU cores. If a sound file is given, sound output will be written to it in
+/// WAV format. If any core halted due to a fatal error, the reasons are
+/// returned.
+pub fn run(
+    rom: [u8; ROM_SIZE],
+    disk_a_path: &str,
+    disk_b_path: &str,
+    sound_file: Option<File>,
+    num_cores: usize,
+) -> Result<(), String> {
     // Create communication channels.
     let (interrupt_tx, interrupt_rx) = mpsc::channel();
     let interrupt_tx_keyboard = interrupt_tx.clone();
+    let interrupt_tx_mouse = interrupt_tx.clone();
+    let interrupt_tx_timer = interrupt_tx.clone();
     let interrupt_tx_mmu = interrupt_tx.clone();
     let interrupt_tx_disk_a = interrupt_tx.clone();
     let interrupt_tx_disk_b = interrupt_tx.clone();
@@ -24,21 +40,50 @@ pub fn run(rom: [u8; ROM_SIZE], disk_a_path: &str, disk_b_path: &str) {
     let ui_tx_cpu = ui_tx.clone();
     let (keyboard_tx, keyboard_rx) = mpsc::channel();
     let keyboard_tx_ui = keyboard_tx.clone();
+    let (mouse_tx, mouse_rx) = mpsc::channel();
+    let mouse_tx_ui = mouse_tx.clone();
 
     // Create components.
     let disk_a = RealDiskController::new(disk_a_path, interrupt_tx_disk_a, cpu::INTERRUPT_DISK_A);
     let disk_b = RealDiskController::new(disk_b_path, interrupt_tx_disk_b, cpu::INTERRUPT_DISK_B);
     let display = display::DisplayController::new(ui_tx_display);
+    let display_memory = display.memory();
     let keyboard =
         keyboard::KeyboardController::new(keyboard_tx, keyboard_rx, interrupt_tx_keyboard);
-    let mmu = mmu::MMU::new(interrupt_tx_mmu, disk_a, disk_b, display, keyboard, rom);
-    let mut cpu = cpu::CPU::new(ui_tx_cpu, mmu, interrupt_tx, interrupt_rx);
-    let mut ui = ui::UI::new(ui_tx, ui_rx, keyboard_tx_ui);
+    let mouse = mouse::MouseController::new(mouse_tx, mouse_rx, interrupt_tx_mouse);
+    let sound = sound::SoundController::new(sound_file);
+    let timer = interval_timer::IntervalTimerController::new(interrupt_tx_timer);
+    let mmu = mmu::MMU::new(
+        interrupt_tx_mmu,
+        disk_a,
+        disk_b,
+        display,
+        keyboard,
+        mouse,
+        sound,
+        timer,
+        rom,
+    );
+    let mut cpu = cpu::CPU::new(ui_tx_cpu, mmu, interrupt_tx, interrupt_rx, num_cores);
+    let mut ui = ui::UI::new(ui_tx, ui_rx, keyboard_tx_ui, mouse_tx_ui, display_memory);
 
     // Run the Simulatron.
     cpu.start();
     ui.run().unwrap();
     cpu.stop();
+
+    // Report any cores that halted due to a fatal error.
+    let errors: Vec<String> = (0..num_cores)
+        .filter_map(|core| {
+            cpu.fatal_error(core)
+                .map(|reason| format!("CPU core {} halted due to a fatal error: {}.", core, reason))
+        })
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("\n"))
+    }
 }
 
 /// Initialise logging for tests.
@@ -5,8 +5,11 @@ use crate::cpu::{
 };
 use crate::disk::DiskController;
 use crate::display::DisplayController;
+use crate::interval_timer::IntervalTimerController;
 use crate::keyboard::KeyboardController;
+use crate::mouse::MouseController;
 use crate::ram::RAM;
+use crate::sound::SoundController;
 
 // Page fault types.
 pub const PAGE_FAULT_INVALID_PAGE: u32 = 0;
@@ -16,11 +19,19 @@ pub const PAGE_FAULT_COW: u32 = 3;
 
 // Memory-mapped zones.
 const BEGIN_INTERRUPT_VECTOR: u32 = 0x0000; // Read/Write
-const BEGIN_RESERVED_1: u32 = 0x0020; // No access
 const BEGIN_ROM: u32 = 0x0040; // Read-only
-const BEGIN_DISPLAY: u32 = 0x0240; // Write-only
+const BEGIN_DISPLAY: u32 = 0x0240; // Read/Write
 const BEGIN_KEYBOARD: u32 = 0x19B0; // Read-only
-const BEGIN_RESERVED_2: u32 = 0x19B2; // No access
+const BEGIN_MOUSE: u32 = 0x19B3; // Read-only
+const BEGIN_RESERVED_2: u32 = 0x19B7; // No access
+const BEGIN_DISPLAY_CONTROL: u32 = 0x19C0; // Read/Write
+const BEGIN_RESERVED_3: u32 = 0x19C8; // No access
+const BEGIN_SOUND: u32 = 0x19D0; // Read/Write
+const BEGIN_SOUND_COMMAND: u32 = 0x19D5; // Write-only
+const BEGIN_RESERVED_4: u32 = 0x19D6; // No access
+const BEGIN_TIMER: u32 = 0x19DF; // Read-only
+const BEGIN_TIMER_RELOAD: u32 = 0x19F8; // Read/Write
+const BEGIN_RESERVED_5: u32 = 0x1A0C; // No access
 const BEGIN_DISK_A_STATUS: u32 = 0x1FEC; // Read-only
 const BEGIN_DISK_A_ADDRESS: u32 = 0x1FF1; // Read/Write
 const BEGIN_DISK_A_COMMAND: u32 = 0x1FF5; // Write-only
@@ -31,7 +42,7 @@ const BEGIN_DISK_A_DATA: u32 = 0x2000; // Read/Write
 const BEGIN_DISK_B_DATA: u32 = 0x3000; // Read/Write
 const BEGIN_RAM: u32 = 0x4000; // Read/Write
 
-const INTERRUPT_VECTOR_SIZE: usize = (BEGIN_RESERVED_1 - BEGIN_INTERRUPT_VECTOR) as usize;
+const INTERRUPT_VECTOR_SIZE: usize = (BEGIN_ROM - BEGIN_INTERRUPT_VECTOR) as usize;
 type InterruptVector = [u8; INTERRUPT_VECTOR_SIZE];
 
 pub const RAM_SIZE: usize = (u32::MAX - BEGIN_RAM + 1) as usize;
@@ -51,37 +62,49 @@ enum Intent {
 /// A memory management unit.
 #[allow(clippy::upper_case_acronyms)]
 pub struct MMU<D> {
-    interrupt_tx: Sender<u32>,
+    interrupt_tx: Vec<Sender<u32>>, // One per CPU core.
+    core: usize,                    // The core currently accessing memory.
     interrupt_vector: InterruptVector,
     disk_a: D,
     disk_b: D,
     display: DisplayController,
     keyboard: KeyboardController,
+    mouse: MouseController,
+    sound: SoundController,
+    timer: IntervalTimerController,
     ram: RAM,
     rom: ROM,
-    pfsr: u32, // Page Fault Status Register
+    pfsr: Vec<u32>, // Page Fault Status Register for each core
 }
 
 impl<D: DiskController> MMU<D> {
     /// Construct a new MMU.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         interrupt_tx: Sender<u32>,
         disk_a: D,
         disk_b: D,
         display: DisplayController,
         keyboard: KeyboardController,
+        mouse: MouseController,
+        sound: SoundController,
+        timer: IntervalTimerController,
         rom: ROM,
     ) -> Self {
         MMU {
-            interrupt_tx,
+            interrupt_tx: vec![interrupt_tx],
+            core: 0,
             interrupt_vector: [0; INTERRUPT_VECTOR_SIZE],
             disk_a,
             disk_b,
             display,
             keyboard,
+            mouse,
+            sound,
+            timer,
             ram: RAM::new(),
             rom,
-            pfsr: 0,
+            pfsr: vec![0],
         }
     }
 
@@ -90,6 +113,9 @@ impl<D: DiskController> MMU<D> {
         self.disk_a.start();
         self.disk_b.start();
         self.keyboard.start();
+        self.mouse.start();
+        self.sound.start();
+        self.timer.start();
     }
 
     /// Stop all the peripherals mapped by the MMU. Panics if not running.
@@ -97,11 +123,28 @@ impl<D: DiskController> MMU<D> {
         self.disk_a.stop();
         self.disk_b.stop();
         self.keyboard.stop();
+        self.mouse.stop();
+        self.sound.stop();
+        self.timer.stop();
     }
 
-    /// Read the page fault status register.
+    /// Add another CPU core with the given interrupt channel, returning its ID.
+    /// Core 0 uses the interrupt channel passed to the constructor.
+    pub fn add_core(&mut self, interrupt_tx: Sender<u32>) -> usize {
+        self.interrupt_tx.push(interrupt_tx);
+        self.pfsr.push(0);
+        self.interrupt_tx.len() - 1
+    }
+
+    /// Select the core that subsequent accesses are made on behalf of. Any
+    /// faults they cause are reported to this core.
+    pub fn select_core(&mut self, core: usize) {
+        self.core = core;
+    }
+
+    /// Read the page fault status register of the selected core.
     pub fn page_fault_status_register(&self) -> u32 {
-        self.pfsr
+        self.pfsr[self.core]
     }
 
     pub fn store_virtual_8(&mut self, pdpr: u32, address: u32, value: u8) -> CPUResult<()> {
@@ -123,6 +166,11 @@ impl<D: DiskController> MMU<D> {
         self.store_virtual_8(pdpr, address + 3, lower)
     }
 
+    pub fn store_virtual_64(&mut self, pdpr: u32, address: u32, value: u64) -> CPUResult<()> {
+        self.store_virtual_32(pdpr, address, (value >> 32) as u32)?;
+        self.store_virtual_32(pdpr, address + 4, value as u32)
+    }
+
     pub fn load_virtual_8(&mut self, pdpr: u32, address: u32, is_fetch: bool) -> CPUResult<u8> {
         let intent = if is_fetch {
             Intent::Execute
@@ -147,27 +195,57 @@ impl<D: DiskController> MMU<D> {
         Ok(u32::from_be_bytes([upper, upper_mid, lower_mid, lower]))
     }
 
+    pub fn load_virtual_64(&mut self, pdpr: u32, address: u32, is_fetch: bool) -> CPUResult<u64> {
+        let upper = self.load_virtual_32(pdpr, address, is_fetch)?;
+        let lower = self.load_virtual_32(pdpr, address + 4, is_fetch)?;
+        Ok((u64::from(upper) << 32) | u64::from(lower))
+    }
+
     pub fn store_physical_8(&mut self, address: u32, value: u8) -> CPUResult<()> {
         macro_rules! reject {
             () => {{
-                self.interrupt_tx.send(INTERRUPT_ILLEGAL_OPERATION).unwrap();
+                self.interrupt_tx[self.core]
+                    .send(INTERRUPT_ILLEGAL_OPERATION)
+                    .unwrap();
                 Err(TryAgainError)
             }};
         }
 
-        if address < BEGIN_RESERVED_1 {
+        if address < BEGIN_ROM {
             // Interrupt handlers
             self.interrupt_vector[address as usize] = value;
             Ok(())
         } else if address < BEGIN_DISPLAY {
-            // Reserved, ROM
+            // ROM
             reject!()
         } else if address < BEGIN_KEYBOARD {
             // Memory-mapped display
             self.display.store(address - BEGIN_DISPLAY, value);
             Ok(())
+        } else if address < BEGIN_DISPLAY_CONTROL {
+            // Keyboard, Mouse, Reserved
+            reject!()
+        } else if address < BEGIN_RESERVED_3 {
+            // Display control
+            self.display
+                .store_control(address - BEGIN_DISPLAY_CONTROL, value);
+            Ok(())
+        } else if address < BEGIN_SOUND {
+            // Reserved
+            reject!()
+        } else if address < BEGIN_RESERVED_4 {
+            // Sound registers
+            self.sound.store(address - BEGIN_SOUND, value);
+            Ok(())
+        } else if address < BEGIN_TIMER_RELOAD {
+            // Reserved, Timer read-only
+            reject!()
+        } else if address < BEGIN_RESERVED_5 {
+            // Timer control
+            self.timer.store(address - BEGIN_TIMER, value);
+            Ok(())
         } else if address < BEGIN_DISK_A_ADDRESS {
-            // Keyboard, Reserved, Disk A read-only
+            // Reserved, Disk A read-only
             reject!()
         } else if address < BEGIN_DISK_B_STATUS {
             // Disk A control
@@ -211,29 +289,54 @@ impl<D: DiskController> MMU<D> {
         self.store_physical_8(address + 3, lower)
     }
 
+    pub fn store_physical_64(&mut self, address: u32, value: u64) -> CPUResult<()> {
+        self.store_physical_32(address, (value >> 32) as u32)?;
+        self.store_physical_32(address + 4, value as u32)
+    }
+
     pub fn load_physical_8(&self, address: u32) -> CPUResult<u8> {
         macro_rules! reject {
             () => {{
-                self.interrupt_tx.send(INTERRUPT_ILLEGAL_OPERATION).unwrap();
+                self.interrupt_tx[self.core]
+                    .send(INTERRUPT_ILLEGAL_OPERATION)
+                    .unwrap();
                 Err(TryAgainError)
             }};
         }
 
-        if address < BEGIN_RESERVED_1 {
+        if address < BEGIN_ROM {
             // Interrupt handlers
             Ok(self.interrupt_vector[address as usize])
-        } else if address < BEGIN_ROM {
-            // Reserved
-            reject!()
         } else if address < BEGIN_DISPLAY {
             // ROM
             Ok(self.rom[(address - BEGIN_ROM) as usize])
         } else if address < BEGIN_KEYBOARD {
             // Memory-mapped display
-            reject!()
-        } else if address < BEGIN_RESERVED_2 {
+            Ok(self.display.load(address - BEGIN_DISPLAY))
+        } else if address < BEGIN_MOUSE {
             // Keyboard buffers
             Ok(self.keyboard.load(address - BEGIN_KEYBOARD))
+        } else if address < BEGIN_RESERVED_2 {
+            // Mouse registers
+            Ok(self.mouse.load(address - BEGIN_MOUSE))
+        } else if address < BEGIN_DISPLAY_CONTROL {
+            // Reserved
+            reject!()
+        } else if address < BEGIN_RESERVED_3 {
+            // Display control
+            Ok(self.display.load_control(address - BEGIN_DISPLAY_CONTROL))
+        } else if address < BEGIN_SOUND {
+            // Reserved
+            reject!()
+        } else if address < BEGIN_SOUND_COMMAND {
+            // Sound registers
+            Ok(self.sound.load(address - BEGIN_SOUND))
+        } else if address < BEGIN_TIMER {
+            // Sound command, Reserved
+            reject!()
+        } else if address < BEGIN_RESERVED_5 {
+            // Timer registers
+            Ok(self.timer.load(address - BEGIN_TIMER))
         } else if address < BEGIN_DISK_A_STATUS {
             // Reserved
             reject!()
@@ -275,6 +378,12 @@ impl<D: DiskController> MMU<D> {
         Ok(u32::from_be_bytes([upper, upper_mid, lower_mid, lower]))
     }
 
+    pub fn load_physical_64(&self, address: u32) -> CPUResult<u64> {
+        let upper = self.load_physical_32(address)?;
+        let lower = self.load_physical_32(address + 4)?;
+        Ok((u64::from(upper) << 32) | u64::from(lower))
+    }
+
     fn virtual_to_physical_address(
         &mut self,
         virtual_address: u32,
@@ -286,8 +395,10 @@ impl<D: DiskController> MMU<D> {
         let directory_entry = self.load_physical_32(directory_entry_address)?;
         // Check it's valid.
         if (directory_entry & 1) == 0 {
-            self.pfsr = PAGE_FAULT_INVALID_PAGE;
-            self.interrupt_tx.send(INTERRUPT_PAGE_FAULT).unwrap();
+            self.pfsr[self.core] = PAGE_FAULT_INVALID_PAGE;
+            self.interrupt_tx[self.core]
+                .send(INTERRUPT_PAGE_FAULT)
+                .unwrap();
             return Err(TryAgainError);
         }
         // Find the page table entry.
@@ -296,14 +407,18 @@ impl<D: DiskController> MMU<D> {
         let page_table_entry = self.load_physical_32(page_table_base + page_table_offset)?;
         // Check it's valid.
         if (page_table_entry & 1) == 0 {
-            self.pfsr = PAGE_FAULT_INVALID_PAGE;
-            self.interrupt_tx.send(INTERRUPT_PAGE_FAULT).unwrap();
+            self.pfsr[self.core] = PAGE_FAULT_INVALID_PAGE;
+            self.interrupt_tx[self.core]
+                .send(INTERRUPT_PAGE_FAULT)
+                .unwrap();
             return Err(TryAgainError);
         }
         // Check it's present.
         if (page_table_entry & 2) == 0 {
-            self.pfsr = PAGE_FAULT_NOT_PRESENT;
-            self.interrupt_tx.send(INTERRUPT_PAGE_FAULT).unwrap();
+            self.pfsr[self.core] = PAGE_FAULT_NOT_PRESENT;
+            self.interrupt_tx[self.core]
+                .send(INTERRUPT_PAGE_FAULT)
+                .unwrap();
             return Err(TryAgainError);
         }
         // Check permissions.
@@ -313,15 +428,19 @@ impl<D: DiskController> MMU<D> {
             Intent::Execute => page_table_entry & 16,
         };
         if legal == 0 {
-            self.pfsr = PAGE_FAULT_ILLEGAL_ACCESS;
-            self.interrupt_tx.send(INTERRUPT_PAGE_FAULT).unwrap();
+            self.pfsr[self.core] = PAGE_FAULT_ILLEGAL_ACCESS;
+            self.interrupt_tx[self.core]
+                .send(INTERRUPT_PAGE_FAULT)
+                .unwrap();
             return Err(TryAgainError);
         }
         // Check COW.
         if let Intent::Write = intent {
             if (page_table_entry & 32) != 0 {
-                self.pfsr = PAGE_FAULT_COW;
-                self.interrupt_tx.send(INTERRUPT_PAGE_FAULT).unwrap();
+                self.pfsr[self.core] = PAGE_FAULT_COW;
+                self.interrupt_tx[self.core]
+                    .send(INTERRUPT_PAGE_FAULT)
+                    .unwrap();
                 return Err(TryAgainError);
             }
         }
@@ -360,10 +479,24 @@ mod tests {
             let display = DisplayController::new(display_tx);
             let (keyboard_tx, keyboard_rx) = mpsc::channel();
             let keyboard = KeyboardController::new(keyboard_tx, keyboard_rx, interrupt_tx.clone());
+            let (mouse_tx, mouse_rx) = mpsc::channel();
+            let mouse = MouseController::new(mouse_tx, mouse_rx, interrupt_tx.clone());
+            let sound = SoundController::new(None);
+            let timer = IntervalTimerController::new(interrupt_tx.clone());
             let rom = [0; ROM_SIZE];
 
             MMUFixture {
-                mmu: MMU::new(interrupt_tx, disk_a, disk_b, display, keyboard, rom),
+                mmu: MMU::new(
+                    interrupt_tx,
+                    disk_a,
+                    disk_b,
+                    display,
+                    keyboard,
+                    mouse,
+                    sound,
+                    timer,
+                    rom,
+                ),
                 interrupt_rx,
             }
         }
@@ -0,0 +1,221 @@
+use log::info;
+use std::sync::{
+    mpsc::{Receiver, Sender},
+    Arc, Mutex,
+};
+use std::thread;
+
+use crate::cpu::INTERRUPT_MOUSE;
+
+// Button bits in the button register.
+pub const MOUSE_BUTTON_LEFT: u8 = 0b001;
+pub const MOUSE_BUTTON_RIGHT: u8 = 0b010;
+pub const MOUSE_BUTTON_MIDDLE: u8 = 0b100;
+
+/// Commands that can be sent to the mouse controller.
+enum InternalMouseMessage {
+    Press { row: u8, col: u8, button: u8 },
+    Release { row: u8, col: u8, button: u8 },
+    Move { row: u8, col: u8 },
+    Scroll { row: u8, col: u8, delta: i8 },
+    JoinThread, // This is not exposed by MouseMessage; only this module can use it.
+}
+
+/// A public wrapper that doesn't allow join thread messages.
+pub struct MouseMessage(InternalMouseMessage);
+
+#[allow(non_snake_case)] // We're breaking method naming conventions to simulate the enum names.
+impl MouseMessage {
+    pub fn Press(row: u8, col: u8, button: u8) -> Self {
+        MouseMessage(InternalMouseMessage::Press { row, col, button })
+    }
+
+    pub fn Release(row: u8, col: u8, button: u8) -> Self {
+        MouseMessage(InternalMouseMessage::Release { row, col, button })
+    }
+
+    pub fn Move(row: u8, col: u8) -> Self {
+        MouseMessage(InternalMouseMessage::Move { row, col })
+    }
+
+    pub fn Scroll(row: u8, col: u8, delta: i8) -> Self {
+        MouseMessage(InternalMouseMessage::Scroll { row, col, delta })
+    }
+
+    fn JoinThread() -> Self {
+        MouseMessage(InternalMouseMessage::JoinThread)
+    }
+
+    fn internal(&self) -> &InternalMouseMessage {
+        &self.0
+    }
+}
+
+/// Data shared between the controller and CPU threads.
+struct SharedData {
+    row: u8,
+    col: u8,
+    buttons: u8,
+    scroll: i8,
+}
+
+/// A mouse controller.
+pub struct MouseController {
+    mouse_tx: Sender<MouseMessage>,
+    mouse_rx: Option<Receiver<MouseMessage>>,
+    interrupt_tx: Option<Sender<u32>>,
+    thread_handle: Option<thread::JoinHandle<(Receiver<MouseMessage>, Sender<u32>)>>,
+    shared_data: Arc<Mutex<SharedData>>,
+}
+
+impl MouseController {
+    /// Construct a new mouse controller with the given interrupt channel
+    /// and mouse event channel.
+    pub fn new(
+        mouse_tx: Sender<MouseMessage>,
+        mouse_rx: Receiver<MouseMessage>,
+        interrupt_tx: Sender<u32>,
+    ) -> Self {
+        MouseController {
+            mouse_tx,
+            mouse_rx: Some(mouse_rx),
+            interrupt_tx: Some(interrupt_tx),
+            thread_handle: None,
+            shared_data: Arc::new(Mutex::new(SharedData {
+                row: 0,
+                col: 0,
+                buttons: 0,
+                scroll: 0,
+            })),
+        }
+    }
+
+    /// Start the mouse controller thread. Panics if already running.
+    pub fn start(&mut self) {
+        // Take temporary ownership of the channels.
+        let mouse_rx = self
+            .mouse_rx
+            .take()
+            .expect("MouseController was already running.");
+        let interrupt_channel = self.interrupt_tx.take().unwrap();
+        info!("Mouse controller starting.");
+
+        // Start the listener thread.
+        let shared_data = Arc::clone(&self.shared_data);
+        let thread_handle = thread::spawn(move || loop {
+            // Receive the next event.
+            let mouse_message = mouse_rx
+                .recv()
+                .expect("Failed to receive mouse event from UI.");
+            // Record it in the registers and send an interrupt.
+            let mut sd = shared_data.lock().unwrap();
+            let (row, col) = match *mouse_message.internal() {
+                InternalMouseMessage::Press { row, col, button } => {
+                    sd.buttons |= button;
+                    (row, col)
+                }
+                InternalMouseMessage::Release { row, col, button } => {
+                    sd.buttons &= !button;
+                    (row, col)
+                }
+                InternalMouseMessage::Move { row, col } => (row, col),
+                InternalMouseMessage::Scroll { row, col, delta } => {
+                    sd.scroll = sd.scroll.saturating_add(delta);
+                    (row, col)
+                }
+                InternalMouseMessage::JoinThread => {
+                    return (mouse_rx, interrupt_channel);
+                }
+            };
+            sd.row = row;
+            sd.col = col;
+            interrupt_channel.send(INTERRUPT_MOUSE).unwrap();
+        });
+        self.thread_handle = Some(thread_handle);
+    }
+
+    /// Stop the mouse controller thread. Panics if not running.
+    pub fn stop(&mut self) {
+        // Join the listener thread.
+        self.mouse_tx
+            .send(MouseMessage::JoinThread())
+            .expect("Failed to send JoinThread to mouse listener thread.");
+        let thread_handle = self
+            .thread_handle
+            .take()
+            .expect("MouseController was already stopped.");
+        let (mouse_rx, interrupt_channel) = thread_handle
+            .join()
+            .expect("Mouse listener thread terminated with error.");
+        // Re-acquire ownership of the channels.
+        self.mouse_rx = Some(mouse_rx);
+        self.interrupt_tx = Some(interrupt_channel);
+        info!("Mouse Controller stopping.");
+    }
+
+    /// Handle a memory-mapped status request. Reading the scroll register
+    /// resets it to zero.
+    pub fn load(&self, address: u32) -> u8 {
+        let mut sd = self.shared_data.lock().unwrap();
+        match address {
+            0 => sd.row,
+            1 => sd.col,
+            2 => sd.buttons,
+            3 => {
+                let scroll = sd.scroll;
+                sd.scroll = 0;
+                scroll as u8
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ntest::timeout;
+    use std::sync::mpsc;
+
+    use crate::init_test_logging;
+
+    #[test]
+    #[timeout(1000)]
+    fn test_mouse_events() {
+        init_test_logging();
+
+        let (interrupt_tx, interrupt_rx) = mpsc::channel();
+        let (mouse_tx, mouse_rx) = mpsc::channel();
+        let mouse_tx_manual = mouse_tx.clone();
+        let mut mouse = MouseController::new(mouse_tx, mouse_rx, interrupt_tx);
+        mouse.start();
+
+        // Each event should raise an interrupt and update the position.
+        let send = |message| {
+            mouse_tx_manual.send(message).unwrap();
+            assert_eq!(interrupt_rx.recv(), Ok(INTERRUPT_MOUSE));
+        };
+
+        send(MouseMessage::Press(3, 4, MOUSE_BUTTON_LEFT));
+        send(MouseMessage::Press(3, 4, MOUSE_BUTTON_RIGHT));
+        assert_eq!(mouse.load(0), 3);
+        assert_eq!(mouse.load(1), 4);
+        assert_eq!(mouse.load(2), MOUSE_BUTTON_LEFT | MOUSE_BUTTON_RIGHT);
+
+        send(MouseMessage::Move(24, 79));
+        send(MouseMessage::Release(24, 79, MOUSE_BUTTON_LEFT));
+        assert_eq!(mouse.load(0), 24);
+        assert_eq!(mouse.load(1), 79);
+        assert_eq!(mouse.load(2), MOUSE_BUTTON_RIGHT);
+
+        // Scrolling should accumulate until read.
+        send(MouseMessage::Scroll(0, 0, -1));
+        send(MouseMessage::Scroll(0, 0, -1));
+        send(MouseMessage::Scroll(0, 0, 1));
+        assert_eq!(mouse.load(3) as i8, -1);
+        assert_eq!(mouse.load(3), 0);
+
+        mouse.stop();
+    }
+}
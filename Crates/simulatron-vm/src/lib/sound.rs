@@ -0,0 +1,347 @@
+use log::{error, info, warn};
+use std::fs::File;
+use std::io::{self, Seek, SeekFrom, Write};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+// Register addresses.
+const ADDRESS_FREQUENCY_1: u32 = 0; // Frequency in Hz.
+const ADDRESS_FREQUENCY_2: u32 = 1;
+const ADDRESS_DURATION_1: u32 = 2; // Duration in milliseconds.
+const ADDRESS_DURATION_2: u32 = 3;
+const ADDRESS_VOLUME: u32 = 4; // Volume.
+const ADDRESS_CMD: u32 = 5; // Command.
+
+// Sound commands.
+const COMMAND_PLAY: u8 = 1;
+
+// WAV output format: 8-bit unsigned mono PCM.
+const SAMPLE_RATE: u32 = 22050;
+const SILENCE: u8 = 0x80;
+const HEADER_SIZE: u32 = 44;
+const RIFF_SIZE_OFFSET: u64 = 4;
+const DATA_SIZE_OFFSET: u64 = 40;
+
+/// Commands that can be sent to the writer thread.
+enum SoundMessage {
+    Play {
+        frequency: u16,
+        duration: u16,
+        volume: u8,
+    },
+    JoinThread,
+}
+
+/// The WAV file being written, if any, and the size of its data so far.
+struct SoundOutput {
+    file: Option<File>,
+    data_size: u32,
+}
+
+impl SoundOutput {
+    /// Render the given tone to the end of the file.
+    fn play(&mut self, frequency: u16, duration: u16, volume: u8) {
+        if self.file.is_none() {
+            return;
+        }
+        let samples = square_wave(frequency, duration, volume);
+        // The RIFF chunk size must also fit in 32 bits.
+        let new_size = match self
+            .data_size
+            .checked_add(samples.len() as u32)
+            .filter(|size| size.checked_add(HEADER_SIZE - 8).is_some())
+        {
+            Some(size) => size,
+            None => {
+                warn!("Sound output has reached the 4GiB WAV size limit, disabling.");
+                self.file = None;
+                return;
+            }
+        };
+        self.write_or_disable(|output| {
+            // Append the samples, then fix up the sizes in the header so the
+            // file is always valid.
+            output.seek(SeekFrom::End(0))?;
+            output.write_all(&samples)?;
+            output.seek(SeekFrom::Start(RIFF_SIZE_OFFSET))?;
+            output.write_all(&(HEADER_SIZE - 8 + new_size).to_le_bytes())?;
+            output.seek(SeekFrom::Start(DATA_SIZE_OFFSET))?;
+            output.write_all(&new_size.to_le_bytes())
+        });
+        if self.file.is_some() {
+            self.data_size = new_size;
+        }
+    }
+
+    /// Perform the given write on the file, if any. If it fails, log the
+    /// error and stop producing output.
+    fn write_or_disable<F>(&mut self, write: F)
+    where
+        F: FnOnce(&mut File) -> io::Result<()>,
+    {
+        if let Some(file) = self.file.as_mut() {
+            if let Err(e) = write(file) {
+                error!("Failed to write sound output, disabling: {}", e);
+                self.file = None;
+            }
+        }
+    }
+}
+
+/// A tone generator, which renders everything it plays to a WAV file.
+/// Without a file, tones are silently discarded. The file is written by a
+/// separate thread, so that playing a tone never blocks on disk I/O.
+pub struct SoundController {
+    output: Option<SoundOutput>,
+    command_tx: Option<Sender<SoundMessage>>,
+    thread_handle: Option<thread::JoinHandle<SoundOutput>>,
+    frequency: u16,
+    duration: u16,
+    volume: u8,
+}
+
+impl SoundController {
+    /// Construct a new sound controller that writes to the given file, if any.
+    pub fn new(file: Option<File>) -> Self {
+        let mut output = SoundOutput { file, data_size: 0 };
+        output.write_or_disable(write_header);
+        SoundController {
+            output: Some(output),
+            command_tx: None,
+            thread_handle: None,
+            frequency: 0,
+            duration: 0,
+            volume: 0,
+        }
+    }
+
+    /// Start the writer thread. Panics if already running.
+    pub fn start(&mut self) {
+        let mut output = self
+            .output
+            .take()
+            .expect("SoundController was already running.");
+        let (command_tx, command_rx) = mpsc::channel();
+        info!("Sound controller starting.");
+
+        let thread_handle = thread::spawn(move || loop {
+            match command_rx.recv() {
+                Ok(SoundMessage::Play {
+                    frequency,
+                    duration,
+                    volume,
+                }) => output.play(frequency, duration, volume),
+                // Also exit cleanly if the controller was dropped while running.
+                Ok(SoundMessage::JoinThread) | Err(_) => return output,
+            }
+        });
+        self.thread_handle = Some(thread_handle);
+        self.command_tx = Some(command_tx);
+    }
+
+    /// Stop the writer thread, after it has finished writing everything
+    /// played so far. Panics if not running.
+    pub fn stop(&mut self) {
+        let command_tx = self
+            .command_tx
+            .take()
+            .expect("SoundController was already stopped.");
+        command_tx.send(SoundMessage::JoinThread).unwrap();
+        let output = self
+            .thread_handle
+            .take()
+            .unwrap()
+            .join()
+            .expect("Sound writer thread terminated with error.");
+        self.output = Some(output);
+        info!("Sound controller stopping.");
+    }
+
+    /// Handle a memory-mapped register write.
+    pub fn store(&mut self, address: u32, value: u8) {
+        match address {
+            ADDRESS_FREQUENCY_1 => {
+                self.frequency = (self.frequency & 0x00FF) | (u16::from(value) << 8)
+            }
+            ADDRESS_FREQUENCY_2 => self.frequency = (self.frequency & 0xFF00) | u16::from(value),
+            ADDRESS_DURATION_1 => {
+                self.duration = (self.duration & 0x00FF) | (u16::from(value) << 8)
+            }
+            ADDRESS_DURATION_2 => self.duration = (self.duration & 0xFF00) | u16::from(value),
+            ADDRESS_VOLUME => self.volume = value,
+            ADDRESS_CMD => match value {
+                COMMAND_PLAY => self.play(),
+                _ => warn!("Ignoring unknown sound command {}.", value),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    /// Handle a memory-mapped register read.
+    pub fn load(&self, address: u32) -> u8 {
+        match address {
+            ADDRESS_FREQUENCY_1 => (self.frequency >> 8) as u8,
+            ADDRESS_FREQUENCY_2 => self.frequency as u8,
+            ADDRESS_DURATION_1 => (self.duration >> 8) as u8,
+            ADDRESS_DURATION_2 => self.duration as u8,
+            ADDRESS_VOLUME => self.volume,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Send the currently configured tone to the writer thread.
+    fn play(&mut self) {
+        info!(
+            "Playing {}Hz tone for {}ms at volume {}.",
+            self.frequency, self.duration, self.volume
+        );
+        let command_tx = self
+            .command_tx
+            .as_ref()
+            .expect("SoundController is not running.");
+        command_tx
+            .send(SoundMessage::Play {
+                frequency: self.frequency,
+                duration: self.duration,
+                volume: self.volume,
+            })
+            .unwrap();
+    }
+}
+
+/// Write a WAV header for an empty 8-bit mono PCM stream.
+fn write_header(output: &mut File) -> io::Result<()> {
+    output.write_all(b"RIFF")?;
+    output.write_all(&(HEADER_SIZE - 8).to_le_bytes())?;
+    output.write_all(b"WAVE")?;
+    output.write_all(b"fmt ")?;
+    output.write_all(&16u32.to_le_bytes())?; // Format chunk size.
+    output.write_all(&1u16.to_le_bytes())?; // PCM.
+    output.write_all(&1u16.to_le_bytes())?; // Mono.
+    output.write_all(&SAMPLE_RATE.to_le_bytes())?;
+    output.write_all(&SAMPLE_RATE.to_le_bytes())?; // Bytes per second.
+    output.write_all(&1u16.to_le_bytes())?; // Bytes per sample.
+    output.write_all(&8u16.to_le_bytes())?; // Bits per sample.
+    output.write_all(b"data")?;
+    output.write_all(&0u32.to_le_bytes())
+}
+
+/// Generate the samples for a square wave. A frequency of zero gives silence.
+fn square_wave(frequency: u16, duration: u16, volume: u8) -> Vec<u8> {
+    let num_samples = u32::from(duration) * SAMPLE_RATE / 1000;
+    if frequency == 0 {
+        return vec![SILENCE; num_samples as usize];
+    }
+    // Scale the volume to the maximum amplitude of an 8-bit sample.
+    let amplitude = (u32::from(volume) * 127 / 255) as u8;
+    (0..num_samples)
+        .map(|i| {
+            // Position within the current period, in half-periods.
+            let half_periods = u64::from(i) * u64::from(frequency) * 2 / u64::from(SAMPLE_RATE);
+            if half_periods % 2 == 0 {
+                SILENCE + amplitude
+            } else {
+                SILENCE - amplitude
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Read;
+
+    use crate::init_test_logging;
+
+    /// Play the given tone on a controller writing to a temporary file, and
+    /// return the file's contents.
+    fn render(tones: &[(u16, u16, u8)]) -> Vec<u8> {
+        init_test_logging();
+
+        let file = tempfile::tempfile().unwrap();
+        let mut sound = SoundController::new(Some(file.try_clone().unwrap()));
+        sound.start();
+        for &(frequency, duration, volume) in tones {
+            let [freq_1, freq_2] = frequency.to_be_bytes();
+            let [dur_1, dur_2] = duration.to_be_bytes();
+            sound.store(ADDRESS_FREQUENCY_1, freq_1);
+            sound.store(ADDRESS_FREQUENCY_2, freq_2);
+            sound.store(ADDRESS_DURATION_1, dur_1);
+            sound.store(ADDRESS_DURATION_2, dur_2);
+            sound.store(ADDRESS_VOLUME, volume);
+            sound.store(ADDRESS_CMD, COMMAND_PLAY);
+        }
+        sound.stop();
+
+        let mut contents = Vec::new();
+        let mut file = file;
+        file.seek(SeekFrom::Start(0)).unwrap();
+        file.read_to_end(&mut contents).unwrap();
+        contents
+    }
+
+    #[test]
+    fn test_registers() {
+        init_test_logging();
+
+        let mut sound = SoundController::new(None);
+        sound.start();
+        sound.store(ADDRESS_FREQUENCY_1, 0x01);
+        sound.store(ADDRESS_FREQUENCY_2, 0xB8);
+        sound.store(ADDRESS_DURATION_1, 0x03);
+        sound.store(ADDRESS_DURATION_2, 0xE8);
+        sound.store(ADDRESS_VOLUME, 0xFF);
+        // Playing without an output should do nothing.
+        sound.store(ADDRESS_CMD, COMMAND_PLAY);
+        assert_eq!(sound.load(ADDRESS_FREQUENCY_1), 0x01);
+        assert_eq!(sound.load(ADDRESS_FREQUENCY_2), 0xB8);
+        assert_eq!(sound.load(ADDRESS_DURATION_1), 0x03);
+        assert_eq!(sound.load(ADDRESS_DURATION_2), 0xE8);
+        assert_eq!(sound.load(ADDRESS_VOLUME), 0xFF);
+        sound.stop();
+    }
+
+    #[test]
+    fn test_wav_output() {
+        // 100ms of 441Hz (50 samples per period) at full volume, then a
+        // 10ms rest.
+        let wav = render(&[(441, 100, 255), (0, 10, 255)]);
+        const TONE_SAMPLES: usize = 2205;
+        const REST_SAMPLES: usize = 220;
+        const DATA_SIZE: usize = TONE_SAMPLES + REST_SAMPLES;
+
+        // Check the header.
+        assert_eq!(wav.len(), HEADER_SIZE as usize + DATA_SIZE);
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(wav[4..8], (36 + DATA_SIZE as u32).to_le_bytes());
+        assert_eq!(&wav[8..16], b"WAVEfmt ");
+        assert_eq!(wav[24..28], SAMPLE_RATE.to_le_bytes());
+        assert_eq!(&wav[36..40], b"data");
+        assert_eq!(wav[40..44], (DATA_SIZE as u32).to_le_bytes());
+
+        // Check the samples.
+        let data = &wav[44..];
+        assert!(data[0..25].iter().all(|&s| s == 0xFF));
+        assert!(data[25..50].iter().all(|&s| s == 0x01));
+        assert!(data[50..75].iter().all(|&s| s == 0xFF));
+        assert!(data[TONE_SAMPLES..].iter().all(|&s| s == SILENCE));
+    }
+
+    #[test]
+    fn test_size_limit() {
+        init_test_logging();
+
+        let file = tempfile::tempfile().unwrap();
+        let mut output = SoundOutput {
+            file: Some(file.try_clone().unwrap()),
+            data_size: u32::MAX - 100,
+        };
+        // 10ms of samples would take the RIFF size past 4GiB.
+        output.play(441, 10, 255);
+        assert!(output.file.is_none());
+        assert_eq!(output.data_size, u32::MAX - 100);
+        assert_eq!(file.metadata().unwrap().len(), 0);
+    }
+}
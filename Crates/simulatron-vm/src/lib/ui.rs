@@ -1,11 +1,12 @@
 use crossterm::{
     cursor,
-    event::{self, Event, KeyCode, KeyModifiers},
+    event::{self, Event, KeyCode, KeyModifiers, MouseButton, MouseEvent, MouseEventKind},
     queue,
     style::{self, Color},
     terminal, QueueableCommand,
 };
 use log::info;
+use simulatron_utils::charset::u8_to_printable_char;
 use std::io::{self, Stdout, Write};
 use std::sync::{
     atomic::{AtomicBool, Ordering},
@@ -14,7 +15,12 @@ use std::sync::{
 };
 use std::thread;
 
-use crate::keyboard::KeyMessage;
+use crate::display::{rgb, Cursor, CursorStyle, DisplayMemory, DisplayMode, SharedDisplayMemory};
+use crate::keyboard::{
+    KeyMessage, MODIFIER_ALT, MODIFIER_CTRL, MODIFIER_HYPER, MODIFIER_META, MODIFIER_SHIFT,
+    MODIFIER_SUPER,
+};
+use crate::mouse::{MouseMessage, MOUSE_BUTTON_LEFT, MOUSE_BUTTON_MIDDLE, MOUSE_BUTTON_RIGHT};
 
 // UI Constants.
 const TITLE: &str =
@@ -26,34 +32,18 @@ const EMPTY_ROW: &str =
     "                                                                                ";
 const BOTTOM_BORDER: &str =
     "┗━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━┛";
+const UPPER_HALF_BLOCK: char = '▀';
 
 // Simulatron constants.
 const ROWS: u16 = 25;
 const COLS: u16 = 80;
-const BUF_LEN: usize = ROWS as usize * COLS as usize;
 
 /// Commands that get sent to the UI listener thread.
 #[derive(Debug, PartialEq, Eq)]
 pub enum UICommand {
-    SetChar {
-        row: u16,
-        col: u16,
-        character: char,
-    },
-    SetFg {
-        row: u16,
-        col: u16,
-        r: u8,
-        g: u8,
-        b: u8,
-    },
-    SetBg {
-        row: u16,
-        col: u16,
-        r: u8,
-        g: u8,
-        b: u8,
-    },
+    RedrawCell { row: u16, col: u16 },
+    RedrawAll,
+    CPUFatalError { core: usize, reason: String },
     CPUHalted,
 }
 
@@ -62,9 +52,8 @@ pub struct UI {
     ui_tx: Option<Sender<UICommand>>,
     ui_rx: Receiver<UICommand>,
     keyboard_tx: Option<Sender<KeyMessage>>,
-    char_buf: Vec<char>,
-    fg_buf: Vec<Color>,
-    bg_buf: Vec<Color>,
+    mouse_tx: Option<Sender<MouseMessage>>,
+    display_memory: SharedDisplayMemory,
 }
 
 impl UI {
@@ -73,14 +62,15 @@ impl UI {
         ui_tx: Sender<UICommand>,
         ui_rx: Receiver<UICommand>,
         keyboard_tx: Sender<KeyMessage>,
+        mouse_tx: Sender<MouseMessage>,
+        display_memory: SharedDisplayMemory,
     ) -> Self {
         Self {
             ui_tx: Some(ui_tx),
             ui_rx,
             keyboard_tx: Some(keyboard_tx),
-            char_buf: vec![' '; BUF_LEN],
-            fg_buf: vec![Color::from((255, 255, 255)); BUF_LEN],
-            bg_buf: vec![Color::from((0, 0, 0)); BUF_LEN],
+            mouse_tx: Some(mouse_tx),
+            display_memory,
         }
     }
 
@@ -94,6 +84,7 @@ impl UI {
         queue!(
             stdout,
             terminal::EnterAlternateScreen,
+            event::EnableMouseCapture,
             style::SetForegroundColor(Color::White),
             style::SetBackgroundColor(Color::Black),
             terminal::Clear(terminal::ClearType::All),
@@ -117,77 +108,98 @@ impl UI {
         write!(stdout, "{}", BOTTOM_BORDER)?;
         stdout.flush()?;
 
-        // Launch the keyboard listener thread.
+        // Launch the keyboard and mouse listener thread.
         let join = Arc::new(AtomicBool::new(false));
         let join1 = join.clone();
         let ui_tx = self.ui_tx.take().unwrap();
         let keyboard_tx = self.keyboard_tx.take().unwrap();
+        let mouse_tx = self.mouse_tx.take().unwrap();
 
         let join_handle = thread::spawn(move || loop {
-            if let Event::Key(key) = event::read().unwrap() {
-                // Quit on Alt+Shift+Q.
-                if key.code == KeyCode::Char('Q')
-                    && key
-                        .modifiers
-                        .contains(KeyModifiers::union(KeyModifiers::ALT, KeyModifiers::SHIFT))
-                {
-                    ui_tx.send(UICommand::CPUHalted).unwrap();
-                } else {
-                    // Send the key to the keyboard controller.
-                    if let Some(k) = key_to_u8(key.code) {
-                        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
-                        let alt = key.modifiers.contains(KeyModifiers::ALT);
-                        let msg = KeyMessage::Key(k, ctrl, alt);
-                        keyboard_tx.send(msg).unwrap();
+            match event::read().unwrap() {
+                Event::Key(key) => {
+                    // Quit on Alt+Shift+Q.
+                    if key.code == KeyCode::Char('Q')
+                        && key
+                            .modifiers
+                            .contains(KeyModifiers::union(KeyModifiers::ALT, KeyModifiers::SHIFT))
+                    {
+                        ui_tx.send(UICommand::CPUHalted).unwrap();
+                    } else {
+                        // Send the key to the keyboard controller.
+                        if let Some(k) = key_to_u8(key.code) {
+                            let msg = KeyMessage::Key(k, modifiers_to_u8(key.modifiers));
+                            keyboard_tx.send(msg).unwrap();
+                        }
+                    }
+                    // Check if we should join the thread.
+                    if join1.load(Ordering::Relaxed) {
+                        return (ui_tx, keyboard_tx, mouse_tx);
                     }
                 }
-            }
-            // Check if we should join the thread.
-            if join1.load(Ordering::Relaxed) {
-                return (ui_tx, keyboard_tx);
+                Event::Mouse(mouse) => {
+                    // Send the event to the mouse controller.
+                    if let Some(msg) = mouse_to_message(mouse) {
+                        mouse_tx.send(msg).unwrap();
+                    }
+                }
+                _ => {}
             }
         });
 
         // Listen for UICommands.
         info!("UI online.");
         loop {
-            match self.ui_rx.recv().unwrap() {
-                UICommand::SetChar {
-                    row,
-                    col,
-                    character,
-                } => {
-                    let index = usize::from(row * COLS + col);
-                    self.char_buf[index] = character;
-                    self.redraw_char(&mut stdout, col, row)?;
+            let command = self.ui_rx.recv().unwrap();
+            let memory = self.display_memory.read().unwrap();
+            match command {
+                UICommand::RedrawCell { row, col } => {
+                    draw_cell(&mut stdout, &memory, col, row)?;
                 }
-                UICommand::SetFg { row, col, r, g, b } => {
-                    let index = usize::from(row * COLS + col);
-                    self.fg_buf[index] = Color::from((r, g, b));
-                    self.redraw_char(&mut stdout, col, row)?;
+                UICommand::RedrawAll => {
+                    for row in 0..ROWS {
+                        for col in 0..COLS {
+                            draw_cell(&mut stdout, &memory, col, row)?;
+                        }
+                    }
                 }
-                UICommand::SetBg { row, col, r, g, b } => {
-                    let index = usize::from(row * COLS + col);
-                    self.bg_buf[index] = Color::from((r, g, b));
-                    self.redraw_char(&mut stdout, col, row)?;
+                UICommand::CPUFatalError { core, reason } => {
+                    // Report why the core stopped below the display.
+                    queue!(
+                        stdout,
+                        cursor::MoveTo(0, ROWS + 4 + core as u16),
+                        style::SetForegroundColor(Color::White),
+                        style::SetBackgroundColor(Color::DarkRed),
+                    )?;
+                    write!(
+                        stdout,
+                        "CPU core {} halted due to a fatal error: {}.",
+                        core, reason
+                    )?;
+                    stdout.queue(style::SetBackgroundColor(Color::Black))?;
                 }
                 UICommand::CPUHalted => break,
             }
+            // Drawing moves the terminal cursor, so put it back afterwards.
+            place_cursor(&mut stdout, &memory)?;
+            stdout.flush()?;
         }
 
-        // Join the keyboard listener thread.
+        // Join the keyboard and mouse listener thread.
         join.store(true, Ordering::Relaxed);
         queue!(
             stdout,
+            cursor::Hide,
             cursor::MoveTo(20, ROWS + 3),
             style::SetForegroundColor(Color::White),
             style::SetBackgroundColor(Color::DarkRed),
         )?;
         write!(stdout, "Processor halted. Press any key to exit.")?;
         stdout.flush()?;
-        let (ui_tx, keyboard_tx) = join_handle.join().unwrap();
+        let (ui_tx, keyboard_tx, mouse_tx) = join_handle.join().unwrap();
         self.ui_tx = Some(ui_tx);
         self.keyboard_tx = Some(keyboard_tx);
+        self.mouse_tx = Some(mouse_tx);
 
         // Cleanup.
         queue!(
@@ -195,6 +207,7 @@ impl UI {
             terminal::Clear(terminal::ClearType::All),
             style::ResetColor,
             cursor::Show,
+            event::DisableMouseCapture,
             terminal::LeaveAlternateScreen,
         )?;
         stdout.flush()?;
@@ -203,24 +216,105 @@ impl UI {
         info!("UI exited.");
         Ok(())
     }
+}
 
-    /// Redraw the given character.
-    fn redraw_char(&self, stdout: &mut Stdout, col: u16, row: u16) -> crossterm::Result<()> {
-        let index = usize::from(row * COLS + col);
-        let fg = self.fg_buf[index];
-        let bg = self.bg_buf[index];
-        let character = self.char_buf[index];
-        queue!(
+/// Draw the given cell from display memory according to the current
+/// display mode.
+fn draw_cell(
+    stdout: &mut Stdout,
+    memory: &DisplayMemory,
+    col: u16,
+    row: u16,
+) -> crossterm::Result<()> {
+    let (character, fg, bg) = match memory.mode() {
+        DisplayMode::Text => {
+            let (character, fg, bg) = memory.text_cell(row, col);
+            let character = u8_to_printable_char(character).unwrap_or(' ');
+            match memory.cursor() {
+                Some(Cursor {
+                    row: cursor_row,
+                    col: cursor_col,
+                    style: CursorStyle::Inverted,
+                }) if cursor_row == row && cursor_col == col => (character, bg, fg),
+                _ => (character, fg, bg),
+            }
+        }
+        DisplayMode::Graphics => {
+            // The upper pixel is drawn in the foreground colour, and the
+            // lower pixel shows through in the background colour.
+            let upper = memory.pixel(row * 2, col);
+            let lower = memory.pixel(row * 2 + 1, col);
+            (UPPER_HALF_BLOCK, upper, lower)
+        }
+    };
+    queue!(
+        stdout,
+        cursor::MoveTo(col + 1, row + 2), // Account for border.
+        style::SetForegroundColor(Color::from(rgb(fg))),
+        style::SetBackgroundColor(Color::from(rgb(bg))),
+    )?;
+    write!(stdout, "{}", character)
+}
+
+/// Show or hide the terminal cursor according to the hardware text cursor.
+/// The inverted style is drawn as part of the cell, so needs no terminal
+/// cursor.
+fn place_cursor(stdout: &mut Stdout, memory: &DisplayMemory) -> crossterm::Result<()> {
+    let shape = match memory.cursor() {
+        Some(Cursor { row, col, style }) => match style {
+            CursorStyle::Inverted => None,
+            CursorStyle::Underscore => Some((row, col, cursor::CursorShape::UnderScore)),
+            CursorStyle::Bar => Some((row, col, cursor::CursorShape::Line)),
+            CursorStyle::Block => Some((row, col, cursor::CursorShape::Block)),
+        },
+        None => None,
+    };
+    match shape {
+        Some((row, col, shape)) => queue!(
             stdout,
+            cursor::SetCursorShape(shape),
             cursor::MoveTo(col + 1, row + 2), // Account for border.
-            style::SetForegroundColor(fg),
-            style::SetBackgroundColor(bg),
-        )?;
-        write!(stdout, "{}", character)?;
-        stdout.flush()
+            cursor::Show,
+        ),
+        None => queue!(stdout, cursor::Hide),
     }
 }
 
+/// Try to convert a `MouseEvent` to a message for the mouse controller.
+/// Only events within the display area are converted.
+fn mouse_to_message(mouse: MouseEvent) -> Option<MouseMessage> {
+    // Account for border.
+    let row = mouse.row.checked_sub(2).filter(|&row| row < ROWS)? as u8;
+    let col = mouse.column.checked_sub(1).filter(|&col| col < COLS)? as u8;
+    let button_to_u8 = |button| match button {
+        MouseButton::Left => MOUSE_BUTTON_LEFT,
+        MouseButton::Right => MOUSE_BUTTON_RIGHT,
+        MouseButton::Middle => MOUSE_BUTTON_MIDDLE,
+    };
+    Some(match mouse.kind {
+        MouseEventKind::Down(button) => MouseMessage::Press(row, col, button_to_u8(button)),
+        MouseEventKind::Up(button) => MouseMessage::Release(row, col, button_to_u8(button)),
+        MouseEventKind::Drag(_) | MouseEventKind::Moved => MouseMessage::Move(row, col),
+        MouseEventKind::ScrollDown => MouseMessage::Scroll(row, col, 1),
+        MouseEventKind::ScrollUp => MouseMessage::Scroll(row, col, -1),
+    })
+}
+
+/// Convert `KeyModifiers` to the keyboard metadata representation.
+fn modifiers_to_u8(modifiers: KeyModifiers) -> u8 {
+    [
+        (KeyModifiers::CONTROL, MODIFIER_CTRL),
+        (KeyModifiers::ALT, MODIFIER_ALT),
+        (KeyModifiers::SHIFT, MODIFIER_SHIFT),
+        (KeyModifiers::SUPER, MODIFIER_SUPER),
+        (KeyModifiers::HYPER, MODIFIER_HYPER),
+        (KeyModifiers::META, MODIFIER_META),
+    ]
+    .into_iter()
+    .filter(|&(modifier, _)| modifiers.contains(modifier))
+    .fold(0, |acc, (_, bit)| acc | bit)
+}
+
 /// Try to convert a `KeyCode` to the Simulatron character set representation
 /// of that key.
 fn key_to_u8(key: KeyCode) -> Option<u8> {